@@ -1549,7 +1549,14 @@ fn substitute_macros(input: &str) -> String {
         ("[LOGOUT]", "      Logout"),
         ("[YANK]", "        Yank"),
         ("[OWNER]", "       Owner"),
+        ("[MERGING]", "     Merging"),
         ("[MIGRATING]", "   Migrating"),
+        ("[SKIPPING]", "    Skipping"),
+        ("[UPDATED]", "     Updated"),
+        ("[SCANNING]", "    Scanning"),
+        ("[UPGRADING]", "   Upgrading"),
+        ("[AFFECTED]", "    Affected"),
+        ("[STOPPED]", "     Stopped"),
     ];
     let mut result = input.to_owned();
     for &(pat, subst) in &macros {
@@ -336,6 +336,7 @@ pub struct Dependency {
     registry: Option<String>,
     package: Option<String>,
     optional: bool,
+    public: bool,
 }
 
 /// A file to be created in a package.
@@ -585,7 +586,7 @@ impl Package {
                     (true, Some("alternative")) => None,
                     _ => panic!("registry_dep currently only supports `alternative`"),
                 };
-                serde_json::json!({
+                let mut json = serde_json::json!({
                     "name": dep.name,
                     "req": dep.vers,
                     "features": dep.features,
@@ -595,7 +596,11 @@ impl Package {
                     "kind": dep.kind,
                     "registry": registry_url,
                     "package": dep.package,
-                })
+                });
+                if dep.public {
+                    json["public"] = serde_json::json!(true);
+                }
+                json
             })
             .collect::<Vec<_>>();
         let cksum = {
@@ -803,6 +808,7 @@ impl Dependency {
             features: Vec::new(),
             package: None,
             optional: false,
+            public: false,
             registry: None,
         }
     }
@@ -848,6 +854,12 @@ impl Dependency {
         self.optional = optional;
         self
     }
+
+    /// Changes this to a public dependency in the index.
+    pub fn public(&mut self, public: bool) -> &mut Self {
+        self.public = public;
+        self
+    }
 }
 
 fn split_index_features(mut features: FeatureMap) -> (FeatureMap, Option<FeatureMap>) {
@@ -186,6 +186,7 @@ pub fn resolve_with_config_raw(
         &HashSet::new(),
         Some(config),
         true,
+        false,
     );
 
     // The largest test in our suite takes less then 30 sec.
@@ -1,4 +1,5 @@
 use cargo::core::dependency::DepKind;
+use cargo::core::resolver::{RequirementReason, ResolveError};
 use cargo::core::Dependency;
 use cargo::util::Config;
 use cargo_util::is_ci;
@@ -602,6 +603,53 @@ fn test_resolving_minimum_version_with_transitive_deps() {
     assert!(!res.contains(&("util", "1.0.0").to_pkgid()));
 }
 
+#[test]
+fn resolving_with_direct_minimal_versions() {
+    let reg = registry(vec![
+        pkg!(("foo", "1.0.0") => [dep_req("util", "1.0")]),
+        pkg!(("foo", "1.1.0") => [dep_req("util", "1.0")]),
+        pkg!(("util", "1.0.0")),
+        pkg!(("util", "1.1.0")),
+        pkg!(("bar", "1.0.0") => [dep_req("util", ">=1.0.1")]),
+    ]);
+
+    let mut config = Config::default().unwrap();
+    // -Z direct-minimal-versions
+    // Only the direct dependencies of the root are resolved to their minimal
+    // versions, while "util" is resolved to its maximal version.
+    config.nightly_features_allowed = true;
+    config
+        .configure(
+            1,
+            false,
+            None,
+            false,
+            false,
+            false,
+            &None,
+            &["direct-minimal-versions".to_string()],
+            &[],
+        )
+        .unwrap();
+
+    let res = resolve_with_config(
+        vec![dep_req("foo", "1.0.0"), dep_req("bar", "1.0.0")],
+        &reg,
+        &config,
+    )
+    .unwrap();
+
+    assert_same(
+        &res,
+        &names(&[
+            ("root", "1.0.0"),
+            ("foo", "1.0.0"),
+            ("bar", "1.0.0"),
+            ("util", "1.1.0"),
+        ]),
+    );
+}
+
 #[test]
 fn resolving_incompat_versions() {
     let reg = registry(vec![
@@ -613,6 +661,55 @@ fn resolving_incompat_versions() {
     assert!(resolve(vec![dep_req("foo", "=1.0.1"), dep("bar")], &reg).is_err());
 }
 
+#[test]
+fn resolving_incompat_versions_explained() {
+    let reg = registry(vec![
+        pkg!(("foo", "1.0.1")),
+        pkg!(("foo", "1.0.2")),
+        pkg!("bar" => [dep_req("foo", "=1.0.2")]),
+    ]);
+
+    let mut config = Config::default().unwrap();
+    // -Z resolve-explain
+    // Both versions of "foo" are semver compatible, so the explanation lists
+    // the failing requirement and the one which selected the other version.
+    config.nightly_features_allowed = true;
+    config
+        .configure(
+            1,
+            false,
+            None,
+            false,
+            false,
+            false,
+            &None,
+            &["resolve-explain".to_string()],
+            &[],
+        )
+        .unwrap();
+
+    let err =
+        resolve_with_config(vec![dep_req("foo", "=1.0.1"), dep("bar")], &reg, &config).unwrap_err();
+    let explanation = err
+        .downcast_ref::<ResolveError>()
+        .and_then(|e| e.explanation())
+        .expect("the conflict should be explained");
+    assert_eq!(explanation.package, "foo");
+    assert_eq!(explanation.candidates.len(), 1);
+    assert_eq!(explanation.requirements.len(), 2);
+    assert_eq!(
+        explanation.requirements[0].reason,
+        RequirementReason::Failed
+    );
+    assert_eq!(explanation.requirements[0].selected, None);
+    assert_eq!(
+        explanation.requirements[1].reason,
+        RequirementReason::Semver
+    );
+    assert!(explanation.requirements[1].selected.is_some());
+    assert!(err.to_string().contains("conflicting requirements:"));
+}
+
 #[test]
 fn resolving_wrong_case_from_registry() {
     // In the future we may #5678 allow this to happen.
@@ -131,6 +131,39 @@ Run with 'cargo -Z [FLAG] [SUBCOMMAND]'",
     config_configure(config, &expanded_args, subcommand_args, global_args)?;
     super::init_git_transports(config);
 
+    if config.cli_unstable().daemon && runs_in_daemon(cmd, subcommand_args) {
+        let args = std::env::args_os()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        match cargo::util::daemon::run_in_daemon(config, args)? {
+            Some(0) => return Ok(()),
+            Some(code) => return Err(CliError::code(code)),
+            None => {}
+        }
+    }
+
+    execute_subcommand(config, cmd, subcommand_args)
+}
+
+/// Whether `cmd` can be run by `cargo daemon`, which only writes to stderr.
+fn runs_in_daemon(cmd: &str, subcommand_args: &ArgMatches<'_>) -> bool {
+    (cmd == "build" || cmd == "check")
+        && !subcommand_args.is_present("message-format")
+        && !subcommand_args.is_present("unit-graph")
+        && !subcommand_args.is_present("build-plan")
+        && !subcommand_args.is_present("watch")
+}
+
+/// Runs a command sent to `cargo daemon`, with the arguments of the cargo
+/// process which sent it.
+pub fn run_in_daemon(config: &mut Config, args: Vec<String>) -> CliResult {
+    let args = cli().get_matches_from_safe(args)?;
+    let (expanded_args, global_args) = expand_aliases(config, args)?;
+    let (cmd, subcommand_args) = match expanded_args.subcommand() {
+        (cmd, Some(args)) => (cmd, args),
+        _ => return Ok(()),
+    };
+    config_configure(config, &expanded_args, subcommand_args, global_args)?;
     execute_subcommand(config, cmd, subcommand_args)
 }
 
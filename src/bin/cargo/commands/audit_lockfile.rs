@@ -0,0 +1,33 @@
+use crate::command_prelude::*;
+
+use anyhow::format_err;
+use cargo::ops;
+
+pub fn cli() -> App {
+    subcommand("audit-lockfile")
+        .about("Check Cargo.lock against a local security advisory database (unstable)")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg(
+            opt(
+                "db",
+                "Path to the advisory database (defaults to `audit.db` config)",
+            )
+            .value_name("PATH"),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    if !config.cli_unstable().audit {
+        return Err(format_err!(
+            "the `cargo audit-lockfile` command is unstable, pass `-Z audit` to enable it"
+        )
+        .into());
+    }
+    let ws = args.workspace(config)?;
+    let opts = ops::AuditOptions {
+        db: args.value_of_path("db", config),
+    };
+    ops::audit(&ws, &opts)?;
+    Ok(())
+}
@@ -64,6 +64,10 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let ops = TestOptions {
         no_run: args.is_present("no-run"),
         no_fail_fast: args.is_present("no-fail-fast"),
+        parallel: false,
+        report: Default::default(),
+        shard: None,
+        coverage: None,
         compile_opts,
     };
 
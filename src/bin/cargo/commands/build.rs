@@ -1,6 +1,6 @@
 use crate::command_prelude::*;
 
-use cargo::ops;
+use cargo::ops::{self, WatchRun};
 
 pub fn cli() -> App {
     subcommand("build")
@@ -44,10 +44,16 @@ pub fn cli() -> App {
         .arg_build_plan()
         .arg_unit_graph()
         .arg_future_incompat_report()
+        .arg_affected()
+        .arg_watch()
         .after_help("Run `cargo help build` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    args.watch(config, |watch| build(config, args, watch))
+}
+
+fn build(config: &Config, args: &ArgMatches<'_>, watch: Option<&mut WatchRun>) -> CliResult {
     let ws = args.workspace(config)?;
     let mut compile_opts = args.compile_options(
         config,
@@ -55,6 +61,9 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         Some(&ws),
         ProfileChecking::Checked,
     )?;
+    if !args.select_affected(config, &ws, &mut compile_opts)? {
+        return Ok(());
+    }
 
     if let Some(out_dir) = args.value_of_path("out-dir", config) {
         compile_opts.build_config.export_dir = Some(out_dir);
@@ -68,5 +77,8 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
             .fail_if_stable_opt("--out-dir", 6790)?;
     }
     ops::compile(&ws, &compile_opts)?;
+    if let Some(watch) = watch {
+        watch.files = compile_opts.build_config.watched_files.take();
+    }
     Ok(())
 }
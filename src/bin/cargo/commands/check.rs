@@ -1,6 +1,6 @@
 use crate::command_prelude::*;
 
-use cargo::ops;
+use cargo::ops::{self, WatchRun};
 
 pub fn cli() -> App {
     subcommand("check")
@@ -36,10 +36,16 @@ pub fn cli() -> App {
         .arg_message_format()
         .arg_unit_graph()
         .arg_future_incompat_report()
+        .arg_affected()
+        .arg_watch()
         .after_help("Run `cargo help check` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    args.watch(config, |watch| check(config, args, watch))
+}
+
+fn check(config: &Config, args: &ArgMatches<'_>, watch: Option<&mut WatchRun>) -> CliResult {
     let ws = args.workspace(config)?;
     let test = match args.value_of("profile") {
         Some("test") => true,
@@ -54,8 +60,15 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         }
     };
     let mode = CompileMode::Check { test };
-    let compile_opts = args.compile_options(config, mode, Some(&ws), ProfileChecking::Unchecked)?;
+    let mut compile_opts =
+        args.compile_options(config, mode, Some(&ws), ProfileChecking::Unchecked)?;
+    if !args.select_affected(config, &ws, &mut compile_opts)? {
+        return Ok(());
+    }
 
     ops::compile(&ws, &compile_opts)?;
+    if let Some(watch) = watch {
+        watch.files = compile_opts.build_config.watched_files.take();
+    }
     Ok(())
 }
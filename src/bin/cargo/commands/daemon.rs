@@ -0,0 +1,56 @@
+use crate::command_prelude::*;
+
+use std::rc::Rc;
+
+use anyhow::format_err;
+use cargo::util::daemon;
+use cargo::util::{DaemonCache, DaemonServer};
+
+pub fn cli() -> App {
+    subcommand("daemon")
+        .about("Keep manifests loaded between `cargo build` and `cargo check` commands (unstable)")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg(opt("stop", "Stop the running daemon"))
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    if !config.cli_unstable().daemon {
+        return Err(format_err!(
+            "the `cargo daemon` command is unstable, pass `-Z daemon` to enable it"
+        )
+        .into());
+    }
+    if args.is_present("stop") {
+        if daemon::stop_daemon(config)? {
+            config.shell().status("Stopped", "the cargo daemon")?;
+        } else {
+            config.shell().warn("no cargo daemon is running")?;
+        }
+        return Ok(());
+    }
+
+    let server = DaemonServer::new(config)?;
+    config.shell().status(
+        "Listening",
+        format!(
+            "on {} for commands run with `-Z daemon`, stop with `cargo daemon --stop`",
+            server.addr()
+        ),
+    )?;
+    let cache = Rc::new(DaemonCache::new());
+    let homedir = config.home().as_path_unlocked().to_path_buf();
+    server.run(config, |request, shell| {
+        let mut request_config = Config::new(shell, request.cwd, homedir.clone());
+        request_config.set_daemon_cache(Rc::clone(&cache));
+        match crate::cli::run_in_daemon(&mut request_config, request.args) {
+            Ok(()) => 0,
+            Err(CliError { error, exit_code }) => {
+                if let Some(error) = error {
+                    cargo::display_error(&error, &mut request_config.shell());
+                }
+                exit_code
+            }
+        }
+    })?;
+    Ok(())
+}
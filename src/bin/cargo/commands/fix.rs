@@ -41,6 +41,15 @@ pub fn cli() -> App {
                 .long("edition")
                 .help("Fix in preparation for the next edition"),
         )
+        .arg(
+            Arg::with_name("migrate-workspace")
+                .long("migrate-workspace")
+                .requires("edition")
+                .help(
+                    "Migrate workspace members to the next edition one at a time, \
+                     in dependency order (unstable)",
+                ),
+        )
         .arg(
             Arg::with_name("idioms")
                 .long("edition-idioms")
@@ -81,6 +90,14 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     };
     let mode = CompileMode::Check { test };
 
+    let migrate_workspace = args.is_present("migrate-workspace");
+    if migrate_workspace && !config.cli_unstable().edition_migration {
+        return Err(anyhow::format_err!(
+            "Usage of `--migrate-workspace` requires `-Z edition-migration`"
+        )
+        .into());
+    }
+
     // Unlike other commands default `cargo fix` to all targets to fix as much
     // code as we can.
     let mut opts = args.compile_options(config, mode, Some(&ws), ProfileChecking::Unchecked)?;
@@ -106,6 +123,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
             allow_no_vcs: args.is_present("allow-no-vcs"),
             allow_staged: args.is_present("allow-staged"),
             broken_code: args.is_present("broken-code"),
+            migrate_workspace,
         },
     )?;
     Ok(())
@@ -0,0 +1,60 @@
+use crate::command_prelude::*;
+
+use anyhow::format_err;
+use cargo::ops;
+use cargo_util::paths;
+
+pub fn cli() -> App {
+    subcommand("merge-lockfile")
+        .about("Merge two versions of Cargo.lock, as a git merge driver (unstable)")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg(
+            Arg::with_name("base")
+                .value_name("BASE")
+                .required(true)
+                .help("The common ancestor version (unused)"),
+        )
+        .arg(
+            Arg::with_name("ours")
+                .value_name("OURS")
+                .required(true)
+                .help("Our version, which is replaced with the merged version"),
+        )
+        .arg(
+            Arg::with_name("theirs")
+                .value_name("THEIRS")
+                .required(true)
+                .help("Their version"),
+        )
+        .arg_manifest_path()
+        .after_help(
+            "\
+To use it as the merge driver of Cargo.lock, add to .git/config:
+
+    [merge \"cargo-lock\"]
+        name = Cargo.lock merge driver
+        driver = cargo -Z lockfile-merge merge-lockfile %O %A %B
+
+and to .gitattributes:
+
+    Cargo.lock merge=cargo-lock
+",
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    if !config.cli_unstable().lockfile_merge {
+        return Err(format_err!(
+            "the `cargo merge-lockfile` command is unstable, pass `-Z lockfile-merge` to enable it"
+        )
+        .into());
+    }
+    let ws = args.workspace(config)?;
+    let ours_path = args.value_of_path("ours", config).unwrap();
+    let theirs_path = args.value_of_path("theirs", config).unwrap();
+    let ours = paths::read(&ours_path)?;
+    let theirs = paths::read(&theirs_path)?;
+    let merged = ops::merge_lockfiles(&ws, &ours, &theirs)?;
+    paths::write(&ours_path, merged)?;
+    Ok(())
+}
@@ -2,11 +2,13 @@ use crate::command_prelude::*;
 
 pub fn builtin() -> Vec<App> {
     vec![
+        audit_lockfile::cli(),
         bench::cli(),
         build::cli(),
         check::cli(),
         clean::cli(),
         config::cli(),
+        daemon::cli(),
         doc::cli(),
         fetch::cli(),
         fix::cli(),
@@ -17,11 +19,14 @@ pub fn builtin() -> Vec<App> {
         locate_project::cli(),
         login::cli(),
         logout::cli(),
+        merge_lockfile::cli(),
         metadata::cli(),
         new::cli(),
+        outdated_deps::cli(),
         owner::cli(),
         package::cli(),
         pkgid::cli(),
+        policy::cli(),
         publish::cli(),
         read_manifest::cli(),
         report::cli(),
@@ -42,11 +47,13 @@ pub fn builtin() -> Vec<App> {
 
 pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches<'_>) -> CliResult> {
     let f = match cmd {
+        "audit-lockfile" => audit_lockfile::exec,
         "bench" => bench::exec,
         "build" => build::exec,
         "check" => check::exec,
         "clean" => clean::exec,
         "config" => config::exec,
+        "daemon" => daemon::exec,
         "doc" => doc::exec,
         "fetch" => fetch::exec,
         "fix" => fix::exec,
@@ -57,11 +64,14 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches<'_>) -> Cli
         "locate-project" => locate_project::exec,
         "login" => login::exec,
         "logout" => logout::exec,
+        "merge-lockfile" => merge_lockfile::exec,
         "metadata" => metadata::exec,
         "new" => new::exec,
+        "outdated-deps" => outdated_deps::exec,
         "owner" => owner::exec,
         "package" => package::exec,
         "pkgid" => pkgid::exec,
+        "policy" => policy::exec,
         "publish" => publish::exec,
         "read-manifest" => read_manifest::exec,
         "report" => report::exec,
@@ -82,11 +92,13 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches<'_>) -> Cli
     Some(f)
 }
 
+pub mod audit_lockfile;
 pub mod bench;
 pub mod build;
 pub mod check;
 pub mod clean;
 pub mod config;
+pub mod daemon;
 pub mod doc;
 pub mod fetch;
 pub mod fix;
@@ -98,11 +110,14 @@ pub mod install;
 pub mod locate_project;
 pub mod login;
 pub mod logout;
+pub mod merge_lockfile;
 pub mod metadata;
 pub mod new;
+pub mod outdated_deps;
 pub mod owner;
 pub mod package;
 pub mod pkgid;
+pub mod policy;
 pub mod publish;
 pub mod read_manifest;
 pub mod report;
@@ -0,0 +1,37 @@
+use crate::command_prelude::*;
+
+use anyhow::format_err;
+use cargo::ops::{self, OutdatedOptions};
+
+pub fn cli() -> App {
+    subcommand("outdated-deps")
+        .about("Show dependencies with newer versions available (unstable)")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg(
+            opt("output-format", "Output format: text, json")
+                .value_name("FMT")
+                .possible_values(&["text", "json"])
+                .default_value("text"),
+        )
+        .arg(opt(
+            "upgrade",
+            "Rewrite version requirements in Cargo.toml to the latest versions",
+        ))
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    if !config.cli_unstable().outdated {
+        return Err(format_err!(
+            "the `cargo outdated-deps` command is unstable, pass `-Z outdated` to enable it"
+        )
+        .into());
+    }
+    let ws = args.workspace(config)?;
+    let opts = OutdatedOptions {
+        json: args.value_of("output-format") == Some("json"),
+        upgrade: args.is_present("upgrade"),
+    };
+    ops::outdated(&ws, &opts)?;
+    Ok(())
+}
@@ -17,6 +17,10 @@ pub fn cli() -> App {
             "no-verify",
             "Don't verify the contents by building them",
         ))
+        .arg(opt(
+            "minimal-versions",
+            "Verify with the minimal versions of direct dependencies (unstable)",
+        ))
         .arg(opt(
             "no-metadata",
             "Ignore warnings about a lack of human-usable metadata",
@@ -35,11 +39,18 @@ pub fn cli() -> App {
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     let ws = args.workspace(config)?;
+    let minimal_versions = args.is_present("minimal-versions");
+    if minimal_versions {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--minimal-versions", 5657)?;
+    }
     ops::package(
         &ws,
         &PackageOpts {
             config,
             verify: !args.is_present("no-verify"),
+            minimal_versions,
             list: args.is_present("list"),
             check_metadata: !args.is_present("no-metadata"),
             allow_dirty: args.is_present("allow-dirty"),
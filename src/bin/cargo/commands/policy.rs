@@ -0,0 +1,33 @@
+use crate::command_prelude::*;
+
+use anyhow::format_err;
+use cargo::ops::{self, tree};
+use std::str::FromStr;
+
+pub fn cli() -> App {
+    subcommand("policy")
+        .about("Check dependencies against the `[policy]` config table (unstable)")
+        .arg(opt("quiet", "No output printed to stdout").short("q"))
+        .arg(
+            opt("charset", "Character set to use in output: utf8, ascii")
+                .value_name("CHARSET")
+                .possible_values(&["utf8", "ascii"])
+                .default_value("utf8"),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    if !config.cli_unstable().dependency_policy {
+        return Err(format_err!(
+            "the `cargo policy` command is unstable, pass `-Z dependency-policy` to enable it"
+        )
+        .into());
+    }
+    let ws = args.workspace(config)?;
+    let charset = tree::Charset::from_str(args.value_of("charset").unwrap())
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let opts = ops::policy::PolicyOptions { charset };
+    ops::policy::check_policy(&ws, &opts)?;
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use crate::command_prelude::*;
 use crate::util::restricted_names::is_glob_pattern;
+use anyhow::Context as _;
 use cargo::core::Verbosity;
-use cargo::ops::{self, CompileFilter, Packages};
+use cargo::ops::{self, CompileFilter, Packages, WatchRun};
 use cargo_util::ProcessError;
 
 pub fn cli() -> App {
@@ -27,10 +28,15 @@ pub fn cli() -> App {
         .arg_message_format()
         .arg_unit_graph()
         .arg_ignore_rust_version()
+        .arg_watch()
         .after_help("Run `cargo help run` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    args.watch(config, |watch| run(config, args, watch))
+}
+
+fn run(config: &Config, args: &ArgMatches<'_>, watch: Option<&mut WatchRun>) -> CliResult {
     let ws = args.workspace(config)?;
 
     let mut compile_opts = args.compile_options(
@@ -82,6 +88,19 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         }
     };
 
+    if let Some(watch) = watch {
+        // The program is restarted when the sources change, instead of
+        // replacing cargo.
+        let process = ops::run_process(&ws, &compile_opts, &values_os(args, "args"))?;
+        watch.files = compile_opts.build_config.watched_files.take();
+        let child = process
+            .build_command()
+            .spawn()
+            .with_context(|| format!("could not execute process {}", process))?;
+        watch.process = Some(child);
+        return Ok(());
+    }
+
     ops::run(&ws, &compile_opts, &values_os(args, "args")).map_err(|err| {
         let proc_err = match err.downcast_ref::<ProcessError>() {
             Some(e) => e,
@@ -1,6 +1,6 @@
 use crate::command_prelude::*;
 use anyhow::Error;
-use cargo::ops::{self, CompileFilter, FilterRule, LibRule};
+use cargo::ops::{self, CompileFilter, FilterRule, LibRule, WatchRun};
 
 pub fn cli() -> App {
     subcommand("test")
@@ -40,6 +40,47 @@ pub fn cli() -> App {
         .arg(opt("doc", "Test only this library's documentation"))
         .arg(opt("no-run", "Compile, but don't run tests"))
         .arg(opt("no-fail-fast", "Run all tests regardless of failure"))
+        .arg(
+            opt(
+                "report-junit",
+                "Write a JUnit XML report of the test results to this file (unstable)",
+            )
+            .value_name("PATH"),
+        )
+        .arg(
+            opt(
+                "report-json",
+                "Write a JSON report of the test results to this file (unstable)",
+            )
+            .value_name("PATH"),
+        )
+        .arg(
+            opt(
+                "shard",
+                "Run only the N-th of M shards of the test suites (unstable)",
+            )
+            .value_name("N/M"),
+        )
+        .arg(
+            opt(
+                "shard-durations",
+                "Balance the shards with the durations of a JSON test report (unstable)",
+            )
+            .value_name("PATH")
+            .requires("shard"),
+        )
+        .arg(opt(
+            "coverage",
+            "Measure the code coverage of the tests, in target/coverage (unstable)",
+        ))
+        .arg(
+            opt(
+                "coverage-format",
+                "Format of the coverage report, `lcov` or `json` (unstable)",
+            )
+            .value_name("FMT")
+            .requires("coverage"),
+        )
         .arg_package_spec(
             "Package to run tests for",
             "Test all packages in the workspace",
@@ -56,10 +97,16 @@ pub fn cli() -> App {
         .arg_message_format()
         .arg_unit_graph()
         .arg_future_incompat_report()
+        .arg_affected()
+        .arg_watch()
         .after_help("Run `cargo help test` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
+    args.watch(config, |watch| test(config, args, watch))
+}
+
+fn test(config: &Config, args: &ArgMatches<'_>, watch: Option<&mut WatchRun>) -> CliResult {
     let ws = args.workspace(config)?;
 
     let mut compile_opts = args.compile_options(
@@ -68,6 +115,9 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         Some(&ws),
         ProfileChecking::Checked,
     )?;
+    if !args.select_affected(config, &ws, &mut compile_opts)? {
+        return Ok(());
+    }
 
     compile_opts.build_config.requested_profile =
         args.get_profile_name(config, "test", ProfileChecking::Checked)?;
@@ -88,7 +138,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
                 101,
             ));
         }
-        if no_run {
+        if no_run && !config.cli_unstable().doctest_xcompile {
             return Err(CliError::new(
                 anyhow::format_err!("Can't skip running doc tests with --no-run"),
                 101,
@@ -114,13 +164,55 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         }
     }
 
+    let report = ops::TestReportOptions {
+        junit: args.value_of_path("report-junit", config),
+        json: args.value_of_path("report-json", config),
+    };
+    if report.is_enabled() && !config.cli_unstable().test_report {
+        return Err(anyhow::format_err!(
+            "Usage of `--report-junit` and `--report-json` requires `-Z test-report`"
+        )
+        .into());
+    }
+
+    let shard = match args.value_of("shard") {
+        Some(shard) => {
+            if !config.cli_unstable().test_shard {
+                return Err(
+                    anyhow::format_err!("Usage of `--shard` requires `-Z test-shard`").into(),
+                );
+            }
+            let durations = args.value_of_path("shard-durations", config);
+            Some(ops::TestShard::parse(shard, durations)?)
+        }
+        None => None,
+    };
+
+    let coverage = if args.is_present("coverage") {
+        if !config.cli_unstable().coverage {
+            return Err(anyhow::format_err!("Usage of `--coverage` requires `-Z coverage`").into());
+        }
+        compile_opts.build_config.coverage = true;
+        let format = args.value_of("coverage-format").unwrap_or("lcov");
+        Some(ops::CoverageFormat::parse(format)?)
+    } else {
+        None
+    };
+
     let ops = ops::TestOptions {
         no_run,
         no_fail_fast: args.is_present("no-fail-fast"),
+        parallel: config.cli_unstable().parallel_tests,
+        report,
+        shard,
+        coverage,
         compile_opts,
     };
 
     let err = ops::run_tests(&ws, &ops, &test_args)?;
+    if let Some(watch) = watch {
+        watch.files = ops.compile_opts.build_config.watched_files.take();
+    }
     match err {
         None => Ok(()),
         Some(err) => {
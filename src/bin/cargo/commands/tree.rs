@@ -56,6 +56,13 @@ pub fn cli() -> App {
             )
             .short("i"),
         )
+        .arg(
+            opt(
+                "why",
+                "Explain why the given package (or `SPEC/FEATURE`) is included (unstable)",
+            )
+            .value_name("SPEC"),
+        )
         .arg(multi_opt(
             "prune",
             "SPEC",
@@ -103,6 +110,15 @@ pub fn cli() -> App {
                 .short("f")
                 .default_value("{p}"),
         )
+        .arg(
+            opt(
+                "output-format",
+                "Output format of the dependency graph: text, dot, json (unstable)",
+            )
+            .value_name("FMT")
+            .possible_values(&["text", "dot", "json"])
+            .default_value("text"),
+        )
         .arg(
             // Backwards compatibility with old cargo-tree.
             Arg::with_name("version")
@@ -154,7 +170,25 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
     };
     let target = tree::Target::from_cli(targets);
 
-    let (edge_kinds, no_proc_macro) = parse_edge_kinds(config, args)?;
+    let (mut edge_kinds, no_proc_macro) = parse_edge_kinds(config, args)?;
+
+    let why = match args.value_of("why") {
+        Some(why) => {
+            if !config.cli_unstable().tree_why {
+                return Err(format_err!("Usage of `--why` requires `-Z tree-why`").into());
+            }
+            if args.is_present("invert") || args.is_present("duplicates") {
+                return Err(format_err!(
+                    "the `--why` flag cannot be used with `--invert` or `--duplicates`"
+                )
+                .into());
+            }
+            // The explanation is based on the feature graph.
+            edge_kinds.insert(EdgeKind::Feature);
+            Some(tree::WhyTarget::parse(why)?)
+        }
+        None => None,
+    };
     let graph_features = edge_kinds.contains(&EdgeKind::Feature);
 
     let pkgs_to_prune = args._values_of("prune");
@@ -198,6 +232,13 @@ subtree of the package given to -p.\n\
 
     let charset = tree::Charset::from_str(args.value_of("charset").unwrap())
         .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let output_format = tree::OutputFormat::from_str(args.value_of("output-format").unwrap())
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    if output_format != tree::OutputFormat::Text && !config.cli_unstable().tree_output_format {
+        return Err(
+            format_err!("Usage of `--output-format` requires `-Z tree-output-format`").into(),
+        );
+    }
     let opts = tree::TreeOptions {
         cli_features: args.cli_features()?,
         packages,
@@ -213,8 +254,14 @@ subtree of the package given to -p.\n\
         graph_features,
         max_display_depth: args.value_of_u32("depth")?.unwrap_or(u32::MAX),
         no_proc_macro,
+        output_format,
+        why,
     };
 
+    if opts.why.is_some() && opts.output_format != tree::OutputFormat::Text {
+        return Err(format_err!("the `--why` flag does not support `--output-format`").into());
+    }
+
     if opts.graph_features && opts.duplicates {
         return Err(format_err!("the `-e features` flag does not support `--duplicates`").into());
     }
@@ -21,6 +21,15 @@ pub fn cli() -> App {
             )
             .value_name("PRECISE"),
         )
+        .arg(
+            opt(
+                "output-format",
+                "Output format of the summary of changes: text, json (unstable)",
+            )
+            .value_name("FMT")
+            .possible_values(&["text", "json"])
+            .default_value("text"),
+        )
         .arg_manifest_path()
         .after_help("Run `cargo help update` for more detailed information.\n")
 }
@@ -32,12 +41,20 @@ pub fn exec(config: &mut Config, args: &ArgMatches<'_>) -> CliResult {
         print_available_packages(&ws)?;
     }
 
+    let json_report = args.value_of("output-format") == Some("json");
+    if json_report && !config.cli_unstable().update_report {
+        return Err(
+            anyhow::format_err!("Usage of `--output-format` requires `-Z update-report`").into(),
+        );
+    }
+
     let update_opts = UpdateOptions {
         aggressive: args.is_present("aggressive"),
         precise: args.value_of("precise"),
         to_update: values(args, "package"),
         dry_run: args.is_present("dry-run"),
         workspace: args.is_present("workspace"),
+        json_report,
         config,
     };
     ops::update_lockfile(&ws, &update_opts)?;
@@ -5,6 +5,7 @@ use anyhow::bail;
 use cargo_util::ProcessBuilder;
 use serde::ser;
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 
 /// Configuration information for a rustc build.
@@ -12,6 +13,9 @@ use std::path::PathBuf;
 pub struct BuildConfig {
     /// The requested kind of compilation for this session
     pub requested_kinds: Vec<CompileKind>,
+    /// Whether `requested_kinds` comes from the `build.target` config value
+    /// instead of `--target` flags.
+    pub requested_kinds_from_config: bool,
     /// Number of rustc jobs to run in parallel.
     pub jobs: u32,
     /// Build profile
@@ -39,6 +43,12 @@ pub struct BuildConfig {
     pub export_dir: Option<PathBuf>,
     /// `true` to output a future incompatibility report at the end of the build
     pub future_incompat_report: bool,
+    /// `true` to instrument the workspace members for code coverage. The
+    /// artifacts are kept in a separate `coverage` directory.
+    pub coverage: bool,
+    /// The files the build depends on, recorded when it is `Some`, for the
+    /// watch mode to rerun the build when they change.
+    pub watched_files: RefCell<Option<BTreeSet<PathBuf>>>,
 }
 
 impl BuildConfig {
@@ -72,6 +82,7 @@ impl BuildConfig {
 
         Ok(BuildConfig {
             requested_kinds,
+            requested_kinds_from_config: requested_targets.is_empty() && cfg.target.is_some(),
             jobs,
             requested_profile: InternedString::new("dev"),
             mode,
@@ -83,6 +94,8 @@ impl BuildConfig {
             rustfix_diagnostic_server: RefCell::new(None),
             export_dir: None,
             future_incompat_report: false,
+            coverage: false,
+            watched_files: RefCell::new(None),
         })
     }
 
@@ -99,6 +112,11 @@ impl BuildConfig {
     pub fn single_requested_kind(&self) -> CargoResult<CompileKind> {
         match self.requested_kinds.len() {
             1 => Ok(self.requested_kinds[0]),
+            _ if self.requested_kinds_from_config => bail!(
+                "only one target is supported, but the `build.target` config value \
+                 lists several targets\n\
+                 Pass `--target` to choose one of them."
+            ),
             _ => bail!("only one `--target` argument is supported"),
         }
     }
@@ -148,10 +148,8 @@ impl<'cfg> Compilation<'cfg> {
             rustc_workspace_wrapper_process,
             primary_rustc_process,
             target_runners: bcx
-                .build_config
-                .requested_kinds
+                .all_kinds
                 .iter()
-                .chain(Some(&CompileKind::Host))
                 .map(|kind| Ok((*kind, target_runner(bcx, *kind)?)))
                 .collect::<CargoResult<HashMap<_, _>>>()?,
         })
@@ -220,6 +218,12 @@ impl<'cfg> Compilation<'cfg> {
         )
     }
 
+    /// Returns the directory of the LLVM tools installed with the
+    /// `llvm-tools-preview` rustup component, like `llvm-cov`.
+    pub fn llvm_tools_dir(&self) -> PathBuf {
+        self.sysroot_host_libdir.with_file_name("bin")
+    }
+
     pub fn target_runner(&self, kind: CompileKind) -> Option<&(PathBuf, Vec<String>)> {
         self.target_runners.get(&kind).and_then(|x| x.as_ref())
     }
@@ -2,7 +2,7 @@ use crate::core::Target;
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
 use crate::util::{Config, StableHasher};
-use anyhow::{bail, Context as _};
+use anyhow::Context as _;
 use serde::Serialize;
 use std::collections::BTreeSet;
 use std::fs;
@@ -44,41 +44,35 @@ impl CompileKind {
     }
 
     /// Creates a new list of `CompileKind` based on the requested list of
-    /// targets.
+    /// targets, or on the `build.target` config value if there are none.
     ///
-    /// If no targets are given then this returns a single-element vector with
+    /// All the targets are built in the same build graph. If no targets are
+    /// given then this returns a single-element vector with
     /// `CompileKind::Host`.
     pub fn from_requested_targets(
         config: &Config,
         targets: &[String],
     ) -> CargoResult<Vec<CompileKind>> {
-        if targets.len() > 1 && !config.cli_unstable().multitarget {
-            bail!("specifying multiple `--target` flags requires `-Zmultitarget`")
-        }
-        if !targets.is_empty() {
-            return Ok(targets
-                .iter()
-                .map(|value| Ok(CompileKind::Target(CompileTarget::new(value)?)))
-                // First collect into a set to deduplicate any `--target` passed
-                // more than once...
-                .collect::<CargoResult<BTreeSet<_>>>()?
-                // ... then generate a flat list for everything else to use.
-                .into_iter()
-                .collect());
-        }
-        let kind = match &config.build_config()?.target {
-            Some(val) => {
-                let value = if val.raw_value().ends_with(".json") {
-                    let path = val.clone().resolve_path(config);
-                    path.to_str().expect("must be utf-8 in toml").to_string()
-                } else {
-                    val.raw_value().to_string()
-                };
-                CompileKind::Target(CompileTarget::new(&value)?)
+        let targets = if !targets.is_empty() {
+            targets.to_vec()
+        } else {
+            match &config.build_config()?.target {
+                Some(target) => target.values(config),
+                None => Vec::new(),
             }
-            None => CompileKind::Host,
         };
-        Ok(vec![kind])
+        if targets.is_empty() {
+            return Ok(vec![CompileKind::Host]);
+        }
+        Ok(targets
+            .iter()
+            .map(|value| Ok(CompileKind::Target(CompileTarget::new(value)?)))
+            // First collect into a set to deduplicate any target given more
+            // than once...
+            .collect::<CargoResult<BTreeSet<_>>>()?
+            // ... then generate a flat list for everything else to use.
+            .into_iter()
+            .collect())
     }
 
     /// Hash used for fingerprinting.
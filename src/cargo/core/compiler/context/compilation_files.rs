@@ -204,11 +204,6 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         }
     }
 
-    /// Additional export directory from `--out-dir`.
-    pub fn export_dir(&self) -> Option<PathBuf> {
-        self.export_dir.clone()
-    }
-
     /// Directory name to use for a package in the form `NAME-HASH`.
     ///
     /// Note that some units may share the same directory, so care should be
@@ -472,6 +467,14 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
                 None
             } else {
                 self.export_dir.as_ref().and_then(|export_dir| {
+                    // With several `--target`, each target gets its own
+                    // directory, as their artifacts have the same names.
+                    let export_dir = match unit.kind {
+                        CompileKind::Target(_) if bcx.build_config.requested_kinds.len() > 1 => {
+                            export_dir.join(triple)
+                        }
+                        _ => export_dir.clone(),
+                    };
                     hardlink
                         .as_ref()
                         .map(|hardlink| export_dir.join(hardlink.file_name().unwrap()))
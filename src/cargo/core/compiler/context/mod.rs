@@ -14,7 +14,7 @@ use crate::util::profile;
 
 use super::build_plan::BuildPlan;
 use super::custom_build::{self, BuildDeps, BuildScriptOutputs, BuildScripts};
-use super::fingerprint::Fingerprint;
+use super::fingerprint::{self, Fingerprint};
 use super::job_queue::JobQueue;
 use super::layout::Layout;
 use super::lto::Lto;
@@ -277,6 +277,17 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 self.compilation.native_dirs.insert(dir.clone());
             }
         }
+
+        if let Some(files) = &mut *self.bcx.build_config.watched_files.borrow_mut() {
+            // Files generated in the target directory change with the build
+            // itself.
+            let target_dir = self.bcx.ws.target_dir().into_path_unlocked();
+            for unit in self.bcx.unit_graph.keys().filter(|unit| unit.is_local()) {
+                files.insert(unit.pkg.manifest_path().to_path_buf());
+                let watched = fingerprint::watched_files(&self, unit)?;
+                files.extend(watched.into_iter().filter(|f| !f.starts_with(&target_dir)));
+            }
+        }
         Ok(self.compilation)
     }
 
@@ -298,7 +309,12 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     }
 
     pub fn prepare_units(&mut self) -> CargoResult<()> {
-        let dest = self.bcx.profiles.get_dir_name();
+        let mut dest = self.bcx.profiles.get_dir_name().to_string();
+        if self.bcx.build_config.coverage {
+            // Keeps the instrumented artifacts apart from the usual ones, so
+            // that they don't rebuild each other.
+            dest = format!("coverage/{}", dest);
+        }
         let host_layout = Layout::new(self.bcx.ws, None, &dest)?;
         let mut targets = HashMap::new();
         for kind in self.bcx.all_kinds.iter() {
@@ -331,6 +331,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::core::compiler::unit_graph::UnitDep;
 use crate::core::Package;
+use crate::sources::PathSource;
 use crate::util;
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
@@ -1606,6 +1607,40 @@ pub fn dep_info_loc(cx: &mut Context<'_, '_>, unit: &Unit) -> PathBuf {
     cx.files().fingerprint_file_path(unit, "dep-")
 }
 
+/// Returns the files which the up-to-date check of `unit` looks at, for the
+/// watch mode: the files listed in its dep-info, or the `rerun-if-changed`
+/// paths of a build script. A build script without `rerun-if-changed`
+/// depends on all the files of a local package.
+pub fn watched_files(cx: &Context<'_, '_>, unit: &Unit) -> CargoResult<Vec<PathBuf>> {
+    let fingerprint = match cx.fingerprints.get(unit) {
+        Some(fingerprint) => fingerprint,
+        None => return Ok(Vec::new()),
+    };
+    let pkg_root = unit.pkg.root();
+    let target_root = target_root(cx);
+    let mut files = Vec::new();
+    for local in fingerprint.local.lock().unwrap().iter() {
+        match local {
+            LocalFingerprint::CheckDepInfo { dep_info } => {
+                let dep_info = target_root.join(dep_info);
+                if let Some(info) = parse_dep_info(pkg_root, &target_root, &dep_info)? {
+                    files.extend(info.files);
+                }
+            }
+            LocalFingerprint::RerunIfChanged { paths, .. } => {
+                files.extend(paths.iter().map(|path| pkg_root.join(path)));
+            }
+            LocalFingerprint::Precalculated(..) if unit.is_local() => {
+                let source_id = unit.pkg.package_id().source_id();
+                let source = PathSource::new(pkg_root, source_id, cx.bcx.config);
+                files.extend(source.list_files(&unit.pkg)?);
+            }
+            LocalFingerprint::Precalculated(..) | LocalFingerprint::RerunIfEnvChanged { .. } => {}
+        }
+    }
+    Ok(files)
+}
+
 /// Returns an absolute path that target directory.
 /// All paths are rewritten to be relative to this.
 fn target_root(cx: &Context<'_, '_>) -> PathBuf {
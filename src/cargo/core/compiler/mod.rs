@@ -54,6 +54,7 @@ pub use crate::core::compiler::unit::{Unit, UnitInterner};
 use crate::core::manifest::TargetSourcePath;
 use crate::core::profiles::{PanicStrategy, Profile, Strip};
 use crate::core::{Feature, PackageId, Target};
+use crate::ops;
 use crate::util::errors::{CargoResult, VerboseError};
 use crate::util::interning::InternedString;
 use crate::util::machine_message::{self, Message};
@@ -424,7 +425,6 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
 fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResult<Work> {
     let bcx = cx.bcx;
     let outputs = cx.outputs(unit)?;
-    let export_dir = cx.files().export_dir();
     let package_id = unit.pkg.package_id();
     let manifest_path = PathBuf::from(unit.pkg.manifest_path());
     let profile = unit.profile;
@@ -462,8 +462,7 @@ fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResu
             destinations.push(dst.clone());
             paths::link_or_copy(src, dst)?;
             if let Some(ref path) = output.export_path {
-                let export_dir = export_dir.as_ref().unwrap();
-                paths::create_dir_all(export_dir)?;
+                paths::create_dir_all(path.parent().unwrap())?;
 
                 paths::link_or_copy(src, path)?;
             }
@@ -852,6 +851,12 @@ fn build_base_args(
         cmd.args(args);
     }
 
+    // Only the code of the workspace is measured, build scripts and proc
+    // macros aren't.
+    if bcx.build_config.coverage && bcx.ws.is_member(&unit.pkg) && !unit.target.for_host() {
+        cmd.args(&ops::instrument_coverage_flag(bcx.rustc())?);
+    }
+
     // `-C overflow-checks` is implied by the setting of `-C debug-assertions`,
     // so we only need to provide `-C overflow-checks` if it differs from
     // the value of `-C debug-assertions` we would provide.
@@ -25,10 +25,11 @@ use crate::core::resolver::Resolve;
 use crate::core::{Dependency, Package, PackageId, PackageSet, Target, Workspace};
 use crate::ops::resolve_all_features;
 use crate::util::interning::InternedString;
-use crate::util::Config;
+use crate::util::{Config, StableHasher};
 use crate::CargoResult;
 use log::trace;
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 
 /// Collection of stuff used while creating the `UnitGraph`.
 struct State<'a, 'cfg> {
@@ -38,6 +39,9 @@ struct State<'a, 'cfg> {
     package_set: &'a PackageSet<'cfg>,
     usr_resolve: &'a Resolve,
     usr_features: &'a ResolvedFeatures,
+    /// The features of the root whose dependencies are being generated, see
+    /// `ResolvedFeatures::for_root`.
+    root_features: &'a ResolvedFeatures,
     std_resolve: Option<&'a Resolve>,
     std_features: Option<&'a ResolvedFeatures>,
     /// This flag is `true` while generating the dependencies for the standard
@@ -84,6 +88,7 @@ pub fn build_unit_dependencies<'a, 'cfg>(
         package_set,
         usr_resolve: resolve,
         usr_features: features,
+        root_features: features,
         std_resolve,
         std_features,
         is_std: false,
@@ -192,12 +197,90 @@ fn deps_of_roots(roots: &[Unit], mut state: &mut State<'_, '_>) -> CargoResult<(
         } else {
             UnitFor::new_normal()
         };
-        deps_of(unit, &mut state, unit_for)?;
+        if !state.is_std && state.usr_features.is_per_root() {
+            // The dependencies of a unit can differ between the roots, so the
+            // graph of each root is built separately before being merged.
+            state.root_features = state.usr_features.for_root(unit.pkg.package_id());
+            let graph = std::mem::take(&mut state.unit_dependencies);
+            let dev_edges = std::mem::take(&mut state.dev_dependency_edges);
+            deps_of(unit, &mut state, unit_for)?;
+            let root_graph = std::mem::replace(&mut state.unit_dependencies, graph);
+            let root_dev_edges = std::mem::replace(&mut state.dev_dependency_edges, dev_edges);
+            merge_root_graph(state, unit, root_graph, root_dev_edges);
+        } else {
+            deps_of(unit, &mut state, unit_for)?;
+        }
     }
+    state.root_features = state.usr_features;
 
     Ok(())
 }
 
+/// Merges the graph of the single root `root` into `state.unit_dependencies`.
+///
+/// Two roots may use the same unit with different dependencies, for example
+/// when only the features of a dependency of that unit differ. Every unit
+/// but the root gets a `dep_hash` of its dependencies, which keeps such units
+/// apart, while the units with the same dependencies are still shared.
+fn merge_root_graph(
+    state: &mut State<'_, '_>,
+    root: &Unit,
+    root_graph: UnitGraph,
+    root_dev_edges: HashSet<(Unit, Unit)>,
+) {
+    fn merge(
+        state: &mut State<'_, '_>,
+        root_graph: &UnitGraph,
+        memo: &mut HashMap<Unit, Unit>,
+        unit: &Unit,
+        is_root: bool,
+    ) -> Unit {
+        if let Some(new_unit) = memo.get(unit) {
+            return new_unit.clone();
+        }
+        let mut dep_hash = StableHasher::new();
+        let new_deps: Vec<UnitDep> = root_graph[unit]
+            .iter()
+            .map(|dep| {
+                let new_dep_unit = merge(state, root_graph, memo, &dep.unit, false);
+                new_dep_unit.hash(&mut dep_hash);
+                UnitDep {
+                    unit: new_dep_unit,
+                    ..dep.clone()
+                }
+            })
+            .collect();
+        let new_unit = if is_root {
+            unit.clone()
+        } else {
+            state.interner.intern(
+                &unit.pkg,
+                &unit.target,
+                unit.profile,
+                unit.kind,
+                unit.mode,
+                unit.features.clone(),
+                unit.is_std,
+                dep_hash.finish(),
+            )
+        };
+        memo.insert(unit.clone(), new_unit.clone());
+        state
+            .unit_dependencies
+            .entry(new_unit.clone())
+            .or_insert(new_deps);
+        new_unit
+    }
+
+    let mut memo = HashMap::new();
+    merge(state, &root_graph, &mut memo, root, true);
+    state.dev_dependency_edges.extend(
+        root_dev_edges
+            .iter()
+            .map(|(parent, dep)| (memo[parent].clone(), memo[dep].clone())),
+    );
+}
+
 /// Compute the dependencies of a single unit.
 fn deps_of(unit: &Unit, state: &mut State<'_, '_>, unit_for: UnitFor) -> CargoResult<()> {
     // Currently the `unit_dependencies` map does not include `unit_for`. This should
@@ -278,15 +361,9 @@ fn compute_deps(
                 new_unit_dep(state, unit, pkg, lib, dep_unit_for, CompileKind::Host, mode)?;
             ret.push(unit_dep);
         } else {
-            let unit_dep = new_unit_dep(
-                state,
-                unit,
-                pkg,
-                lib,
-                dep_unit_for,
-                unit.kind.for_target(lib),
-                mode,
-            )?;
+            let kind = unit.kind.for_target(lib);
+            check_forced_target(state, unit, pkg, kind)?;
+            let unit_dep = new_unit_dep(state, unit, pkg, lib, dep_unit_for, kind, mode)?;
             ret.push(unit_dep);
         }
 
@@ -568,6 +645,36 @@ fn check_or_build_mode(mode: CompileMode, target: &Target) -> CompileMode {
     }
 }
 
+/// Checks that the library of `pkg` can be built for `kind` as a dependency
+/// of `parent`. A package with a `package.forced-target` key is only ever
+/// built for that target, so it can't be linked into crates built for
+/// another one.
+fn check_forced_target(
+    state: &State<'_, '_>,
+    parent: &Unit,
+    pkg: &Package,
+    kind: CompileKind,
+) -> CargoResult<()> {
+    let forced = match pkg.manifest().forced_kind() {
+        Some(forced) => forced,
+        None => return Ok(()),
+    };
+    let target = state.target_data.short_name(&kind);
+    let forced_target = state.target_data.short_name(&forced);
+    if target != forced_target {
+        anyhow::bail!(
+            "package `{}` depends on `{}`, which has `package.forced-target = \"{}\"`, \
+             but `{}` is built for `{}`",
+            parent.pkg.name(),
+            pkg.name(),
+            forced_target,
+            parent.pkg.name(),
+            target
+        );
+    }
+    Ok(())
+}
+
 /// Create a new Unit for a dependency from `parent` to `pkg` and `target`.
 fn new_unit_dep(
     state: &State<'_, '_>,
@@ -743,7 +850,7 @@ impl<'a, 'cfg> State<'a, 'cfg> {
         if self.is_std {
             self.std_features.unwrap()
         } else {
-            self.usr_features
+            self.root_features
         }
     }
 
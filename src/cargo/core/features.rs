@@ -100,6 +100,7 @@ use anyhow::{bail, Error};
 use cargo_util::ProcessBuilder;
 use serde::{Deserialize, Serialize};
 
+use crate::core::resolver::ExplainFormat;
 use crate::util::errors::CargoResult;
 use crate::util::{indented_lines, iter_join};
 use crate::Config;
@@ -392,7 +393,13 @@ features! {
     (unstable, edition2021, "", "reference/unstable.html#edition-2021"),
 
     // Allow to specify per-package targets (compile kinds)
-    (unstable, per_package_target, "", "reference/unstable.html#per-package-target"),
+    (stable, per_package_target, "1.55", "reference/manifest.html#the-default-target-and-forced-target-fields"),
+
+    // Allow to declare conflicting and required features in `[features]`.
+    (unstable, feature_constraints, "", "reference/unstable.html#feature-constraints"),
+
+    // Allow to document features, and mark them unstable or private.
+    (unstable, feature_metadata, "", "reference/unstable.html#feature-metadata"),
 }
 
 const PUBLISH_LOCKFILE_REMOVED: &str = "The publish-lockfile key in Cargo.toml \
@@ -578,6 +585,8 @@ unstable_cli_options!(
     // All other unstable features.
     // Please keep this list lexiographically ordered.
     advanced_env: bool = (HIDDEN),
+    affected: bool = ("Allow `--affected` to select the workspace members affected by changes in a git revision range"),
+    audit: bool = ("Enable the `cargo audit-lockfile` command to check Cargo.lock against a local advisory database"),
     avoid_dev_deps: bool = ("Avoid installing dev-dependencies if possible"),
     binary_dep_depinfo: bool = ("Track changes to dependency artifacts"),
     #[serde(deserialize_with = "deserialize_build_std")]
@@ -585,29 +594,44 @@ unstable_cli_options!(
     build_std_features: Option<Vec<String>>  = ("Configure features enabled for the standard library itself when building the standard library"),
     config_include: bool = ("Enable the `include` key in config files"),
     configurable_env: bool = ("Enable the [env] section in the .cargo/config.toml file"),
+    coverage: bool = ("Allow `cargo test --coverage` to measure the code coverage of the tests"),
     credential_process: bool = ("Add a config setting to fetch registry authentication tokens by calling an external process"),
+    daemon: bool = ("Allow `cargo daemon`, and running `cargo build` and `cargo check` in it"),
+    dependency_policy: bool = ("Enable the `cargo policy` command to check dependencies against the `[policy]` config table"),
+    direct_minimal_versions: bool = ("Resolve minimal versions of direct dependencies of workspace members, and maximum versions of the rest"),
     doctest_in_workspace: bool = ("Compile doctests with paths relative to the workspace root"),
     doctest_xcompile: bool = ("Compile and run doctests for non-host target using runner config"),
     dual_proc_macros: bool = ("Build proc-macros for both the host and the target"),
+    edition_migration: bool = ("Allow `cargo fix --edition` to migrate workspace members one at a time"),
     future_incompat_report: bool = ("Enable creation of a future-incompat report for all dependencies"),
     extra_link_arg: bool = ("Allow `cargo:rustc-link-arg` in build scripts"),
     features: Option<Vec<String>>  = (HIDDEN),
     jobserver_per_rustc: bool = (HIDDEN),
+    locked_checksums: bool = ("Forbid network access, and verify the checksum of every package in Cargo.lock against its source"),
+    lockfile_merge: bool = ("Resolve merge conflicts in Cargo.lock, and enable the `cargo merge-lockfile` command"),
     minimal_versions: bool = ("Resolve minimal dependency versions instead of maximum"),
     mtime_on_use: bool = ("Configure Cargo to update the mtime of used files"),
-    multitarget: bool = ("Allow passing multiple `--target` flags to the cargo subcommand selected"),
     named_profiles: bool = ("Allow defining custom profiles"),
     namespaced_features: bool = ("Allow features with `dep:` prefix"),
     no_index_update: bool = ("Do not update the registry index even if the cache is outdated"),
+    outdated: bool = ("Enable the `cargo outdated-deps` command to show dependencies with newer versions"),
     panic_abort_tests: bool = ("Enable support to run tests with -Cpanic=abort"),
+    parallel_tests: bool = ("Run test binaries in parallel in `cargo test`, buffering their output"),
     host_config: bool = ("Enable the [host] section in the .cargo/config.toml file"),
     target_applies_to_host: bool = ("Enable the `target-applies-to-host` key in the .cargo/config.toml file"),
     patch_in_config: bool = ("Allow `[patch]` sections in .cargo/config.toml files"),
+    resolve_explain: Option<ExplainFormat> = ("Explain dependency resolution conflicts and suggest fixes, also as JSON with `=json`"),
     rustdoc_map: bool = ("Allow passing external documentation mappings to rustdoc"),
     separate_nightlies: bool = (HIDDEN),
     terminal_width: Option<Option<usize>>  = ("Provide a terminal width to rustc for error truncation"),
+    test_report: bool = ("Allow `cargo test --report-junit` and `--report-json` to write reports of the test results"),
+    test_shard: bool = ("Allow `cargo test --shard` to run one part of the tests, to split them across machines"),
     timings: Option<Vec<String>>  = ("Display concurrency information"),
+    tree_output_format: bool = ("Allow `cargo tree --output-format` to emit DOT or JSON"),
+    tree_why: bool = ("Allow `cargo tree --why` to explain why a package or feature is included"),
     unstable_options: bool = ("Allow the usage of unstable options"),
+    update_report: bool = ("Allow `cargo update --output-format json` to report the changes to Cargo.lock"),
+    watch: bool = ("Allow `--watch` to rerun `cargo build`, `check`, `test` and `run` when the sources change"),
     weak_dep_features: bool = ("Allow `dep_name?/feature` feature syntax"),
     skip_rustdoc_fingerprint: bool = (HIDDEN),
 );
@@ -646,6 +670,9 @@ const STABILIZED_FEATURES: &str = "The new feature resolver is now available \
     See https://doc.rust-lang.org/nightly/cargo/reference/features.html#feature-resolver-version-2 \
     for more information.";
 
+const STABILIZED_MULTITARGET: &str = "Multiple `--target` flags, and a list \
+    of targets in the `build.target` config value, are now always available.";
+
 fn deserialize_build_std<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -771,10 +798,19 @@ impl CliUnstable {
             "unstable-options" => self.unstable_options = parse_empty(k, v)?,
             "no-index-update" => self.no_index_update = parse_empty(k, v)?,
             "avoid-dev-deps" => self.avoid_dev_deps = parse_empty(k, v)?,
+            "audit" => self.audit = parse_empty(k, v)?,
             "minimal-versions" => self.minimal_versions = parse_empty(k, v)?,
+            "locked-checksums" => self.locked_checksums = parse_empty(k, v)?,
+            "lockfile-merge" => self.lockfile_merge = parse_empty(k, v)?,
+            "direct-minimal-versions" => self.direct_minimal_versions = parse_empty(k, v)?,
             "advanced-env" => self.advanced_env = parse_empty(k, v)?,
+            "affected" => self.affected = parse_empty(k, v)?,
             "config-include" => self.config_include = parse_empty(k, v)?,
+            "coverage" => self.coverage = parse_empty(k, v)?,
             "dual-proc-macros" => self.dual_proc_macros = parse_empty(k, v)?,
+            "daemon" => self.daemon = parse_empty(k, v)?,
+            "dependency-policy" => self.dependency_policy = parse_empty(k, v)?,
+            "edition-migration" => self.edition_migration = parse_empty(k, v)?,
             // can also be set in .cargo/config or with and ENV
             "mtime-on-use" => self.mtime_on_use = parse_empty(k, v)?,
             "named-profiles" => self.named_profiles = parse_empty(k, v)?,
@@ -784,9 +820,22 @@ impl CliUnstable {
             }
             "build-std-features" => self.build_std_features = Some(parse_features(v)),
             "timings" => self.timings = Some(parse_timings(v)),
+            "resolve-explain" => {
+                self.resolve_explain = Some(match v {
+                    None | Some("text") => ExplainFormat::Text,
+                    Some("json") => ExplainFormat::Json,
+                    Some(s) => bail!(
+                        "unknown `-Z resolve-explain` format `{}`, expected `text` or `json`",
+                        s
+                    ),
+                })
+            }
             "doctest-xcompile" => self.doctest_xcompile = parse_empty(k, v)?,
             "doctest-in-workspace" => self.doctest_in_workspace = parse_empty(k, v)?,
             "panic-abort-tests" => self.panic_abort_tests = parse_empty(k, v)?,
+            "parallel-tests" => self.parallel_tests = parse_empty(k, v)?,
+            "test-report" => self.test_report = parse_empty(k, v)?,
+            "test-shard" => self.test_shard = parse_empty(k, v)?,
             "jobserver-per-rustc" => self.jobserver_per_rustc = parse_empty(k, v)?,
             "configurable-env" => self.configurable_env = parse_empty(k, v)?,
             "host-config" => self.host_config = parse_empty(k, v)?,
@@ -814,11 +863,15 @@ impl CliUnstable {
                 self.features = Some(feats);
             }
             "separate-nightlies" => self.separate_nightlies = parse_empty(k, v)?,
-            "multitarget" => self.multitarget = parse_empty(k, v)?,
             "rustdoc-map" => self.rustdoc_map = parse_empty(k, v)?,
             "terminal-width" => self.terminal_width = Some(parse_usize_opt(v)?),
+            "tree-output-format" => self.tree_output_format = parse_empty(k, v)?,
+            "tree-why" => self.tree_why = parse_empty(k, v)?,
+            "update-report" => self.update_report = parse_empty(k, v)?,
+            "outdated" => self.outdated = parse_empty(k, v)?,
             "namespaced-features" => self.namespaced_features = parse_empty(k, v)?,
             "weak-dep-features" => self.weak_dep_features = parse_empty(k, v)?,
+            "watch" => self.watch = parse_empty(k, v)?,
             "extra-link-arg" => self.extra_link_arg = parse_empty(k, v)?,
             "credential-process" => self.credential_process = parse_empty(k, v)?,
             "skip-rustdoc-fingerprint" => self.skip_rustdoc_fingerprint = parse_empty(k, v)?,
@@ -829,6 +882,7 @@ impl CliUnstable {
             "config-profile" => stabilized_warn(k, "1.43", STABILIZED_CONFIG_PROFILE),
             "crate-versions" => stabilized_warn(k, "1.47", STABILIZED_CRATE_VERSIONS),
             "package-features" => stabilized_warn(k, "1.51", STABILIZED_PACKAGE_FEATURES),
+            "multitarget" => stabilized_warn(k, "1.55", STABILIZED_MULTITARGET),
             "future-incompat-report" => self.future_incompat_report = parse_empty(k, v)?,
             _ => bail!("unknown `-Z` flag specified: {}", k),
         }
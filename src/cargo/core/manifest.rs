@@ -20,6 +20,7 @@ use crate::util::interning::InternedString;
 use crate::util::toml::{TomlManifest, TomlProfiles};
 use crate::util::{short_hash, Config, Filesystem};
 
+#[derive(Clone, Debug)]
 pub enum EitherManifest {
     Real(Manifest),
     Virtual(VirtualManifest),
@@ -53,6 +54,33 @@ pub struct Manifest {
     default_run: Option<String>,
     metabuild: Option<Vec<String>>,
     resolve_behavior: Option<ResolveBehavior>,
+    feature_constraints: BTreeMap<InternedString, FeatureConstraints>,
+    feature_metadata: BTreeMap<InternedString, FeatureMetadata>,
+}
+
+/// Documentation and stability of a feature, declared with the table form of
+/// a feature in `[features]`.
+#[derive(Clone, Debug, Serialize)]
+pub struct FeatureMetadata {
+    /// A description of the feature.
+    pub doc: Option<String>,
+    /// Whether the feature is unstable, and may change or be removed in a
+    /// semver-compatible release.
+    pub unstable: bool,
+    /// Whether the feature is meant to be enabled by dependents. Private
+    /// features are used by the package itself, for example to group other
+    /// features.
+    pub public: bool,
+}
+
+/// Constraints on enabling a feature, declared with the table form of a
+/// feature in `[features]`.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureConstraints {
+    /// Features which cannot be enabled together with this feature.
+    pub conflicts: Vec<InternedString>,
+    /// Features which must be enabled whenever this feature is.
+    pub requires: Vec<InternedString>,
 }
 
 /// When parsing `Cargo.toml`, some warnings should silenced
@@ -389,6 +417,8 @@ impl Manifest {
         original: Rc<TomlManifest>,
         metabuild: Option<Vec<String>>,
         resolve_behavior: Option<ResolveBehavior>,
+        feature_constraints: BTreeMap<InternedString, FeatureConstraints>,
+        feature_metadata: BTreeMap<InternedString, FeatureMetadata>,
     ) -> Manifest {
         Manifest {
             summary,
@@ -414,6 +444,8 @@ impl Manifest {
             default_run,
             metabuild,
             resolve_behavior,
+            feature_constraints,
+            feature_metadata,
         }
     }
 
@@ -515,15 +547,6 @@ impl Manifest {
                 })?;
         }
 
-        if self.default_kind.is_some() || self.forced_kind.is_some() {
-            self.unstable_features
-                .require(Feature::per_package_target())
-                .with_context(|| {
-                    "the `package.default-target` and `package.forced-target` \
-                     manifest keys are unstable and may not work properly"
-                })?;
-        }
-
         Ok(())
     }
 
@@ -552,6 +575,18 @@ impl Manifest {
         self.default_run.as_deref()
     }
 
+    /// The constraints declared on the features of this package, keyed by
+    /// feature name.
+    pub fn feature_constraints(&self) -> &BTreeMap<InternedString, FeatureConstraints> {
+        &self.feature_constraints
+    }
+
+    /// The documentation and stability of the features of this package which
+    /// declare them, keyed by feature name.
+    pub fn feature_metadata(&self) -> &BTreeMap<InternedString, FeatureMetadata> {
+        &self.feature_metadata
+    }
+
     pub fn metabuild(&self) -> Option<&Vec<String>> {
         self.metabuild.as_ref()
     }
@@ -22,7 +22,7 @@ use crate::core::dependency::DepKind;
 use crate::core::resolver::features::ForceAllTargets;
 use crate::core::resolver::{HasDevUnits, Resolve};
 use crate::core::source::MaybePackage;
-use crate::core::{Dependency, Manifest, PackageId, SourceId, Target};
+use crate::core::{Dependency, FeatureMetadata, Manifest, PackageId, SourceId, Target};
 use crate::core::{SourceMap, Summary, Workspace};
 use crate::ops;
 use crate::util::config::PackageCacheLock;
@@ -88,6 +88,8 @@ pub struct SerializedPackage {
     dependencies: Vec<Dependency>,
     targets: Vec<Target>,
     features: BTreeMap<InternedString, Vec<InternedString>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    feature_metadata: BTreeMap<InternedString, FeatureMetadata>,
     manifest_path: PathBuf,
     metadata: Option<toml::Value>,
     publish: Option<Vec<String>>,
@@ -237,11 +239,7 @@ impl Package {
                 })
                 .collect()
         } else {
-            self.manifest()
-                .original()
-                .features()
-                .cloned()
-                .unwrap_or_default()
+            self.manifest().original().features().unwrap_or_default()
         };
 
         SerializedPackage {
@@ -255,6 +253,7 @@ impl Package {
             dependencies: summary.dependencies().to_vec(),
             targets,
             features,
+            feature_metadata: self.manifest().feature_metadata().clone(),
             manifest_path: self.manifest_path().to_path_buf(),
             metadata: self.manifest().custom_metadata().cloned(),
             authors: manmeta.authors.clone(),
@@ -572,6 +571,39 @@ impl<'cfg> PackageSet<'cfg> {
         Ok(())
     }
 
+    /// Loads every package of `resolve` from its source and verifies its
+    /// contents, for `-Z locked-checksums`. Unlike a build, this also covers
+    /// the packages which are only used on other platforms.
+    ///
+    /// All the packages are checked before returning an error, so that it
+    /// lists every package which is missing or has been modified.
+    pub fn verify_locked(&self, resolve: &Resolve) -> CargoResult<()> {
+        let mut problems = Vec::new();
+        let mut ids: Vec<_> = resolve
+            .iter()
+            .filter(|id| !id.source_id().is_path())
+            .collect();
+        ids.sort();
+        for id in ids {
+            let result = self
+                .get_one(id)
+                .and_then(|_| match self.sources().get(id.source_id()) {
+                    Some(source) => source.verify(id),
+                    None => Ok(()),
+                });
+            if let Err(e) = result {
+                problems.push(format!("`{}`: {}", id, e.root_cause()));
+            }
+        }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "the sources of the locked packages cannot be verified with -Z locked-checksums:\n  {}",
+            problems.join("\n  ")
+        )
+    }
+
     pub fn sources(&self) -> Ref<'_, SourceMap<'cfg>> {
         self.sources.borrow()
     }
@@ -28,13 +28,20 @@ use std::rc::Rc;
 pub struct RegistryQueryer<'a> {
     pub registry: &'a mut (dyn Registry + 'a),
     replacements: &'a [(PackageIdSpec, Dependency)],
-    try_to_use: &'a HashSet<PackageId>,
+    pub(super) try_to_use: &'a HashSet<PackageId>,
     /// If set the list of dependency candidates will be sorted by minimal
     /// versions first. That allows `cargo update -Z minimal-versions` which will
     /// specify minimum dependency versions to be used.
     minimal_versions: bool,
-    /// a cache of `Candidate`s that fulfil a `Dependency`
-    registry_cache: HashMap<Dependency, Rc<Vec<Summary>>>,
+    /// If set the candidates for the direct dependencies of the root
+    /// summaries are sorted by minimal versions first, while all other
+    /// dependencies are sorted by maximal versions first. That allows
+    /// checking the lower bounds of the version requirements of workspace
+    /// members with `-Z direct-minimal-versions`.
+    direct_minimal_versions: bool,
+    /// a cache of `Candidate`s that fulfil a `Dependency`, and whether they
+    /// are sorted by minimal versions first
+    registry_cache: HashMap<(Dependency, bool), Rc<Vec<Summary>>>,
     /// a cache of `Dependency`s that are required for a `Summary`
     summary_cache: HashMap<
         (Option<PackageId>, Summary, ResolveOpts),
@@ -50,12 +57,14 @@ impl<'a> RegistryQueryer<'a> {
         replacements: &'a [(PackageIdSpec, Dependency)],
         try_to_use: &'a HashSet<PackageId>,
         minimal_versions: bool,
+        direct_minimal_versions: bool,
     ) -> Self {
         RegistryQueryer {
             registry,
             replacements,
             try_to_use,
             minimal_versions,
+            direct_minimal_versions,
             registry_cache: HashMap::new(),
             summary_cache: HashMap::new(),
             used_replacements: HashMap::new(),
@@ -77,7 +86,18 @@ impl<'a> RegistryQueryer<'a> {
     /// applied by performing a second query for what the override should
     /// return.
     pub fn query(&mut self, dep: &Dependency) -> CargoResult<Rc<Vec<Summary>>> {
-        if let Some(out) = self.registry_cache.get(dep).cloned() {
+        self.query_sorted(dep, self.minimal_versions)
+    }
+
+    /// Like `query`, but the candidates are sorted by minimal versions first
+    /// if `minimal_first` is set.
+    fn query_sorted(
+        &mut self,
+        dep: &Dependency,
+        minimal_first: bool,
+    ) -> CargoResult<Rc<Vec<Summary>>> {
+        let key = (dep.clone(), minimal_first);
+        if let Some(out) = self.registry_cache.get(&key).cloned() {
             return Ok(out);
         }
 
@@ -175,7 +195,7 @@ impl<'a> RegistryQueryer<'a> {
             match previous_cmp {
                 Ordering::Equal => {
                     let cmp = a.version().cmp(b.version());
-                    if self.minimal_versions {
+                    if minimal_first {
                         // Lower version ordered first.
                         cmp
                     } else {
@@ -189,7 +209,7 @@ impl<'a> RegistryQueryer<'a> {
 
         let out = Rc::new(ret);
 
-        self.registry_cache.insert(dep.clone(), out.clone());
+        self.registry_cache.insert(key, out.clone());
 
         Ok(out)
     }
@@ -219,12 +239,17 @@ impl<'a> RegistryQueryer<'a> {
         // for our own dependencies.
         let (used_features, deps) = resolve_features(parent, candidate, opts)?;
 
+        // Only the root summaries are activated without a parent, so these
+        // are direct dependencies of the workspace members.
+        let minimal_first =
+            self.minimal_versions || (self.direct_minimal_versions && parent.is_none());
+
         // Next, transform all dependencies into a list of possible candidates
         // which can satisfy that dependency.
         let mut deps = deps
             .into_iter()
             .map(|(dep, features)| {
-                let candidates = self.query(&dep).with_context(|| {
+                let candidates = self.query_sorted(&dep, minimal_first).with_context(|| {
                     format!(
                         "failed to get `{}` as a dependency of {}",
                         dep.package_name(),
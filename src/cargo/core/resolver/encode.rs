@@ -400,6 +400,107 @@ impl EncodableResolve {
             HashMap::new(),
         ))
     }
+
+    /// Merges the two sides of a merge conflict in `Cargo.lock`.
+    ///
+    /// The result has the packages of both sides, so it may have several
+    /// versions of a package for which each side had a single one. Like the
+    /// `Resolve` from `into_resolve`, it is only meant to guide the resolver.
+    pub fn merge(mut self, mut other: EncodableResolve) -> EncodableResolve {
+        // Dependencies are only written by name when there is a single
+        // version of a package, which may no longer be the case once merged,
+        // so add the version of those that become ambiguous.
+        let mut ids: HashMap<String, HashSet<(String, Option<SourceId>)>> = HashMap::new();
+        for pkg in self.all_packages().chain(other.all_packages()) {
+            ids.entry(pkg.name.clone())
+                .or_default()
+                .insert((pkg.version.clone(), pkg.source));
+        }
+        let ambiguous: HashSet<String> = ids
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(name, _)| name)
+            .collect();
+        self.qualify_dependencies(&ambiguous);
+        other.qualify_dependencies(&ambiguous);
+
+        let mut packages: BTreeMap<(String, String, Option<SourceId>), EncodableDependency> =
+            BTreeMap::new();
+        let all = self.package.take().into_iter().flatten();
+        for pkg in all.chain(other.package.take().into_iter().flatten()) {
+            let key = (pkg.name.clone(), pkg.version.clone(), pkg.source);
+            let existing = match packages.get_mut(&key) {
+                Some(existing) => existing,
+                None => {
+                    packages.insert(key, pkg);
+                    continue;
+                }
+            };
+            if let Some(deps) = pkg.dependencies {
+                let existing_deps = existing.dependencies.get_or_insert_with(Vec::new);
+                for dep in deps {
+                    if !existing_deps.contains(&dep) {
+                        existing_deps.push(dep);
+                    }
+                }
+                existing_deps.sort();
+            }
+            if existing.checksum.is_none() {
+                existing.checksum = pkg.checksum;
+            }
+            if existing.replace.is_none() {
+                existing.replace = pkg.replace;
+            }
+        }
+        self.package = Some(packages.into_iter().map(|(_, pkg)| pkg).collect());
+
+        self.version = self.version.max(other.version);
+        if self.root.is_none() {
+            self.root = other.root;
+        }
+        self.metadata = match (self.metadata, other.metadata) {
+            (Some(mut metadata), Some(other)) => {
+                for (k, v) in other {
+                    metadata.entry(k).or_insert(v);
+                }
+                Some(metadata)
+            }
+            (metadata, other) => metadata.or(other),
+        };
+        for unused in other.patch.unused {
+            if !self.patch.unused.contains(&unused) {
+                self.patch.unused.push(unused);
+            }
+        }
+        self
+    }
+
+    fn all_packages(&self) -> impl Iterator<Item = &EncodableDependency> {
+        self.package.iter().flatten().chain(self.root.iter())
+    }
+
+    /// Adds the version and source to the dependencies on the `ambiguous`
+    /// package names, when this lock file has a single version of them.
+    fn qualify_dependencies(&mut self, ambiguous: &HashSet<String>) {
+        let mut ids: HashMap<String, Vec<(String, Option<SourceId>)>> = HashMap::new();
+        for pkg in self.all_packages() {
+            ids.entry(pkg.name.clone())
+                .or_default()
+                .push((pkg.version.clone(), pkg.source));
+        }
+        let packages = self.package.iter_mut().flatten();
+        for pkg in packages.chain(self.root.iter_mut()) {
+            for dep in pkg.dependencies.iter_mut().flatten() {
+                if dep.version.is_some() || !ambiguous.contains(&dep.name) {
+                    continue;
+                }
+                if let Some([(version, source)]) = ids.get(&dep.name).map(|ids| ids.as_slice()) {
+                    dep.version = Some(version.clone());
+                    dep.source = *source;
+                }
+            }
+        }
+    }
 }
 
 fn build_path_deps(ws: &Workspace<'_>) -> CargoResult<HashMap<String, SourceId>> {
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fmt;
 
 use crate::core::{Dependency, PackageId, Registry, Summary};
@@ -6,12 +7,14 @@ use crate::util::{Config, VersionExt};
 use anyhow::Error;
 
 use super::context::Context;
+use super::explain::{self, ConflictExplanation};
 use super::types::{ConflictMap, ConflictReason};
 
 /// Error during resolution providing a path of `PackageId`s.
 pub struct ResolveError {
     cause: Error,
     package_path: Vec<PackageId>,
+    explanation: Option<ConflictExplanation>,
 }
 
 impl ResolveError {
@@ -19,6 +22,7 @@ impl ResolveError {
         Self {
             cause: cause.into(),
             package_path,
+            explanation: None,
         }
     }
 
@@ -27,6 +31,12 @@ impl ResolveError {
     pub fn package_path(&self) -> &[PackageId] {
         &self.package_path
     }
+
+    /// Returns the explanation of the conflict, if it was requested with
+    /// `-Z resolve-explain`.
+    pub fn explanation(&self) -> Option<&ConflictExplanation> {
+        self.explanation.as_ref()
+    }
 }
 
 impl std::error::Error for ResolveError {
@@ -70,6 +80,7 @@ impl From<(PackageId, ConflictReason)> for ActivateError {
 pub(super) fn activation_error(
     cx: &Context,
     registry: &mut dyn Registry,
+    try_to_use: &HashSet<PackageId>,
     parent: &Summary,
     dep: &Dependency,
     conflicting_activations: &ConflictMap,
@@ -86,6 +97,9 @@ pub(super) fn activation_error(
                 .collect(),
         )
     };
+    let explain = config.map_or(false, |config| {
+        config.cli_unstable().resolve_explain.is_some()
+    });
 
     if !candidates.is_empty() {
         let mut msg = format!("failed to select a version for `{}`.", dep.package_name());
@@ -106,6 +120,7 @@ pub(super) fn activation_error(
                 .join(", "),
         );
 
+        let conflict_map = conflicting_activations;
         let mut conflicting_activations: Vec<_> = conflicting_activations.iter().collect();
         conflicting_activations.sort_unstable();
         // This is reversed to show the newest versions first. I don't know if there is
@@ -177,12 +192,28 @@ pub(super) fn activation_error(
                     // p == parent so the full path is redundant.
                 }
                 ConflictReason::PublicDependency(pkg_id) => {
-                    // TODO: This needs to be implemented.
-                    unimplemented!("pub dep {:?}", pkg_id);
+                    // The candidate would be seen through `parent`, which is
+                    // already described as requiring it.
+                    if **p == parent.package_id() {
+                        continue;
+                    }
+                    msg.push_str("\n\nthe package `");
+                    msg.push_str(&*pkg_id.name());
+                    msg.push_str("` can already see `");
+                    msg.push_str(&p.to_string());
+                    msg.push_str("`, directly or through public dependencies, ");
+                    msg.push_str("and can only see one version of `");
+                    msg.push_str(&*p.name());
+                    msg.push_str("`:\n");
+                    msg.push_str(&describe_path(&cx.parents.path_to_bottom(pkg_id)));
                 }
                 ConflictReason::PubliclyExports(pkg_id) => {
-                    // TODO: This needs to be implemented.
-                    unimplemented!("pub exp {:?}", pkg_id);
+                    msg.push_str("\n\nthe package `");
+                    msg.push_str(&*pkg_id.name());
+                    msg.push_str("` publicly exports `");
+                    msg.push_str(&p.to_string());
+                    msg.push_str("`, which is visible to the packages depending on it:\n");
+                    msg.push_str(&describe_path(&cx.parents.path_to_bottom(p)));
                 }
             }
         }
@@ -202,6 +233,16 @@ pub(super) fn activation_error(
         msg.push_str(&*dep.package_name());
         msg.push_str("` which could resolve this conflict");
 
+        if explain {
+            let explanation =
+                explain::explain_conflict(cx, try_to_use, parent, dep, conflict_map, candidates);
+            msg.push_str("\n\n");
+            msg.push_str(&explanation.to_string());
+            let mut err = to_resolve_err(anyhow::format_err!("{}", msg));
+            err.explanation = Some(explanation);
+            return err;
+        }
+
         return to_resolve_err(anyhow::format_err!("{}", msg));
     }
 
@@ -330,6 +371,14 @@ pub(super) fn activation_error(
             msg
         };
 
+    let mut explanation = None;
+    if explain && !candidates.is_empty() {
+        let e = explain::explain_no_match(cx, parent, dep, &candidates);
+        msg.push_str("\n\n");
+        msg.push_str(&e.to_string());
+        explanation = Some(e);
+    }
+
     if let Some(config) = config {
         if config.offline() {
             msg.push_str(
@@ -341,7 +390,9 @@ pub(super) fn activation_error(
         }
     }
 
-    to_resolve_err(anyhow::format_err!("{}", msg))
+    let mut err = to_resolve_err(anyhow::format_err!("{}", msg));
+    err.explanation = explanation;
+    err
 }
 
 /// Returns String representation of dependency chain for a particular `pkgid`.
@@ -0,0 +1,295 @@
+//! Explanations of resolution failures, for `-Z resolve-explain`.
+//!
+//! When the resolver fails to select a version for a dependency, the
+//! conflicting activations recorded while backtracking are turned into the
+//! list of requirements that cannot be satisfied together, each traced back
+//! to the workspace member that introduced it. From these requirements a few
+//! concrete edits are suggested, such as relaxing a requirement in a local
+//! manifest or updating a locked package with `cargo update --precise`.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{Dependency, PackageId, Summary};
+use crate::util::interning::InternedString;
+
+use super::context::Context;
+use super::types::{ConflictMap, ConflictReason};
+
+/// The version of the JSON explanation, increased on incompatible changes.
+const EXPLANATION_VERSION: u32 = 1;
+
+/// How conflicts are explained with `-Z resolve-explain`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplainFormat {
+    /// In the error message.
+    Text,
+    /// In the error message, and as JSON on stdout.
+    Json,
+}
+
+/// Why a resolution failed, and what could be changed to fix it.
+#[derive(Debug, Serialize)]
+pub struct ConflictExplanation {
+    version: u32,
+    /// The package for which no version could be selected.
+    pub package: InternedString,
+    /// The versions of `package` matching the failing requirement.
+    pub candidates: Vec<String>,
+    /// The requirements which cannot be satisfied together. The first one is
+    /// the requirement which failed.
+    pub requirements: Vec<ConflictRequirement>,
+    /// Suggested changes, in the order they should be tried.
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConflictRequirement {
+    /// The name of the required package.
+    pub package: InternedString,
+    /// The version requirement. Requirements of locked packages are exact.
+    pub req: String,
+    /// The package declaring the requirement.
+    pub required_by: PackageId,
+    /// The version which was already selected for this requirement, if it
+    /// conflicts with the failing one.
+    pub selected: Option<String>,
+    /// Why this requirement conflicts.
+    pub reason: RequirementReason,
+    /// The workspace member through which the requirement is introduced.
+    pub member: PackageId,
+    /// The manifest of `member`.
+    pub manifest: Option<PathBuf>,
+}
+
+/// Why a requirement is part of a conflict.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequirementReason {
+    /// No version could be selected for this requirement.
+    Failed,
+    /// The version selected for this requirement is semver compatible with
+    /// the failing one, so only one of them can be activated.
+    Semver,
+    /// The version selected for this requirement links the same native
+    /// library as the failing one.
+    Links,
+}
+
+impl ConflictRequirement {
+    fn new(
+        cx: &Context,
+        dep: &Dependency,
+        required_by: PackageId,
+        selected: Option<PackageId>,
+        reason: RequirementReason,
+    ) -> ConflictRequirement {
+        let member = **cx.parents.path_to_bottom(&required_by).last().unwrap();
+        ConflictRequirement {
+            package: dep.package_name(),
+            req: dep.version_req().to_string(),
+            required_by,
+            selected: selected.map(|id| id.version().to_string()),
+            reason,
+            member,
+            manifest: manifest_path(member),
+        }
+    }
+}
+
+/// Returns the manifest of a package from a local path.
+fn manifest_path(id: PackageId) -> Option<PathBuf> {
+    if !id.source_id().is_path() {
+        return None;
+    }
+    let dir = id.source_id().url().to_file_path().ok()?;
+    Some(dir.join("Cargo.toml"))
+}
+
+/// Builds the explanation for failing to select any of `candidates` for
+/// `dep` of `parent`. `locked` are the packages from `Cargo.lock`.
+pub(super) fn explain_conflict(
+    cx: &Context,
+    locked: &HashSet<PackageId>,
+    parent: &Summary,
+    dep: &Dependency,
+    conflicting_activations: &ConflictMap,
+    candidates: &[Summary],
+) -> ConflictExplanation {
+    let mut requirements = vec![ConflictRequirement::new(
+        cx,
+        dep,
+        parent.package_id(),
+        None,
+        RequirementReason::Failed,
+    )];
+    let mut suggestions = Vec::new();
+    let mut candidate_versions: Vec<_> = candidates.iter().map(|s| s.version()).collect();
+    candidate_versions.sort_unstable();
+    candidate_versions.dedup();
+
+    for (&selected, reason) in conflicting_activations {
+        let reason = match reason {
+            ConflictReason::Semver => RequirementReason::Semver,
+            ConflictReason::Links(_) => RequirementReason::Links,
+            ConflictReason::MissingFeatures(_)
+            | ConflictReason::RequiredDependencyAsFeature(_)
+            | ConflictReason::NonImplicitDependencyAsFeature(_) => {
+                // The failing requirement itself asks for the features.
+                continue;
+            }
+            ConflictReason::PublicDependency(_) | ConflictReason::PubliclyExports(_) => continue,
+        };
+        let mut selected_reqs = Vec::new();
+        let mut selected_deps = Vec::new();
+        for (&required_by, deps) in cx.parents.edges(&selected) {
+            for selected_dep in deps.iter() {
+                selected_reqs.push(ConflictRequirement::new(
+                    cx,
+                    selected_dep,
+                    required_by,
+                    Some(selected),
+                    reason,
+                ));
+                selected_deps.push((selected_dep, required_by));
+            }
+        }
+        selected_reqs.sort_by(|a, b| a.required_by.cmp(&b.required_by));
+
+        // Only another version of the same package can be changed to select
+        // one of the candidates instead.
+        if reason == RequirementReason::Semver && selected.name() == dep.package_name() {
+            if !dep.version_req().matches(selected.version()) {
+                suggest_relax(
+                    &mut suggestions,
+                    dep,
+                    parent.package_id(),
+                    Some(selected.version()),
+                );
+            }
+            for &(selected_dep, required_by) in &selected_deps {
+                suggest_relax(
+                    &mut suggestions,
+                    selected_dep,
+                    required_by,
+                    candidate_versions.last().copied(),
+                );
+            }
+            // Updating the selected package to a candidate works if the
+            // candidate matches all the requirements on it. The requirements
+            // on a locked package are exact, but the update overrides them.
+            let is_locked = locked.contains(&selected);
+            let update_to = candidate_versions.iter().rev().find(|v| {
+                selected_deps.iter().all(|(d, _)| {
+                    let req = d.version_req();
+                    req.matches(v) || (is_locked && req.is_exact())
+                })
+            });
+            if let (Some(version), true) = (update_to, selected.source_id().is_registry()) {
+                suggestions.push(format!(
+                    "run `cargo update -p {}:{} --precise {}`",
+                    selected.name(),
+                    selected.version(),
+                    version
+                ));
+            }
+        }
+        requirements.extend(selected_reqs);
+    }
+    let mut seen = HashSet::new();
+    suggestions.retain(|s| seen.insert(s.clone()));
+
+    ConflictExplanation {
+        version: EXPLANATION_VERSION,
+        package: dep.package_name(),
+        candidates: candidate_versions.iter().map(|v| v.to_string()).collect(),
+        requirements,
+        suggestions,
+    }
+}
+
+/// Builds the explanation for a requirement which no version matches,
+/// suggesting the newest of the `available` versions.
+pub(super) fn explain_no_match(
+    cx: &Context,
+    parent: &Summary,
+    dep: &Dependency,
+    available: &[Summary],
+) -> ConflictExplanation {
+    let mut suggestions = Vec::new();
+    let newest = available.iter().map(|s| s.version()).max();
+    suggest_relax(&mut suggestions, dep, parent.package_id(), newest);
+    ConflictExplanation {
+        version: EXPLANATION_VERSION,
+        package: dep.package_name(),
+        candidates: Vec::new(),
+        requirements: vec![ConflictRequirement::new(
+            cx,
+            dep,
+            parent.package_id(),
+            None,
+            RequirementReason::Failed,
+        )],
+        suggestions,
+    }
+}
+
+/// Suggests relaxing the requirement `dep` of `required_by` to allow
+/// `version`, if `required_by` has a local manifest that can be edited.
+fn suggest_relax(
+    suggestions: &mut Vec<String>,
+    dep: &Dependency,
+    required_by: PackageId,
+    version: Option<&semver::Version>,
+) {
+    let (version, manifest) = match (version, manifest_path(required_by)) {
+        (Some(version), Some(manifest)) => (version, manifest),
+        _ => return,
+    };
+    if dep.version_req().matches(version) {
+        return;
+    }
+    suggestions.push(format!(
+        "relax the requirement `{} = \"{}\"` in `{}` to allow version {}",
+        dep.name_in_toml(),
+        dep.version_req(),
+        manifest.display(),
+        version
+    ));
+}
+
+impl fmt::Display for ConflictExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conflicting requirements:")?;
+        for req in &self.requirements {
+            write!(
+                f,
+                "\n  `{} = \"{}\"` required by `{}`",
+                req.package, req.req, req.required_by
+            )?;
+            if let Some(selected) = &req.selected {
+                write!(f, ", which selected version {}", selected)?;
+                if req.reason == RequirementReason::Links {
+                    write!(f, " (the native library is linked twice)")?;
+                }
+            }
+            if req.member != req.required_by {
+                write!(f, "\n      introduced by `{}`", req.member)?;
+            }
+            if let Some(manifest) = &req.manifest {
+                write!(f, "\n      in `{}`", manifest.display())?;
+            }
+        }
+        if self.suggestions.is_empty() {
+            write!(f, "\n\nno change to the requirements could be suggested")?;
+        }
+        for suggestion in &self.suggestions {
+            write!(f, "\n\nhelp: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
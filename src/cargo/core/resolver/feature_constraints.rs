@@ -0,0 +1,427 @@
+//! Checks of the constraints declared on features, such as features which
+//! cannot be enabled together, after the features have been resolved.
+//!
+//! When a constraint is violated, the error shows how each of the features
+//! involved was enabled, as a path starting from a package selected on the
+//! command line. The path is found by searching backwards from the feature:
+//! a feature is enabled by another feature of the same package, by another
+//! feature of a dependent package (`dep/feature` syntax), or by the
+//! dependency declaration of a dependent package.
+//!
+//! The same search is used to warn about dependents enabling features which
+//! are marked private or unstable in the metadata of the feature.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use anyhow::bail;
+
+use crate::core::resolver::features::{CliFeatures, FeaturesFor, ResolvedFeatures};
+use crate::core::resolver::Resolve;
+use crate::core::{Dependency, FeatureValue, Package, PackageId, PackageSet};
+use crate::util::interning::InternedString;
+use crate::util::{CargoResult, Config};
+
+/// A package being built, or one of its features.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Node {
+    pkg_id: PackageId,
+    features_for: FeaturesFor,
+    feature: Option<InternedString>,
+}
+
+/// Checks the constraints declared on the features of all the packages in
+/// `package_set` which are built with `resolved`. `roots` are the selected
+/// packages, with the features requested for them on the command line.
+pub(super) fn check(
+    resolve: &Resolve,
+    package_set: &PackageSet<'_>,
+    resolved: &ResolvedFeatures,
+    roots: &[(&Package, CliFeatures)],
+) -> CargoResult<()> {
+    let mut pkgs: Vec<&Package> = package_set
+        .packages()
+        .filter(|pkg| !pkg.manifest().feature_constraints().is_empty())
+        .collect();
+    if pkgs.is_empty() {
+        return Ok(());
+    }
+    pkgs.sort_by_key(|pkg| pkg.package_id());
+    let paths = Paths {
+        resolve,
+        resolved,
+        roots,
+        dependents: dependents(resolve),
+    };
+    for pkg in pkgs {
+        let pkg_id = pkg.package_id();
+        for &features_for in &[FeaturesFor::NormalOrDev, FeaturesFor::HostDep] {
+            let features = match resolved.activated_features_unverified(pkg_id, features_for) {
+                Some(features) => features,
+                None => continue,
+            };
+            for (feature, constraints) in pkg.manifest().feature_constraints() {
+                if !features.contains(feature) {
+                    continue;
+                }
+                for other in &constraints.conflicts {
+                    if features.contains(other) {
+                        bail!(
+                            "features `{}` and `{}` of package `{}` cannot be enabled \
+                             together\n\n{}\n\n{}",
+                            feature,
+                            other,
+                            pkg_id,
+                            paths.describe(pkg_id, features_for, *feature),
+                            paths.describe(pkg_id, features_for, *other),
+                        );
+                    }
+                }
+                for required in &constraints.requires {
+                    if !features.contains(required) {
+                        bail!(
+                            "feature `{}` of package `{}` requires feature `{}`, \
+                             which is not enabled\n\n{}",
+                            feature,
+                            pkg_id,
+                            required,
+                            paths.describe(pkg_id, features_for, *feature),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Warns about the features marked private or unstable in `package_set`
+/// which are enabled by another local package, through its dependency
+/// declaration or its features.
+pub(super) fn warn_feature_metadata(
+    config: &Config,
+    resolve: &Resolve,
+    package_set: &PackageSet<'_>,
+    resolved: &ResolvedFeatures,
+    roots: &[(&Package, CliFeatures)],
+) -> CargoResult<()> {
+    let pkgs: Vec<&Package> = package_set
+        .packages()
+        .filter(|pkg| {
+            pkg.manifest()
+                .feature_metadata()
+                .values()
+                .any(|metadata| !metadata.public || metadata.unstable)
+        })
+        .collect();
+    if pkgs.is_empty() {
+        return Ok(());
+    }
+    let paths = Paths {
+        resolve,
+        resolved,
+        roots,
+        dependents: dependents(resolve),
+    };
+    let mut warnings = BTreeSet::new();
+    for pkg in pkgs {
+        let pkg_id = pkg.package_id();
+        for &features_for in &[FeaturesFor::NormalOrDev, FeaturesFor::HostDep] {
+            let features = match resolved.activated_features_unverified(pkg_id, features_for) {
+                Some(features) => features,
+                None => continue,
+            };
+            for (feature, metadata) in pkg.manifest().feature_metadata() {
+                if !features.contains(feature) {
+                    continue;
+                }
+                let kind = match (metadata.public, metadata.unstable) {
+                    (true, false) => continue,
+                    (false, false) => "private",
+                    (true, true) => "unstable",
+                    (false, true) => "private and unstable",
+                };
+                let node = Node {
+                    pkg_id,
+                    features_for,
+                    feature: Some(*feature),
+                };
+                for pred in paths.predecessors(node) {
+                    if pred.pkg_id != pkg_id && pred.pkg_id.source_id().is_path() {
+                        warnings.insert((pkg_id, *feature, kind, pred.pkg_id));
+                    }
+                }
+            }
+        }
+    }
+    for (pkg_id, feature, kind, dependent) in warnings {
+        config.shell().warn(format!(
+            "feature `{}` of package `{}` is {}, but is enabled by `{}`",
+            feature, pkg_id, kind, dependent
+        ))?;
+    }
+    Ok(())
+}
+
+/// Returns the packages depending on each package, with their dependency
+/// declarations.
+fn dependents(resolve: &Resolve) -> HashMap<PackageId, Vec<(PackageId, &Dependency)>> {
+    let mut dependents: HashMap<_, Vec<_>> = HashMap::new();
+    for pkg_id in resolve.iter() {
+        for (dep_id, deps) in resolve.deps(pkg_id) {
+            for dep in deps {
+                dependents.entry(dep_id).or_default().push((pkg_id, dep));
+            }
+        }
+    }
+    for list in dependents.values_mut() {
+        list.sort_by_key(|(pkg_id, dep)| (*pkg_id, dep.name_in_toml()));
+    }
+    dependents
+}
+
+struct Paths<'a, 'b> {
+    resolve: &'a Resolve,
+    resolved: &'a ResolvedFeatures,
+    roots: &'a [(&'b Package, CliFeatures)],
+    dependents: HashMap<PackageId, Vec<(PackageId, &'a Dependency)>>,
+}
+
+impl<'a, 'b> Paths<'a, 'b> {
+    /// Describes the path from a selected package which enables `feature`.
+    fn describe(
+        &self,
+        pkg_id: PackageId,
+        features_for: FeaturesFor,
+        feature: InternedString,
+    ) -> String {
+        let target = Node {
+            pkg_id,
+            features_for,
+            feature: Some(feature),
+        };
+        let mut s = format!("feature `{}` is enabled by:", feature);
+        match self.find_path(target) {
+            Some((path, reason)) => {
+                for (i, node) in path.iter().enumerate() {
+                    write!(s, "\n  {}", node.pkg_id).unwrap();
+                    if let Some(feature) = node.feature {
+                        write!(s, " feature `{}`", feature).unwrap();
+                    }
+                    if i == 0 {
+                        write!(s, " ({})", reason).unwrap();
+                    }
+                }
+            }
+            None => write!(s, "\n  {} feature `{}`", pkg_id, feature).unwrap(),
+        }
+        s
+    }
+
+    /// Searches backwards from `target` for the shortest path from a selected
+    /// package, returning the path and why its first node is enabled.
+    fn find_path(&self, target: Node) -> Option<(Vec<Node>, &'static str)> {
+        let mut next: HashMap<Node, Node> = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(target);
+        queue.push_back(target);
+        while let Some(node) = queue.pop_front() {
+            if let Some(reason) = self.root_reason(node) {
+                let mut path = vec![node];
+                let mut current = node;
+                while let Some(&n) = next.get(&current) {
+                    path.push(n);
+                    current = n;
+                }
+                return Some((path, reason));
+            }
+            for pred in self.predecessors(node) {
+                if visited.insert(pred) {
+                    next.insert(pred, node);
+                    queue.push_back(pred);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns why `node` is enabled directly by the command line, if it is.
+    fn root_reason(&self, node: Node) -> Option<&'static str> {
+        let (_, cli_features) = self
+            .roots
+            .iter()
+            .find(|(pkg, _)| pkg.package_id() == node.pkg_id)?;
+        let feature = match node.feature {
+            Some(feature) => feature,
+            None => return Some("selected"),
+        };
+        if cli_features.all_features {
+            Some("enabled with --all-features")
+        } else if cli_features
+            .features
+            .contains(&FeatureValue::Feature(feature))
+        {
+            Some("enabled with --features")
+        } else if feature == "default" && cli_features.uses_default_features {
+            Some("default feature of a selected package")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the activated nodes which may directly enable `node`.
+    fn predecessors(&self, node: Node) -> Vec<Node> {
+        let mut preds = Vec::new();
+        match node.feature {
+            Some(feature) => {
+                // Other features of the same package.
+                for (name, fvs) in self.resolve.summary(node.pkg_id).features() {
+                    let enables = fvs.iter().any(|fv| match fv {
+                        FeatureValue::Feature(f) => *f == feature,
+                        FeatureValue::DepFeature {
+                            dep_name,
+                            dep_prefix: false,
+                            weak: false,
+                            ..
+                        } => *dep_name == feature,
+                        _ => false,
+                    });
+                    if enables {
+                        self.push_feature(&mut preds, node.pkg_id, node.features_for, *name);
+                    }
+                }
+                // Dependents, through their dependency declarations or
+                // their features.
+                for &(parent, dep) in self.dependents_of(node.pkg_id) {
+                    for features_for in self.activated_for(parent) {
+                        if !self.is_dep_activated(parent, features_for, dep) {
+                            continue;
+                        }
+                        let enabled_by_cli = self.roots.iter().any(|(pkg, cli_features)| {
+                            pkg.package_id() == parent
+                                && cli_features.features.iter().any(|fv| match fv {
+                                    FeatureValue::DepFeature {
+                                        dep_name,
+                                        dep_feature,
+                                        ..
+                                    } => *dep_name == dep.name_in_toml() && *dep_feature == feature,
+                                    _ => false,
+                                })
+                        });
+                        if dep.features().contains(&feature)
+                            || (feature == "default" && dep.uses_default_features())
+                            || enabled_by_cli
+                        {
+                            preds.push(Node {
+                                pkg_id: parent,
+                                features_for,
+                                feature: None,
+                            });
+                        }
+                        for (name, fvs) in self.resolve.summary(parent).features() {
+                            let enables = fvs.iter().any(|fv| match fv {
+                                FeatureValue::DepFeature {
+                                    dep_name,
+                                    dep_feature,
+                                    ..
+                                } => *dep_name == dep.name_in_toml() && *dep_feature == feature,
+                                _ => false,
+                            });
+                            if enables {
+                                self.push_feature(&mut preds, parent, features_for, *name);
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                // Dependents, through their non-optional dependency
+                // declarations, or the features enabling an optional one.
+                for &(parent, dep) in self.dependents_of(node.pkg_id) {
+                    for features_for in self.activated_for(parent) {
+                        if !self.is_dep_activated(parent, features_for, dep) {
+                            continue;
+                        }
+                        if !dep.is_optional() {
+                            preds.push(Node {
+                                pkg_id: parent,
+                                features_for,
+                                feature: None,
+                            });
+                            continue;
+                        }
+                        for (name, fvs) in self.resolve.summary(parent).features() {
+                            let enables = fvs.iter().any(|fv| match fv {
+                                FeatureValue::Dep { dep_name }
+                                | FeatureValue::DepFeature {
+                                    dep_name,
+                                    weak: false,
+                                    ..
+                                } => *dep_name == dep.name_in_toml(),
+                                _ => false,
+                            });
+                            if enables {
+                                self.push_feature(&mut preds, parent, features_for, *name);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        preds
+    }
+
+    fn push_feature(
+        &self,
+        preds: &mut Vec<Node>,
+        pkg_id: PackageId,
+        features_for: FeaturesFor,
+        feature: InternedString,
+    ) {
+        let is_activated = self
+            .resolved
+            .activated_features_unverified(pkg_id, features_for)
+            .map_or(false, |features| features.contains(&feature));
+        if is_activated {
+            preds.push(Node {
+                pkg_id,
+                features_for,
+                feature: Some(feature),
+            });
+        }
+    }
+
+    fn dependents_of(&self, pkg_id: PackageId) -> &[(PackageId, &'a Dependency)] {
+        self.dependents
+            .get(&pkg_id)
+            .map(|list| list.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns how `pkg_id` is built. Build dependencies and proc-macros may
+    /// be built with different features than normal dependencies.
+    fn activated_for(&self, pkg_id: PackageId) -> Vec<FeaturesFor> {
+        [FeaturesFor::NormalOrDev, FeaturesFor::HostDep]
+            .iter()
+            .copied()
+            .filter(|&features_for| {
+                self.resolved
+                    .activated_features_unverified(pkg_id, features_for)
+                    .is_some()
+            })
+            .collect()
+    }
+
+    fn is_dep_activated(
+        &self,
+        parent: PackageId,
+        features_for: FeaturesFor,
+        dep: &Dependency,
+    ) -> bool {
+        !dep.is_optional()
+            || self
+                .resolved
+                .is_dep_activated(parent, features_for, dep.name_in_toml())
+    }
+}
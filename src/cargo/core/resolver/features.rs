@@ -37,9 +37,10 @@
 
 use crate::core::compiler::{CompileKind, RustcTargetData};
 use crate::core::dependency::{DepKind, Dependency};
+use crate::core::resolver::feature_constraints;
 use crate::core::resolver::types::FeaturesSet;
 use crate::core::resolver::{Resolve, ResolveBehavior};
-use crate::core::{FeatureValue, PackageId, PackageIdSpec, PackageSet, Workspace};
+use crate::core::{FeatureValue, Package, PackageId, PackageIdSpec, PackageSet, Workspace};
 use crate::util::interning::InternedString;
 use crate::util::CargoResult;
 use anyhow::bail;
@@ -67,11 +68,34 @@ pub struct ResolvedFeatures {
     ///
     /// This is the set of optional dependencies enabled for each package.
     legacy_dependencies: Option<HashMap<PackageId, HashSet<InternedString>>>,
+    /// The features resolved separately for each selected workspace member,
+    /// with `FeatureUnification::PerRoot`.
+    roots: HashMap<PackageId, ResolvedFeatures>,
     opts: FeatureOpts,
 }
 
+/// How features are unified between the workspace members.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FeatureUnification {
+    /// The features of all the selected members are unified.
+    Selected,
+    /// The features are resolved separately for each selected member, so
+    /// that a member is built the same way as when it is selected alone.
+    PerRoot,
+    /// The features of all the workspace members are unified, even if they
+    /// are not selected, so that a member is built the same way as when the
+    /// whole workspace is selected.
+    Workspace,
+}
+
+impl Default for FeatureUnification {
+    fn default() -> FeatureUnification {
+        FeatureUnification::Selected
+    }
+}
+
 /// Options for how the feature resolver works.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct FeatureOpts {
     /// Use the new resolver instead of the old one.
     new_resolver: bool,
@@ -83,6 +107,8 @@ pub struct FeatureOpts {
     ignore_inactive_targets: bool,
     /// If enabled, compare against old resolver (for testing).
     compare: bool,
+    /// How features are unified between the workspace members.
+    unification: FeatureUnification,
 }
 
 /// Flag to indicate if Cargo is building *any* dev units (tests, examples, etc.).
@@ -106,7 +132,7 @@ pub enum ForceAllTargets {
 }
 
 /// Flag to indicate if features are requested for a build dependency or not.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum FeaturesFor {
     NormalOrDev,
     /// Build dependency or proc-macro.
@@ -144,7 +170,8 @@ impl FeatureOpts {
                         opts.ignore_inactive_targets = true;
                     }
                     "compare" => opts.compare = true,
-                    "ws" => unimplemented!(),
+                    "ws" => opts.unification = FeatureUnification::Workspace,
+                    "root" => opts.unification = FeatureUnification::PerRoot,
                     s => bail!("-Zfeatures flag `{}` is not supported", s),
                 }
             }
@@ -182,6 +209,12 @@ impl FeatureOpts {
         Ok(opts)
     }
 
+    /// Whether the features of all the workspace members are unified, which
+    /// requires the dependencies of every member to be resolved.
+    pub fn unifies_workspace(&self) -> bool {
+        self.unification == FeatureUnification::Workspace
+    }
+
     /// Creates a new FeatureOpts for the given behavior.
     pub fn new_behavior(behavior: ResolveBehavior, has_dev_units: HasDevUnits) -> FeatureOpts {
         match behavior {
@@ -192,6 +225,7 @@ impl FeatureOpts {
                 decouple_dev_deps: has_dev_units == HasDevUnits::No,
                 ignore_inactive_targets: true,
                 compare: false,
+                unification: FeatureUnification::Selected,
             },
         }
     }
@@ -289,6 +323,20 @@ impl CliFeatures {
 }
 
 impl ResolvedFeatures {
+    /// Returns the features resolved for building the given root package.
+    ///
+    /// This is the features of the root alone with
+    /// `FeatureUnification::PerRoot`, and `self` otherwise.
+    pub fn for_root(&self, root: PackageId) -> &ResolvedFeatures {
+        self.roots.get(&root).unwrap_or(self)
+    }
+
+    /// Whether the features are resolved separately for each root, see
+    /// `for_root`.
+    pub fn is_per_root(&self) -> bool {
+        !self.roots.is_empty()
+    }
+
     /// Returns the list of features that are enabled for the given package.
     pub fn activated_features(
         &self,
@@ -463,65 +511,135 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         use crate::util::profile;
         let _p = profile::start("resolve features");
 
+        let member_features = ws.members_with_features(specs, cli_features)?;
         if !opts.new_resolver {
             // Legacy mode.
-            return Ok(ResolvedFeatures {
+            let resolved = ResolvedFeatures {
                 activated_features: HashMap::new(),
                 activated_dependencies: HashMap::new(),
                 legacy_features: Some(resolve.features_clone()),
                 legacy_dependencies: Some(compute_legacy_deps(resolve)),
+                roots: HashMap::new(),
                 opts,
-            });
+            };
+            feature_constraints::check(resolve, package_set, &resolved, &member_features)?;
+            feature_constraints::warn_feature_metadata(
+                ws.config(),
+                resolve,
+                package_set,
+                &resolved,
+                &member_features,
+            )?;
+            return Ok(resolved);
         }
-        let track_for_host = opts.decouple_host_deps || opts.ignore_inactive_targets;
-        let mut r = FeatureResolver {
+        let new = |opts: &FeatureOpts| FeatureResolver {
             ws,
             target_data,
             requested_targets,
             resolve,
             package_set,
-            opts,
+            opts: opts.clone(),
             activated_features: HashMap::new(),
             activated_dependencies: HashMap::new(),
             processed_deps: HashSet::new(),
-            track_for_host,
+            track_for_host: opts.decouple_host_deps || opts.ignore_inactive_targets,
             deferred_weak_dependencies: HashMap::new(),
         };
-        r.do_resolve(specs, cli_features)?;
+        // The union of the features of all roots is still resolved below, for
+        // the users of the features which are not specific to a root.
+        let mut roots = HashMap::new();
+        if opts.unification == FeatureUnification::PerRoot && member_features.len() > 1 {
+            for member in &member_features {
+                let mut r = new(&opts);
+                let member = std::slice::from_ref(member);
+                r.do_resolve(member)?;
+                let resolved = r.into_resolved(HashMap::new());
+                feature_constraints::check(resolve, package_set, &resolved, member)?;
+                roots.insert(member[0].0.package_id(), resolved);
+            }
+        }
+        let mut r = new(&opts);
+        r.do_resolve(&member_features)?;
         log::debug!("features={:#?}", r.activated_features);
         if r.opts.compare {
             r.compare();
         }
-        Ok(ResolvedFeatures {
-            activated_features: r.activated_features,
-            activated_dependencies: r.activated_dependencies,
+        let resolved = r.into_resolved(roots);
+        // With per-root unification, the features of different roots may
+        // conflict, as they are never unified.
+        if resolved.roots.is_empty() {
+            let mut selected = member_features.clone();
+            if opts.unification == FeatureUnification::Workspace {
+                for member in ws.members() {
+                    let is_selected = selected
+                        .iter()
+                        .any(|(m, _)| m.package_id() == member.package_id());
+                    if !is_selected {
+                        selected.push((member, CliFeatures::new_all(false)));
+                    }
+                }
+            }
+            feature_constraints::check(resolve, package_set, &resolved, &selected)?;
+        }
+        feature_constraints::warn_feature_metadata(
+            ws.config(),
+            resolve,
+            package_set,
+            &resolved,
+            &member_features,
+        )?;
+        Ok(resolved)
+    }
+
+    fn into_resolved(self, roots: HashMap<PackageId, ResolvedFeatures>) -> ResolvedFeatures {
+        ResolvedFeatures {
+            activated_features: self.activated_features,
+            activated_dependencies: self.activated_dependencies,
             legacy_features: None,
             legacy_dependencies: None,
-            opts: r.opts,
-        })
+            roots,
+            opts: self.opts,
+        }
     }
 
     /// Performs the process of resolving all features for the resolve graph.
-    fn do_resolve(
+    fn do_resolve(&mut self, member_features: &[(&Package, CliFeatures)]) -> CargoResult<()> {
+        for (member, cli_features) in member_features {
+            self.activate_member(member.package_id(), cli_features)?;
+        }
+        if self.opts.unification == FeatureUnification::Workspace {
+            // Activate the default features of the members which are not
+            // selected, as if the whole workspace was being built.
+            let default_features = CliFeatures::new_all(false);
+            for member in self.ws.members() {
+                let is_selected = member_features
+                    .iter()
+                    .any(|(m, _)| m.package_id() == member.package_id());
+                if !is_selected {
+                    self.activate_member(member.package_id(), &default_features)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn activate_member(
         &mut self,
-        specs: &[PackageIdSpec],
+        pkg_id: PackageId,
         cli_features: &CliFeatures,
     ) -> CargoResult<()> {
-        let member_features = self.ws.members_with_features(specs, cli_features)?;
-        for (member, cli_features) in &member_features {
-            let fvs = self.fvs_from_requested(member.package_id(), cli_features);
-            let for_host = self.track_for_host && self.is_proc_macro(member.package_id());
-            self.activate_pkg(member.package_id(), for_host, &fvs)?;
-            if for_host {
-                // Also activate without for_host. This is needed if the
-                // proc-macro includes other targets (like binaries or tests),
-                // or running in `cargo test`. Note that in a workspace, if
-                // the proc-macro is selected on the command like (like with
-                // `--workspace`), this forces feature unification with normal
-                // dependencies. This is part of the bigger problem where
-                // features depend on which packages are built.
-                self.activate_pkg(member.package_id(), false, &fvs)?;
-            }
+        let fvs = self.fvs_from_requested(pkg_id, cli_features);
+        let for_host = self.track_for_host && self.is_proc_macro(pkg_id);
+        self.activate_pkg(pkg_id, for_host, &fvs)?;
+        if for_host {
+            // Also activate without for_host. This is needed if the
+            // proc-macro includes other targets (like binaries or tests),
+            // or running in `cargo test`. Note that in a workspace, if
+            // the proc-macro is selected on the command like (like with
+            // `--workspace`), this forces feature unification with normal
+            // dependencies. This is part of the bigger problem where
+            // features depend on which packages are built.
+            self.activate_pkg(pkg_id, false, &fvs)?;
         }
         Ok(())
     }
@@ -69,6 +69,9 @@ use self::types::{FeaturesSet, RcVecIter, RemainingDeps, ResolverProgress};
 pub use self::encode::Metadata;
 pub use self::encode::{EncodableDependency, EncodablePackageId, EncodableResolve};
 pub use self::errors::{ActivateError, ActivateResult, ResolveError};
+pub use self::explain::{
+    ConflictExplanation, ConflictRequirement, ExplainFormat, RequirementReason,
+};
 pub use self::features::{CliFeatures, ForceAllTargets, HasDevUnits};
 pub use self::resolve::{Resolve, ResolveVersion};
 pub use self::types::{ResolveBehavior, ResolveOpts};
@@ -78,6 +81,8 @@ mod context;
 mod dep_cache;
 mod encode;
 mod errors;
+mod explain;
+mod feature_constraints;
 pub mod features;
 mod resolve;
 mod types;
@@ -119,6 +124,10 @@ mod types;
 ///
 ///     When we have a decision for how to implement is without breaking existing functionality
 ///     this flag can be removed.
+///
+/// * `direct_minimal_versions` - prefer the minimal versions of the direct
+///     dependencies of the `summaries`, and the maximal versions of everything
+///     else. This is also enabled by `-Z direct-minimal-versions`.
 pub fn resolve(
     summaries: &[(Summary, ResolveOpts)],
     replacements: &[(PackageIdSpec, Dependency)],
@@ -126,14 +135,24 @@ pub fn resolve(
     try_to_use: &HashSet<PackageId>,
     config: Option<&Config>,
     check_public_visible_dependencies: bool,
+    direct_minimal_versions: bool,
 ) -> CargoResult<Resolve> {
     let cx = Context::new(check_public_visible_dependencies);
     let _p = profile::start("resolving");
-    let minimal_versions = match config {
-        Some(config) => config.cli_unstable().minimal_versions,
-        None => false,
+    let (minimal_versions, direct_minimal_versions) = match config {
+        Some(config) => (
+            config.cli_unstable().minimal_versions,
+            direct_minimal_versions || config.cli_unstable().direct_minimal_versions,
+        ),
+        None => (false, direct_minimal_versions),
     };
-    let mut registry = RegistryQueryer::new(registry, replacements, try_to_use, minimal_versions);
+    let mut registry = RegistryQueryer::new(
+        registry,
+        replacements,
+        try_to_use,
+        minimal_versions,
+        direct_minimal_versions,
+    );
     let cx = activate_deps_loop(cx, &mut registry, summaries, config)?;
 
     let mut cksums = HashMap::new();
@@ -334,6 +353,7 @@ fn activate_deps_loop(
                         Err(errors::activation_error(
                             &cx,
                             registry.registry,
+                            registry.try_to_use,
                             &parent,
                             &dep,
                             &conflicting_activations,
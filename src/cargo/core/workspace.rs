@@ -10,7 +10,7 @@ use itertools::Itertools;
 use log::debug;
 use url::Url;
 
-use crate::core::features::Features;
+use crate::core::features::{Feature, Features};
 use crate::core::registry::PackageRegistry;
 use crate::core::resolver::features::CliFeatures;
 use crate::core::resolver::ResolveBehavior;
@@ -89,6 +89,11 @@ pub struct Workspace<'cfg> {
     // file. This is set for `cargo install` without `--locked`.
     ignore_lock: bool,
 
+    // If `true`, then the resolver prefers the minimal versions of the direct
+    // dependencies of the members. This is set when `cargo package` verifies
+    // with `--minimal-versions`.
+    direct_minimal_versions: bool,
+
     /// The resolver behavior specified with the `resolver` field.
     resolve_behavior: ResolveBehavior,
 
@@ -181,6 +186,7 @@ impl<'cfg> Workspace<'cfg> {
             require_optional_deps: true,
             loaded_packages: RefCell::new(HashMap::new()),
             ignore_lock: false,
+            direct_minimal_versions: false,
             resolve_behavior: ResolveBehavior::V1,
             custom_metadata: None,
         }
@@ -549,6 +555,20 @@ impl<'cfg> Workspace<'cfg> {
         self
     }
 
+    pub fn direct_minimal_versions(&self) -> bool {
+        self.direct_minimal_versions
+    }
+
+    /// Resolves the minimal versions of the direct dependencies of the
+    /// members, as with `-Z direct-minimal-versions`.
+    pub fn set_direct_minimal_versions(
+        &mut self,
+        direct_minimal_versions: bool,
+    ) -> &mut Workspace<'cfg> {
+        self.direct_minimal_versions = direct_minimal_versions;
+        self
+    }
+
     pub fn custom_metadata(&self) -> Option<&toml::Value> {
         self.custom_metadata.as_ref()
     }
@@ -790,6 +810,18 @@ impl<'cfg> Workspace<'cfg> {
         self.resolve_behavior
     }
 
+    /// Returns whether the workspace root or one of the members marks its
+    /// dependencies public or private, in which case the resolver makes
+    /// sure that each package can see only one version of a public
+    /// dependency.
+    pub fn uses_public_dependency(&self) -> bool {
+        let uses = |features: &Features| features.require(Feature::public_dependency()).is_ok();
+        uses(self.unstable_features())
+            || self
+                .members()
+                .any(|pkg| uses(pkg.manifest().unstable_features()))
+    }
+
     /// Returns `true` if this workspace uses the new CLI features behavior.
     ///
     /// The old behavior only allowed choosing the features from the package
@@ -0,0 +1,162 @@
+//! Selecting the workspace members affected by the changes in a range of git
+//! revisions, for `--affected`.
+//!
+//! A changed file belongs to the member with the innermost root containing it,
+//! if the file is one of the files of that member, as listed by `PathSource`.
+//! Changes to files shared by the whole workspace, like `Cargo.lock`, affect
+//! every member. A member is affected if it is changed, or if it depends on an
+//! affected member, through any kind of dependency.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::core::{Package, PackageId, PackageIdSpec, Workspace};
+use crate::ops;
+use crate::sources::PathSource;
+use crate::util::errors::CargoResult;
+
+/// Returns the package ID specs of the members of `ws` affected by the
+/// changes in `revs`.
+///
+/// `revs` is either a range like `main..HEAD` or `main...HEAD`, or a single
+/// revision, which is compared with the working directory.
+pub fn affected_packages(ws: &Workspace<'_>, revs: &str) -> CargoResult<Vec<String>> {
+    let target_dir = ws.target_dir().into_path_unlocked();
+    let changed: Vec<PathBuf> = changed_files(ws.root(), revs)?
+        .into_iter()
+        .filter(|path| !path.starts_with(&target_dir))
+        .collect();
+    let members: Vec<&Package> = ws.members().collect();
+
+    let mut affected: HashSet<PackageId> = HashSet::new();
+    if changed.iter().any(|path| is_workspace_file(ws, path)) {
+        affected.extend(members.iter().map(|pkg| pkg.package_id()));
+    } else {
+        let mut files = HashMap::new();
+        for path in &changed {
+            let owner = members
+                .iter()
+                .filter(|pkg| path.starts_with(pkg.root()))
+                .max_by_key(|pkg| pkg.root().components().count());
+            let owner = match owner {
+                Some(owner) => owner,
+                None => continue,
+            };
+            if !files.contains_key(&owner.package_id()) {
+                let source =
+                    PathSource::new(owner.root(), owner.package_id().source_id(), ws.config());
+                let list: HashSet<PathBuf> = source.list_files(owner)?.into_iter().collect();
+                files.insert(owner.package_id(), list);
+            }
+            // Deleted files are not listed anymore, but were part of the
+            // package as far as we know.
+            if files[&owner.package_id()].contains(path) || !path.exists() {
+                affected.insert(owner.package_id());
+            }
+        }
+
+        // Adds the members depending on the affected ones, until there are
+        // no new ones.
+        let (_, resolve) = ops::resolve_ws(ws)?;
+        let mut pending: Vec<PackageId> = affected.iter().copied().collect();
+        while let Some(id) = pending.pop() {
+            for member in &members {
+                let member_id = member.package_id();
+                if !affected.contains(&member_id)
+                    && resolve.deps(member_id).any(|(dep, _)| dep == id)
+                {
+                    affected.insert(member_id);
+                    pending.push(member_id);
+                }
+            }
+        }
+    }
+
+    let names: BTreeSet<_> = members
+        .iter()
+        .filter(|pkg| affected.contains(&pkg.package_id()))
+        .map(|pkg| pkg.name())
+        .collect();
+    let mut shell = ws.config().shell();
+    shell.status(
+        "Affected",
+        format!(
+            "{} of {} workspace members by changes in `{}`",
+            names.len(),
+            members.len(),
+            revs
+        ),
+    )?;
+    if !names.is_empty() {
+        shell.verbose(|shell| {
+            let names: Vec<_> = names.iter().map(|name| name.as_str()).collect();
+            shell.status("Affected", names.join(", "))
+        })?;
+    }
+
+    Ok(members
+        .iter()
+        .filter(|pkg| affected.contains(&pkg.package_id()))
+        .map(|pkg| PackageIdSpec::from_package_id(pkg.package_id()).to_string())
+        .collect())
+}
+
+/// Whether a change to `path` affects every member of the workspace.
+fn is_workspace_file(ws: &Workspace<'_>, path: &Path) -> bool {
+    let root = ws.root();
+    path == ws.root_manifest()
+        || path == root.join("Cargo.lock")
+        || path == root.join(".cargo/config")
+        || path == root.join(".cargo/config.toml")
+}
+
+/// Returns the absolute paths of the files changed in `revs`, in the git
+/// repository containing `root`.
+fn changed_files(root: &Path, revs: &str) -> CargoResult<Vec<PathBuf>> {
+    let repo = git2::Repository::discover(root).with_context(|| {
+        format!(
+            "failed to find a git repository containing `{}`",
+            root.display()
+        )
+    })?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.to_path_buf(),
+        None => anyhow::bail!("`--affected` cannot be used in a bare git repository"),
+    };
+    let spec = repo
+        .revparse(revs)
+        .with_context(|| format!("failed to parse the git revisions `{}`", revs))?;
+
+    let diff = match (spec.from(), spec.to()) {
+        (Some(from), Some(to)) => {
+            let from = if spec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+                let base =
+                    repo.merge_base(from.peel_to_commit()?.id(), to.peel_to_commit()?.id())?;
+                repo.find_commit(base)?.tree()?
+            } else {
+                from.peel_to_tree()?
+            };
+            repo.diff_tree_to_tree(Some(&from), Some(&to.peel_to_tree()?), None)?
+        }
+        (Some(from), None) => {
+            let mut opts = git2::DiffOptions::new();
+            opts.include_untracked(true).recurse_untracked_dirs(true);
+            repo.diff_tree_to_workdir_with_index(Some(&from.peel_to_tree()?), Some(&mut opts))?
+        }
+        _ => anyhow::bail!("failed to parse the git revisions `{}`", revs),
+    };
+
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        for file in &[delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path() {
+                files.push(workdir.join(path));
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
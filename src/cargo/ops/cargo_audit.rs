@@ -0,0 +1,412 @@
+//! Implementation of `cargo audit-lockfile`.
+//!
+//! This checks every package in `Cargo.lock` against a local copy of a
+//! security advisory database, such as a checkout of
+//! <https://github.com/RustSec/advisory-db>. Advisories are either Markdown
+//! files starting with a fenced ```` ```toml ```` block of metadata, with the
+//! title in the first `# ` heading, or plain TOML files:
+//!
+//! ```toml
+//! [advisory]
+//! id = "RUSTSEC-2021-0001"
+//! package = "bar"
+//! date = "2021-01-01"
+//! url = "https://example.com/advisory"
+//!
+//! [versions]
+//! patched = [">= 1.2.0"]
+//! unaffected = ["< 0.5.0"]
+//! ```
+//!
+//! Advisories only apply to packages from crates.io. Only `Cargo.lock` is
+//! read to check the packages and to show where they come from, so nothing is
+//! fetched from the network and no package has to be downloaded.
+
+use crate::core::{PackageId, Workspace};
+use crate::drop_println;
+use crate::ops;
+use crate::ops::tree::Charset;
+use crate::util::config::ConfigRelativePath;
+use crate::util::{CargoResult, Config, VersionExt};
+use anyhow::{bail, Context};
+use cargo_util::paths;
+use semver::{BuildMetadata, Op, Version, VersionReq};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub struct AuditOptions {
+    /// Path to the advisory database. If not set, the `audit.db` config
+    /// value is used.
+    pub db: Option<PathBuf>,
+}
+
+/// A security advisory for a package.
+struct Advisory {
+    id: String,
+    package: String,
+    title: Option<String>,
+    date: Option<String>,
+    url: Option<String>,
+    /// Set for advisories which are not about a vulnerability, such as
+    /// `unmaintained`. These are reported as warnings.
+    informational: Option<String>,
+    patched: Vec<VersionReq>,
+    unaffected: Vec<VersionReq>,
+}
+
+/// The format of an advisory file.
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: AdvisoryVersions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    title: Option<String>,
+    date: Option<String>,
+    url: Option<String>,
+    informational: Option<String>,
+    withdrawn: Option<String>,
+    /// Older advisories list the versions in the `advisory` table.
+    #[serde(default)]
+    patched_versions: Vec<String>,
+    #[serde(default)]
+    unaffected_versions: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct AdvisoryVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+impl Advisory {
+    fn affects(&self, version: &Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|req| req.matches(version))
+    }
+
+    /// Returns the lowest patched version which is newer than `version`.
+    fn minimum_patched(&self, version: &Version) -> Option<Version> {
+        self.patched
+            .iter()
+            .filter_map(lower_bound)
+            .filter(|v| v > version)
+            .min()
+    }
+}
+
+/// Returns the lowest version matching `req`, if it has a lower bound.
+fn lower_bound(req: &VersionReq) -> Option<Version> {
+    let mut bound: Option<Version> = None;
+    for cmp in &req.comparators {
+        let mut v = Version {
+            major: cmp.major,
+            minor: cmp.minor.unwrap_or(0),
+            patch: cmp.patch.unwrap_or(0),
+            pre: cmp.pre.clone(),
+            build: BuildMetadata::EMPTY,
+        };
+        match cmp.op {
+            Op::Exact | Op::GreaterEq | Op::Tilde | Op::Caret | Op::Wildcard => {}
+            Op::Greater => {
+                match (cmp.minor, cmp.patch) {
+                    (None, _) => {
+                        v.major += 1;
+                    }
+                    (Some(_), None) => {
+                        v.minor += 1;
+                    }
+                    (Some(_), Some(_)) => {
+                        if cmp.pre.is_empty() {
+                            v.patch += 1;
+                        }
+                    }
+                }
+                v.pre = semver::Prerelease::EMPTY;
+            }
+            _ => continue,
+        }
+        if bound.as_ref().map_or(true, |b| v > *b) {
+            bound = Some(v);
+        }
+    }
+    bound.filter(|v| req.matches(v))
+}
+
+/// Loads all advisories from the database at `db`, indexed by package name.
+fn load_advisories(db: &Path) -> CargoResult<BTreeMap<String, Vec<Advisory>>> {
+    if !db.is_dir() {
+        bail!("advisory database `{}` is not a directory", db.display());
+    }
+    let mut advisories: BTreeMap<String, Vec<Advisory>> = BTreeMap::new();
+    let walker = walkdir::WalkDir::new(db)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        // Skip `.git` and other hidden directories.
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+    for entry in walker {
+        let entry = entry
+            .with_context(|| format!("failed to read advisory database `{}`", db.display()))?;
+        let path = entry.path();
+        let is_markdown = match path.extension().and_then(|e| e.to_str()) {
+            Some("md") => true,
+            Some("toml") => false,
+            _ => continue,
+        };
+        let contents = paths::read(path)?;
+        let advisory = parse_advisory(&contents, is_markdown)
+            .with_context(|| format!("failed to parse advisory `{}`", path.display()))?;
+        if let Some(advisory) = advisory {
+            advisories
+                .entry(advisory.package.clone())
+                .or_default()
+                .push(advisory);
+        }
+    }
+    Ok(advisories)
+}
+
+/// Parses an advisory file, returning `None` for files which are not
+/// advisories (such as a README) or for withdrawn advisories.
+fn parse_advisory(contents: &str, is_markdown: bool) -> CargoResult<Option<Advisory>> {
+    let (metadata, title) = if is_markdown {
+        let rest = match contents.trim_start().strip_prefix("```toml") {
+            Some(rest) => rest,
+            None => return Ok(None),
+        };
+        let end = match rest.find("\n```") {
+            Some(end) => end,
+            None => bail!("the ```toml block is not closed"),
+        };
+        let title = rest[end + 4..]
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(|title| title.trim().to_string());
+        (&rest[..end], title)
+    } else {
+        (contents, None)
+    };
+    let value: toml::Value = toml::from_str(metadata)?;
+    if value.get("advisory").is_none() {
+        return Ok(None);
+    }
+    let file: AdvisoryFile = value.try_into()?;
+    let AdvisoryFile {
+        advisory,
+        mut versions,
+    } = file;
+    if advisory.withdrawn.is_some() {
+        return Ok(None);
+    }
+    versions.patched.extend(advisory.patched_versions);
+    versions.unaffected.extend(advisory.unaffected_versions);
+    let parse_reqs = |reqs: Vec<String>| {
+        reqs.iter()
+            .map(|req| {
+                VersionReq::parse(req)
+                    .with_context(|| format!("invalid version requirement `{}`", req))
+            })
+            .collect::<CargoResult<Vec<_>>>()
+    };
+    Ok(Some(Advisory {
+        id: advisory.id,
+        package: advisory.package,
+        title: title.or(advisory.title),
+        date: advisory.date,
+        url: advisory.url,
+        informational: advisory.informational,
+        patched: parse_reqs(versions.patched)?,
+        unaffected: parse_reqs(versions.unaffected)?,
+    }))
+}
+
+/// Checks `Cargo.lock` against the advisory database.
+///
+/// Returns an error if a locked package is affected by a vulnerability.
+pub fn audit(ws: &Workspace<'_>, opts: &AuditOptions) -> CargoResult<()> {
+    let config = ws.config();
+    let db = match &opts.db {
+        Some(db) => db.clone(),
+        None => match config.get::<Option<ConfigRelativePath>>("audit.db")? {
+            Some(db) => db.resolve_path(config),
+            None => bail!(
+                "no advisory database was specified\n\
+                 Pass the path to a local advisory database with `--db`, \
+                 or set `audit.db` in the config."
+            ),
+        },
+    };
+    let advisories = load_advisories(&db)?;
+    let resolve = match ops::load_pkg_lockfile(ws)? {
+        Some(resolve) => resolve,
+        None => bail!(
+            "a Cargo.lock must exist for this command\n\
+             Run `cargo generate-lockfile` to create it."
+        ),
+    };
+
+    let mut package_ids: Vec<PackageId> = resolve.iter().collect();
+    package_ids.sort();
+    let advisory_count: usize = advisories.values().map(|a| a.len()).sum();
+    config.shell().status(
+        "Scanning",
+        format!(
+            "{} locked packages against {} advisor{}",
+            package_ids.len(),
+            advisory_count,
+            if advisory_count == 1 { "y" } else { "ies" }
+        ),
+    )?;
+
+    let mut findings: Vec<(PackageId, &Advisory)> = Vec::new();
+    for id in package_ids {
+        if !id.source_id().is_default_registry() {
+            continue;
+        }
+        for advisory in advisories.get(id.name().as_str()).into_iter().flatten() {
+            if advisory.affects(id.version()) {
+                findings.push((id, advisory));
+            }
+        }
+    }
+    if findings.is_empty() {
+        return Ok(());
+    }
+
+    // Show every path to the affected packages, regardless of platform and
+    // features, since `Cargo.lock` includes all of them.
+    let mut dependents: HashMap<PackageId, Vec<PackageId>> = HashMap::new();
+    for id in resolve.iter() {
+        for (dep, _) in resolve.deps(id) {
+            dependents.entry(dep).or_default().push(id);
+        }
+    }
+    for deps in dependents.values_mut() {
+        deps.sort();
+        deps.dedup();
+    }
+    for (i, (id, advisory)) in findings.iter().enumerate() {
+        if i != 0 {
+            drop_println!(config);
+        }
+        print_finding(config, *id, advisory);
+        drop_println!(config);
+        print_dependents(
+            config,
+            &dependents,
+            *id,
+            &mut HashSet::new(),
+            &mut Vec::new(),
+        );
+    }
+
+    let (warnings, vulnerabilities): (Vec<_>, Vec<_>) = findings
+        .into_iter()
+        .partition(|(_, advisory)| advisory.informational.is_some());
+    if !warnings.is_empty() {
+        config.shell().warn(format!(
+            "found {} informational advisor{} for locked packages",
+            warnings.len(),
+            if warnings.len() == 1 { "y" } else { "ies" }
+        ))?;
+    }
+    if !vulnerabilities.is_empty() {
+        bail!(
+            "found {} vulnerabilit{} in locked packages",
+            vulnerabilities.len(),
+            if vulnerabilities.len() == 1 {
+                "y"
+            } else {
+                "ies"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Prints `id` and the packages depending on it, like `cargo tree --invert`.
+///
+/// Only `Cargo.lock` is used, so that nothing has to be downloaded.
+fn print_dependents(
+    config: &Config,
+    dependents: &HashMap<PackageId, Vec<PackageId>>,
+    id: PackageId,
+    visited: &mut HashSet<PackageId>,
+    levels_continue: &mut Vec<bool>,
+) {
+    let symbols = Charset::Utf8.symbols();
+    let mut prefix = String::new();
+    if let Some((last_continues, rest)) = levels_continue.split_last() {
+        for continues in rest {
+            let c = if *continues { symbols.down } else { " " };
+            prefix.push_str(&format!("{}   ", c));
+        }
+        let c = if *last_continues {
+            symbols.tee
+        } else {
+            symbols.ell
+        };
+        prefix.push_str(&format!("{0}{1}{1} ", c, symbols.right));
+    }
+
+    let parents = dependents.get(&id).map_or(&[][..], |parents| &parents[..]);
+    // Packages which were already displayed, such as in a cycle through
+    // dev-dependencies, are not expanded again.
+    if !visited.insert(id) {
+        let star = if parents.is_empty() { "" } else { " (*)" };
+        drop_println!(config, "{}{}{}", prefix, id, star);
+        return;
+    }
+    drop_println!(config, "{}{}", prefix, id);
+    for (i, parent) in parents.iter().enumerate() {
+        levels_continue.push(i + 1 < parents.len());
+        print_dependents(config, dependents, *parent, visited, levels_continue);
+        levels_continue.pop();
+    }
+}
+
+fn print_finding(config: &Config, id: PackageId, advisory: &Advisory) {
+    let title = advisory.title.as_deref().unwrap_or("no title");
+    match &advisory.informational {
+        Some(kind) => drop_println!(config, "{}: {} ({})", advisory.id, title, kind),
+        None => drop_println!(config, "{}: {}", advisory.id, title),
+    }
+    drop_println!(config, "  package: {}", id);
+    if let Some(date) = &advisory.date {
+        drop_println!(config, "  date: {}", date);
+    }
+    if let Some(url) = &advisory.url {
+        drop_println!(config, "  url: {}", url);
+    }
+    if !advisory.patched.is_empty() {
+        let patched: Vec<String> = advisory.patched.iter().map(|r| r.to_string()).collect();
+        drop_println!(config, "  patched: {}", patched.join(" or "));
+    }
+    match advisory.minimum_patched(id.version()) {
+        Some(version) if version.is_compatible_with(id.version()) => drop_println!(
+            config,
+            "  solution: upgrade to {} (`cargo update -p {}:{} --precise {}`)",
+            version,
+            id.name(),
+            id.version(),
+            version
+        ),
+        Some(version) => drop_println!(
+            config,
+            "  solution: upgrade to {} (the version requirement must be changed)",
+            version
+        ),
+        None => drop_println!(config, "  solution: no patched version is available"),
+    }
+}
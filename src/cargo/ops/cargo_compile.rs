@@ -920,7 +920,9 @@ fn generate_targets(
 
             // No need to worry about build-dependencies, roots are never build dependencies.
             let features_for = FeaturesFor::from_for_host(target.proc_macro());
-            let features = resolved_features.activated_features(pkg.package_id(), features_for);
+            let features = resolved_features
+                .for_root(pkg.package_id())
+                .activated_features(pkg.package_id(), features_for);
 
             // If `--target` has not been specified, then the unit
             // graph is built almost like if `--target $HOST` was
@@ -1114,7 +1116,12 @@ fn generate_targets(
                 )?;
 
                 let features = features_map.entry(pkg).or_insert_with(|| {
-                    resolve_all_features(resolve, resolved_features, package_set, pkg.package_id())
+                    resolve_all_features(
+                        resolve,
+                        resolved_features.for_root(pkg.package_id()),
+                        package_set,
+                        pkg.package_id(),
+                    )
                 });
                 rf.iter().filter(|f| !features.contains(*f)).collect()
             }
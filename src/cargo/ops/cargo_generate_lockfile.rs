@@ -1,15 +1,17 @@
 use std::collections::{BTreeMap, HashSet};
 
 use log::debug;
+use serde::Serialize;
 use termcolor::Color::{self, Cyan, Green, Red};
 
-use crate::core::registry::PackageRegistry;
+use crate::core::registry::{PackageRegistry, Registry};
 use crate::core::resolver::features::{CliFeatures, HasDevUnits};
-use crate::core::{PackageId, PackageIdSpec};
+use crate::core::{Dependency, PackageId, PackageIdSpec};
 use crate::core::{Resolve, SourceId, Workspace};
 use crate::ops;
 use crate::util::config::Config;
-use crate::util::CargoResult;
+use crate::util::interning::InternedString;
+use crate::util::{CargoResult, VersionExt};
 
 pub struct UpdateOptions<'a> {
     pub config: &'a Config,
@@ -18,6 +20,8 @@ pub struct UpdateOptions<'a> {
     pub aggressive: bool,
     pub dry_run: bool,
     pub workspace: bool,
+    /// Print a JSON report of the changes to stdout.
+    pub json_report: bool,
 }
 
 pub fn generate_lockfile(ws: &Workspace<'_>) -> CargoResult<()> {
@@ -53,6 +57,30 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         Some(resolve) => resolve,
         None => {
             match opts.precise {
+                None if opts.json_report => {
+                    // Everything is new, so report every package as added.
+                    let mut registry = PackageRegistry::new(opts.config)?;
+                    let mut resolve = ops::resolve_with_previous(
+                        &mut registry,
+                        ws,
+                        &CliFeatures::new_all(true),
+                        HasDevUnits::Yes,
+                        None,
+                        None,
+                        &[],
+                        true,
+                    )?;
+                    let changes = resolve.iter().map(|id| (Vec::new(), vec![id])).collect();
+                    print_report(opts, &mut registry, changes)?;
+                    if opts.dry_run {
+                        opts.config
+                            .shell()
+                            .warn("not updating lockfile due to dry run")?;
+                    } else {
+                        ops::write_pkg_lockfile(ws, &mut resolve)?;
+                    }
+                    return Ok(());
+                }
                 None => return generate_lockfile(ws),
 
                 // Precise option specified, so calculate a previous_resolve required
@@ -129,7 +157,8 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
     let print_change = |status: &str, msg: String, color: Color| {
         opts.config.shell().status_with_color(status, msg, color)
     };
-    for (removed, added) in compare_dependency_graphs(&previous_resolve, &resolve) {
+    let changes = compare_dependency_graphs(&previous_resolve, &resolve);
+    for (removed, added) in changes.iter() {
         if removed.len() == 1 && added.len() == 1 {
             let msg = if removed[0].source_id().is_git() {
                 format!(
@@ -150,6 +179,9 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
             }
         }
     }
+    if opts.json_report {
+        print_report(opts, &mut registry, changes)?;
+    }
     if opts.dry_run {
         opts.config
             .shell()
@@ -248,3 +280,156 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         changes.into_iter().map(|(_, v)| v).collect()
     }
 }
+
+/// Version of the JSON report, bumped on incompatible changes.
+const REPORT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct UpdateReport {
+    version: u32,
+    /// Whether `Cargo.lock` was left unchanged.
+    dry_run: bool,
+    added: Vec<ReportPackage>,
+    removed: Vec<ReportPackage>,
+    updated: Vec<ReportUpdate>,
+    /// Added or updated packages for which a newer, semver-incompatible
+    /// version is available. These can only be updated further by changing
+    /// the version requirement in a manifest.
+    held_back: Vec<ReportHeldBack>,
+}
+
+#[derive(Serialize)]
+struct ReportPackage {
+    name: InternedString,
+    #[serde(flatten)]
+    version: ReportVersion,
+}
+
+#[derive(Serialize)]
+struct ReportVersion {
+    version: String,
+    /// The source as written in `Cargo.lock`, `null` for path dependencies.
+    /// The URL of a git source ends with its locked commit, so that moving
+    /// to another commit shows in the report.
+    source: Option<String>,
+}
+
+impl ReportVersion {
+    fn new(id: PackageId) -> ReportVersion {
+        ReportVersion {
+            version: id.version().to_string(),
+            source: if id.source_id().is_path() {
+                None
+            } else {
+                Some(id.source_id().as_url().to_string())
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReportUpdate {
+    name: InternedString,
+    from: ReportVersion,
+    to: ReportVersion,
+}
+
+#[derive(Serialize)]
+struct ReportHeldBack {
+    name: InternedString,
+    #[serde(flatten)]
+    version: ReportVersion,
+    /// The newest version available in the registry.
+    latest: String,
+}
+
+/// Prints the JSON report of the changes to `Cargo.lock`.
+///
+/// `changes` is the list of `(removed, added)` packages for each package name
+/// and source. A package whose source changed is reported as updated if that
+/// is the only change for its name.
+fn print_report(
+    opts: &UpdateOptions<'_>,
+    registry: &mut PackageRegistry<'_>,
+    changes: Vec<(Vec<PackageId>, Vec<PackageId>)>,
+) -> CargoResult<()> {
+    let mut report = UpdateReport {
+        version: REPORT_VERSION,
+        dry_run: opts.dry_run,
+        added: Vec::new(),
+        removed: Vec::new(),
+        updated: Vec::new(),
+        held_back: Vec::new(),
+    };
+
+    // The new versions of the changed packages.
+    let mut changed: Vec<PackageId> = changes
+        .iter()
+        .flat_map(|(_, added)| added.iter().copied())
+        .filter(|id| id.source_id().is_registry())
+        .collect();
+    changed.sort();
+
+    // Group the changes by name to detect source changes.
+    let mut by_name: BTreeMap<InternedString, (Vec<PackageId>, Vec<PackageId>)> = BTreeMap::new();
+    for (removed, added) in changes {
+        if removed.len() == 1 && added.len() == 1 {
+            report.updated.push(ReportUpdate {
+                name: removed[0].name(),
+                from: ReportVersion::new(removed[0]),
+                to: ReportVersion::new(added[0]),
+            });
+            continue;
+        }
+        for id in removed {
+            by_name.entry(id.name()).or_default().0.push(id);
+        }
+        for id in added {
+            by_name.entry(id.name()).or_default().1.push(id);
+        }
+    }
+    for (name, (removed, added)) in by_name {
+        if removed.len() == 1 && added.len() == 1 {
+            report.updated.push(ReportUpdate {
+                name,
+                from: ReportVersion::new(removed[0]),
+                to: ReportVersion::new(added[0]),
+            });
+            continue;
+        }
+        report
+            .removed
+            .extend(removed.into_iter().map(|id| ReportPackage {
+                name,
+                version: ReportVersion::new(id),
+            }));
+        report
+            .added
+            .extend(added.into_iter().map(|id| ReportPackage {
+                name,
+                version: ReportVersion::new(id),
+            }));
+    }
+    report.updated.sort_by_key(|u| u.name);
+
+    for id in changed {
+        let dep = Dependency::parse(id.name(), None, id.source_id())?;
+        let latest = registry
+            .query_vec(&dep, false)?
+            .into_iter()
+            .map(|summary| summary.version().clone())
+            .filter(|version| !version.is_prerelease())
+            .max();
+        if let Some(latest) = latest {
+            if latest > *id.version() && !latest.is_compatible_with(id.version()) {
+                report.held_back.push(ReportHeldBack {
+                    name: id.name(),
+                    version: ReportVersion::new(id),
+                    latest: latest.to_string(),
+                });
+            }
+        }
+    }
+
+    opts.config.shell().print_json(&report)
+}
@@ -0,0 +1,386 @@
+//! Implementation of `cargo outdated-deps`.
+//!
+//! For every registry dependency declared by a workspace member, this
+//! compares the locked version with the newest version matching the
+//! requirement ("compatible") and the newest version overall ("latest").
+//! With `--upgrade`, requirements that exclude the latest version are
+//! rewritten in the manifests, and `Cargo.lock` is updated to match.
+//! Otherwise, `Cargo.lock` is left untouched.
+
+use crate::core::dependency::DepKind;
+use crate::core::registry::{PackageRegistry, Registry};
+use crate::core::resolver::{features::CliFeatures, HasDevUnits};
+use crate::core::{Dependency, SourceId, Workspace};
+use crate::drop_println;
+use crate::ops;
+use crate::util::interning::InternedString;
+use crate::util::toml::edit::ManifestEditor;
+use crate::util::{CargoResult, VersionExt};
+use anyhow::bail;
+use cargo_util::paths;
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The version of the JSON output, increased on incompatible changes.
+const REPORT_VERSION: u32 = 1;
+
+pub struct OutdatedOptions {
+    /// Print the report as JSON to stdout instead of a table.
+    pub json: bool,
+    /// Rewrite requirements in the manifests to the latest versions.
+    pub upgrade: bool,
+}
+
+#[derive(Serialize)]
+struct OutdatedReport {
+    version: u32,
+    dependencies: Vec<OutdatedDep>,
+}
+
+#[derive(Serialize)]
+struct OutdatedDep {
+    /// The workspace member declaring the dependency.
+    member: InternedString,
+    /// The name of the dependency in the manifest.
+    name: InternedString,
+    /// The name of the package, which differs from `name` if renamed.
+    package: InternedString,
+    kind: &'static str,
+    target: Option<String>,
+    req: String,
+    locked: Option<String>,
+    /// The newest version matching `req`.
+    compatible: Option<String>,
+    /// The newest version available.
+    latest: Option<String>,
+    #[serde(skip)]
+    manifest_path: PathBuf,
+    #[serde(skip)]
+    dep: Dependency,
+    #[serde(skip)]
+    latest_version: Option<Version>,
+}
+
+pub fn outdated(ws: &Workspace<'_>, opts: &OutdatedOptions) -> CargoResult<()> {
+    let config = ws.config();
+    let deps = {
+        let _lock = config.acquire_package_cache_lock()?;
+        let mut registry = PackageRegistry::new(config)?;
+        // Load the registries before the resolve locks them to `Cargo.lock`,
+        // so that their index is updated to find newer versions.
+        let sources: HashSet<SourceId> = ws
+            .members()
+            .flat_map(|member| member.dependencies())
+            .map(|dep| dep.source_id())
+            .filter(|source_id| source_id.is_registry())
+            .collect();
+        registry.add_sources(sources)?;
+        // Only report on the current resolve, without writing `Cargo.lock`.
+        let previous = ops::load_pkg_lockfile(ws)?;
+        let resolve = ops::resolve_with_previous(
+            &mut registry,
+            ws,
+            &CliFeatures::new_all(true),
+            HasDevUnits::Yes,
+            previous.as_ref(),
+            None,
+            &[],
+            true,
+        )?;
+
+        let mut deps = Vec::new();
+        for member in ws.members() {
+            // The dependencies in the resolve are locked to exact versions,
+            // so the requirements come from the manifest.
+            for dep in member.dependencies() {
+                if !dep.source_id().is_registry() {
+                    continue;
+                }
+                let locked = resolve
+                    .deps(member.package_id())
+                    .find(|(_, decls)| {
+                        decls.iter().any(|d| {
+                            d.name_in_toml() == dep.name_in_toml()
+                                && d.kind() == dep.kind()
+                                && d.platform() == dep.platform()
+                        })
+                    })
+                    .map(|(id, _)| id.version());
+                let (compatible, latest) = query_versions(&mut registry, dep)?;
+                let newer = |v: &Option<Version>| {
+                    v.as_ref()
+                        .map_or(false, |v| locked.map_or(true, |locked| v > locked))
+                };
+                if !newer(&compatible) && !newer(&latest) {
+                    continue;
+                }
+                deps.push(OutdatedDep {
+                    member: member.name(),
+                    name: dep.name_in_toml(),
+                    package: dep.package_name(),
+                    kind: kind_name(dep.kind()),
+                    target: dep.platform().map(|p| p.to_string()),
+                    req: dep.version_req().to_string(),
+                    locked: locked.map(|v| v.to_string()),
+                    compatible: compatible.map(|v| v.to_string()),
+                    latest: latest.as_ref().map(|v| v.to_string()),
+                    manifest_path: member.manifest_path().to_path_buf(),
+                    dep: dep.clone(),
+                    latest_version: latest,
+                });
+            }
+        }
+        deps.sort_by(|a, b| (a.member, a.name, a.kind).cmp(&(b.member, b.name, b.kind)));
+        deps
+    };
+
+    if opts.json {
+        let report = OutdatedReport {
+            version: REPORT_VERSION,
+            dependencies: deps,
+        };
+        config.shell().print_json(&report)?;
+        if opts.upgrade {
+            upgrade(ws, &report.dependencies)?;
+        }
+        return Ok(());
+    }
+
+    if deps.is_empty() {
+        config.shell().note("all dependencies are up to date")?;
+        return Ok(());
+    }
+    print_table(ws, &deps);
+    if opts.upgrade {
+        upgrade(ws, &deps)?;
+    }
+    Ok(())
+}
+
+/// Returns the newest version matching the requirement of `dep`, and the
+/// newest version overall. Pre-releases are ignored unless the requirement
+/// asks for one.
+fn query_versions(
+    registry: &mut PackageRegistry<'_>,
+    dep: &Dependency,
+) -> CargoResult<(Option<Version>, Option<Version>)> {
+    let query = Dependency::parse(dep.package_name(), None, dep.source_id())?;
+    let versions: Vec<Version> = registry
+        .query_vec(&query, false)?
+        .into_iter()
+        .map(|summary| summary.version().clone())
+        .collect();
+    let compatible = versions
+        .iter()
+        .filter(|v| dep.version_req().matches(v))
+        .max()
+        .cloned();
+    let latest = versions.into_iter().filter(|v| !v.is_prerelease()).max();
+    Ok((compatible, latest))
+}
+
+fn kind_name(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::Normal => "normal",
+        DepKind::Development => "dev",
+        DepKind::Build => "build",
+    }
+}
+
+fn print_table(ws: &Workspace<'_>, deps: &[OutdatedDep]) {
+    let or_dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+    let mut rows = vec![[
+        "Member".to_string(),
+        "Name".to_string(),
+        "Requirement".to_string(),
+        "Locked".to_string(),
+        "Compatible".to_string(),
+        "Latest".to_string(),
+        "Kind".to_string(),
+    ]];
+    for dep in deps {
+        let kind = match &dep.target {
+            Some(target) => format!("{} ({})", dep.kind, target),
+            None => dep.kind.to_string(),
+        };
+        rows.push([
+            dep.member.to_string(),
+            dep.name.to_string(),
+            dep.req.clone(),
+            or_dash(&dep.locked),
+            or_dash(&dep.compatible),
+            or_dash(&dep.latest),
+            kind,
+        ]);
+    }
+    let mut widths = [0; 7];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in &rows {
+        let mut line = String::new();
+        for (i, (width, cell)) in widths.iter().zip(row.iter()).enumerate() {
+            if i == row.len() - 1 {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+        }
+        drop_println!(ws.config(), "{}", line);
+    }
+}
+
+/// Rewrites the requirements that exclude the latest version, then updates
+/// `Cargo.lock` for the new requirements.
+fn upgrade(ws: &Workspace<'_>, deps: &[OutdatedDep]) -> CargoResult<()> {
+    let config = ws.config();
+    let mut by_manifest: BTreeMap<&Path, Vec<(&Dependency, &Version)>> = BTreeMap::new();
+    for dep in deps {
+        if let Some(latest) = &dep.latest_version {
+            if !dep.dep.version_req().matches(latest) {
+                by_manifest
+                    .entry(&dep.manifest_path)
+                    .or_default()
+                    .push((&dep.dep, latest));
+            }
+        }
+    }
+    if by_manifest.is_empty() {
+        return Ok(());
+    }
+
+    // Every requirement is located before writing any manifest, so that none
+    // is changed if one of them can't be edited.
+    let mut edited = Vec::new();
+    let mut upgraded = Vec::new();
+    for (path, upgrades) in by_manifest {
+        let display_path = path.strip_prefix(ws.root()).unwrap_or(path).display();
+        let contents = paths::read(path)?;
+        let mut manifest = ManifestEditor::new(&contents);
+        for (dep, latest) in upgrades {
+            match upgrade_dependency(&mut manifest, dep, latest) {
+                Ok(Some((from, to))) => upgraded.push(format!(
+                    "{} from `{}` to `{}` in {}",
+                    dep.name_in_toml(),
+                    from,
+                    to,
+                    display_path
+                )),
+                Ok(None) => bail!(
+                    "could not find the requirement of `{}` in `{}` to upgrade it\n\
+                     Only requirements written on a single line, as a string or \
+                     as the `version` of the dependency, can be upgraded. \
+                     No manifest was changed.",
+                    dep.name_in_toml(),
+                    display_path
+                ),
+                Err(e) => config.shell().warn(format!(
+                    "could not upgrade `{}` in `{}`: {}",
+                    dep.name_in_toml(),
+                    display_path,
+                    e
+                ))?,
+            }
+        }
+        let new_contents = manifest.to_string();
+        if new_contents != contents {
+            edited.push((path, new_contents));
+        }
+    }
+    for (path, contents) in edited {
+        paths::write(path, contents)?;
+    }
+    for message in upgraded {
+        config.shell().status("Upgrading", message)?;
+    }
+
+    // Reload the workspace to pick up the new requirements.
+    let ws = Workspace::new(ws.root_manifest(), config)?;
+    ops::resolve_ws(&ws)?;
+    Ok(())
+}
+
+/// Rewrites the requirement of `dep` in `manifest` to match `latest`,
+/// returning the old and new requirement.
+fn upgrade_dependency(
+    manifest: &mut ManifestEditor,
+    dep: &Dependency,
+    latest: &Version,
+) -> CargoResult<Option<(String, String)>> {
+    let tables: &[&str] = match dep.kind() {
+        DepKind::Normal => &["dependencies"],
+        DepKind::Development => &["dev-dependencies", "dev_dependencies"],
+        DepKind::Build => &["build-dependencies", "build_dependencies"],
+    };
+    let platform = dep.platform().map(|p| p.to_string());
+    let name = dep.name_in_toml();
+    let span = tables.iter().find_map(|table| {
+        let mut path = Vec::new();
+        if let Some(platform) = &platform {
+            path.extend(["target", platform.as_str()]);
+        }
+        path.extend([*table, name.as_str()]);
+        manifest.find_string(&path).or_else(|| {
+            path.push("version");
+            manifest.find_string(&path)
+        })
+    });
+    let span = match span {
+        Some(span) => span,
+        None => return Ok(None),
+    };
+    let from = manifest.get(span).to_string();
+    let to = match upgrade_req(&from, latest) {
+        Some(to) => to,
+        None => bail!("the requirement `{}` is not a plain version", from),
+    };
+    manifest.replace(span, &to);
+    Ok(Some((from, to)))
+}
+
+/// Computes a requirement for `latest` in the style of `req`, keeping its
+/// operator and number of components.
+///
+/// Only plain versions such as `1.2`, `^1.2.3`, `~1.2` or `=1.2.3` can be
+/// upgraded.
+fn upgrade_req(req: &str, latest: &Version) -> Option<String> {
+    let req = req.trim();
+    let (op, version) = match req.chars().next()? {
+        '^' | '~' | '=' => (&req[..1], req[1..].trim_start()),
+        _ => ("", req),
+    };
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.len() > 3 || parts.iter().any(|p| p.parse::<u64>().is_err()) {
+        return None;
+    }
+    let new_version = match parts.len() {
+        1 => latest.major.to_string(),
+        2 => format!("{}.{}", latest.major, latest.minor),
+        _ => format!("{}.{}.{}", latest.major, latest.minor, latest.patch),
+    };
+    Some(format!("{}{}", op, new_version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::upgrade_req;
+    use semver::Version;
+
+    #[test]
+    fn upgrade_reqs() {
+        let latest = Version::parse("2.3.4").unwrap();
+        assert_eq!(upgrade_req("1", &latest).unwrap(), "2");
+        assert_eq!(upgrade_req("1.0", &latest).unwrap(), "2.3");
+        assert_eq!(upgrade_req("1.0.1", &latest).unwrap(), "2.3.4");
+        assert_eq!(upgrade_req("^0.1", &latest).unwrap(), "^2.3");
+        assert_eq!(upgrade_req("~1.2", &latest).unwrap(), "~2.3");
+        assert_eq!(upgrade_req("= 1.2.3", &latest).unwrap(), "=2.3.4");
+        assert!(upgrade_req(">=1.0, <2.0", &latest).is_none());
+        assert!(upgrade_req("1.*", &latest).is_none());
+        assert!(upgrade_req("1.0.0-alpha", &latest).is_none());
+    }
+}
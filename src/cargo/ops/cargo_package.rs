@@ -28,6 +28,9 @@ pub struct PackageOpts<'cfg> {
     pub check_metadata: bool,
     pub allow_dirty: bool,
     pub verify: bool,
+    /// Verify with the minimal versions of the direct dependencies, ignoring
+    /// any packaged `Cargo.lock`, to check the declared lower bounds.
+    pub minimal_versions: bool,
     pub jobs: Option<u32>,
     pub targets: Vec<String>,
     pub cli_features: CliFeatures,
@@ -684,7 +687,11 @@ fn run_verify(ws: &Workspace<'_>, tar: &FileLock, opts: &PackageOpts<'_>) -> Car
     let mut src = PathSource::new(&dst, id, ws.config());
     let new_pkg = src.root_package()?;
     let pkg_fingerprint = hash_all(&dst)?;
-    let ws = Workspace::ephemeral(new_pkg, config, None, true)?;
+    let mut ws = Workspace::ephemeral(new_pkg, config, None, true)?;
+    if opts.minimal_versions {
+        ws.set_ignore_lock(true);
+        ws.set_direct_minimal_versions(true);
+    }
 
     let rustc_args = if pkg
         .manifest()
@@ -692,9 +699,12 @@ fn run_verify(ws: &Workspace<'_>, tar: &FileLock, opts: &PackageOpts<'_>) -> Car
         .require(Feature::public_dependency())
         .is_ok()
     {
-        // FIXME: Turn this on at some point in the future
-        //Some(vec!["-D exported_private_dependencies".to_string()])
-        Some(vec![])
+        // Types of private dependencies must not leak into the public API of
+        // a published package.
+        Some(vec![
+            "-D".to_string(),
+            "exported_private_dependencies".to_string(),
+        ])
     } else {
         None
     };
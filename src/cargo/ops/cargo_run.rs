@@ -6,12 +6,23 @@ use crate::core::compiler::UnitOutput;
 use crate::core::{TargetKind, Workspace};
 use crate::ops;
 use crate::util::CargoResult;
+use cargo_util::ProcessBuilder;
 
 pub fn run(
     ws: &Workspace<'_>,
     options: &ops::CompileOptions,
     args: &[OsString],
 ) -> CargoResult<()> {
+    run_process(ws, options, args)?.exec_replace()
+}
+
+/// Builds the executable to run, and returns the process running it with
+/// `args`.
+pub fn run_process(
+    ws: &Workspace<'_>,
+    options: &ops::CompileOptions,
+    args: &[OsString],
+) -> CargoResult<ProcessBuilder> {
     let config = ws.config();
 
     if options.filter.contains_glob_patterns() {
@@ -97,5 +108,5 @@ pub fn run(
 
     config.shell().status("Running", process.to_string())?;
 
-    process.exec_replace()
+    Ok(process)
 }
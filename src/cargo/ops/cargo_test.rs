@@ -1,16 +1,40 @@
-use crate::core::compiler::{Compilation, CompileKind, Doctest, UnitOutput};
+use crate::core::compiler::{
+    Compilation, CompileKind, CompileMode, CompileTarget, Doctest, Unit, UnitOutput,
+};
 use crate::core::shell::Verbosity;
 use crate::core::{TargetKind, Workspace};
 use crate::ops;
+use crate::ops::test_coverage::{Coverage, CoverageFormat};
+use crate::ops::test_report::{TestReport, TestReportOptions, TestRun, TestSuite};
+use crate::ops::test_shard::TestShard;
 use crate::util::errors::CargoResult;
 use crate::util::{add_path_args, CargoTestError, Config, Test};
-use cargo_util::ProcessError;
+use anyhow::Context as _;
+use cargo_util::{ProcessBuilder, ProcessError};
+use jobserver::Client;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
 use std::ffi::OsString;
+use std::path::Path;
+use std::process::Output;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use termcolor::Color;
 
 pub struct TestOptions {
     pub compile_opts: ops::CompileOptions,
     pub no_run: bool,
     pub no_fail_fast: bool,
+    /// Runs the test binaries concurrently, buffering their output.
+    pub parallel: bool,
+    /// Where to write reports of the results of the tests.
+    pub report: TestReportOptions,
+    /// Runs only one shard of the test suites.
+    pub shard: Option<TestShard>,
+    /// Measures the code coverage of the tests, and writes a report in
+    /// this format.
+    pub coverage: Option<CoverageFormat>,
 }
 
 pub fn run_tests(
@@ -21,16 +45,64 @@ pub fn run_tests(
     let compilation = compile_tests(ws, options)?;
 
     if options.no_run {
-        return Ok(None);
+        let doc = options.compile_opts.build_config.mode == CompileMode::Doctest;
+        if !doc || !ws.config().cli_unstable().doctest_xcompile {
+            return Ok(None);
+        }
+        // `--doc --no-run` builds the doc tests, so that they can be run
+        // later, like the test binaries.
+        let (test, errors) = run_doc_tests(
+            ws,
+            options,
+            test_args,
+            &compilation,
+            None,
+            None,
+            &mut TestReport::new(),
+        )?;
+        return Ok(if errors.is_empty() {
+            None
+        } else {
+            Some(CargoTestError::new(test, errors))
+        });
     }
-    let (test, mut errors) = run_unit_tests(ws.config(), options, test_args, &compilation)?;
+    let selected = match &options.shard {
+        Some(shard) => Some(select_shard(ws.config(), shard, &compilation)?),
+        None => None,
+    };
+    let selected = selected.as_ref();
+    let coverage = match options.coverage {
+        Some(format) => Some(Coverage::new(ws, format)?),
+        None => None,
+    };
+    let coverage = coverage.as_ref();
+    let mut report = TestReport::new();
+    let (test, mut errors) = run_unit_tests(
+        ws.config(),
+        options,
+        test_args,
+        &compilation,
+        selected,
+        coverage,
+        &mut report,
+    )?;
 
     // If we have an error and want to fail fast, then return.
     if !errors.is_empty() && !options.no_fail_fast {
+        write_reports(ws, options, &compilation, selected, coverage, &report)?;
         return Ok(Some(CargoTestError::new(test, errors)));
     }
 
-    let (doctest, docerrors) = run_doc_tests(ws, options, test_args, &compilation)?;
+    let (doctest, docerrors) = run_doc_tests(
+        ws,
+        options,
+        test_args,
+        &compilation,
+        selected,
+        coverage,
+        &mut report,
+    )?;
+    write_reports(ws, options, &compilation, selected, coverage, &report)?;
     let test = if docerrors.is_empty() { test } else { doctest };
     errors.extend(docerrors);
     if errors.is_empty() {
@@ -54,7 +126,15 @@ pub fn run_benches(
     let mut args = args.to_vec();
     args.push("--bench");
 
-    let (test, errors) = run_unit_tests(ws.config(), options, &args, &compilation)?;
+    let (test, errors) = run_unit_tests(
+        ws.config(),
+        options,
+        &args,
+        &compilation,
+        None,
+        None,
+        &mut TestReport::new(),
+    )?;
 
     match errors.len() {
         0 => Ok(None),
@@ -68,15 +148,127 @@ fn compile_tests<'a>(ws: &Workspace<'a>, options: &TestOptions) -> CargoResult<C
     Ok(compilation)
 }
 
+/// Returns the test suites of `compilation` which belong to `shard`.
+fn select_shard(
+    config: &Config,
+    shard: &TestShard,
+    compilation: &Compilation<'_>,
+) -> CargoResult<HashSet<TestSuite>> {
+    let mut suites: Vec<TestSuite> = compilation
+        .tests
+        .iter()
+        .map(|output| unit_suite(&output.unit))
+        .collect();
+    suites.extend(
+        compilation
+            .to_doc_test
+            .iter()
+            .map(|doctest| doc_suite(&doctest.unit)),
+    );
+    let selected = shard.select(&suites)?;
+    config.shell().status(
+        "Shard",
+        format!(
+            "{}/{}, running {} of {} test suites",
+            shard.index,
+            shard.count,
+            selected.len(),
+            suites.len()
+        ),
+    )?;
+    Ok(selected)
+}
+
+fn unit_suite(unit: &Unit) -> TestSuite {
+    TestSuite {
+        package: unit.pkg.name().to_string(),
+        target: unit.target.name().to_string(),
+        kind: unit.target.kind().description(),
+    }
+}
+
+fn doc_suite(unit: &Unit) -> TestSuite {
+    TestSuite {
+        package: unit.pkg.name().to_string(),
+        target: unit.target.name().to_string(),
+        kind: "doctest",
+    }
+}
+
+/// Writes the reports of the results of the tests, and of their coverage.
+fn write_reports(
+    ws: &Workspace<'_>,
+    options: &TestOptions,
+    compilation: &Compilation<'_>,
+    selected: Option<&HashSet<TestSuite>>,
+    coverage: Option<&Coverage>,
+    report: &TestReport,
+) -> CargoResult<()> {
+    report.write(&options.report)?;
+    if let Some(coverage) = coverage {
+        let objects: Vec<&Path> = compilation
+            .tests
+            .iter()
+            .filter(|output| is_selected(selected, &unit_suite(&output.unit)))
+            .map(|output| output.path.as_path())
+            .collect();
+        coverage.report(ws, &compilation.llvm_tools_dir(), &objects)?;
+    }
+    Ok(())
+}
+
+/// Whether `suite` is run, when only the `selected` suites of a shard are.
+fn is_selected(selected: Option<&HashSet<TestSuite>>, suite: &TestSuite) -> bool {
+    selected.map_or(true, |selected| selected.contains(suite))
+}
+
+/// Returns the target `unit` is built for if its tests can't run on the
+/// host. This is the case of the packages built for another target because
+/// of their `package.forced-target` or `package.default-target` key, unless
+/// a runner is configured for it. Tests built for a target passed with
+/// `--target` are always run.
+fn unrunnable_target(
+    options: &TestOptions,
+    compilation: &Compilation<'_>,
+    unit: &Unit,
+) -> Option<CompileTarget> {
+    match unit.kind {
+        CompileKind::Target(target)
+            if target.short_name() != compilation.host
+                && compilation.target_runner(unit.kind).is_none()
+                && !options
+                    .compile_opts
+                    .build_config
+                    .requested_kinds
+                    .contains(&unit.kind) =>
+        {
+            Some(target)
+        }
+        _ => None,
+    }
+}
+
+/// A test executable to run, and how it is described to the user.
+struct TestBinary {
+    cmd: ProcessBuilder,
+    exe_display: String,
+    kind: TargetKind,
+    suite: TestSuite,
+    harness: bool,
+}
+
 /// Runs the unit and integration tests of a package.
 fn run_unit_tests(
     config: &Config,
     options: &TestOptions,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    selected: Option<&HashSet<TestSuite>>,
+    coverage: Option<&Coverage>,
+    report: &mut TestReport,
 ) -> CargoResult<(Test, Vec<ProcessError>)> {
     let cwd = config.cwd();
-    let mut errors = Vec::new();
+    let mut binaries = Vec::new();
 
     for UnitOutput {
         unit,
@@ -84,8 +276,19 @@ fn run_unit_tests(
         script_meta,
     } in compilation.tests.iter()
     {
-        let test = unit.target.name().to_string();
-
+        let suite = unit_suite(unit);
+        if !is_selected(selected, &suite) {
+            continue;
+        }
+        if let Some(target) = unrunnable_target(options, compilation, unit) {
+            config.shell().note(format!(
+                "skipping tests of `{}` ({}) built for `{}`, which cannot run on the host",
+                unit.pkg.name(),
+                path.strip_prefix(cwd).unwrap_or(path).display(),
+                target.short_name(),
+            ))?;
+            continue;
+        }
         let test_path = unit.target.src_path().path().unwrap();
         let exe_display = if let TargetKind::Test = unit.target.kind() {
             format!(
@@ -105,51 +308,258 @@ fn run_unit_tests(
 
         let mut cmd = compilation.target_process(path, unit.kind, &unit.pkg, *script_meta)?;
         cmd.args(test_args);
-        if unit.target.harness() && config.shell().verbosity() == Verbosity::Quiet {
-            cmd.arg("--quiet");
+        if let Some(coverage) = coverage {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            cmd.env("LLVM_PROFILE_FILE", coverage.profile_file(&name));
         }
-        config
-            .shell()
-            .concise(|shell| shell.status("Running", &exe_display))?;
-        config
-            .shell()
-            .verbose(|shell| shell.status("Running", &cmd))?;
-
-        let result = cmd.exec();
-
-        match result {
-            Err(e) => {
-                let e = e.downcast::<ProcessError>()?;
-                errors.push((
-                    unit.target.kind().clone(),
-                    test.clone(),
-                    unit.pkg.name().to_string(),
-                    e,
-                ));
-                if !options.no_fail_fast {
-                    break;
-                }
-            }
-            Ok(()) => {}
+        if unit.target.harness() && options.report.is_enabled() {
+            cmd.args(&["-Zunstable-options", "--format", "json", "--report-time"]);
+        } else if unit.target.harness() && config.shell().verbosity() == Verbosity::Quiet {
+            cmd.arg("--quiet");
         }
+        binaries.push(TestBinary {
+            cmd,
+            exe_display,
+            kind: unit.target.kind().clone(),
+            suite,
+            harness: unit.target.harness(),
+        });
     }
 
+    let mut errors = if options.parallel {
+        run_binaries_parallel(config, options, &binaries, report)?
+    } else {
+        run_binaries(config, options, &binaries, report)?
+    };
+
     if errors.len() == 1 {
-        let (kind, name, pkg_name, e) = errors.pop().unwrap();
+        let (binary, e) = errors.pop().unwrap();
         Ok((
             Test::UnitTest {
-                kind,
-                name,
-                pkg_name,
+                kind: binary.kind.clone(),
+                name: binary.suite.target.clone(),
+                pkg_name: binary.suite.package.clone(),
             },
             vec![e],
         ))
     } else {
-        Ok((
-            Test::Multiple,
-            errors.into_iter().map(|(_, _, _, e)| e).collect(),
-        ))
+        Ok((Test::Multiple, errors.into_iter().map(|(_, e)| e).collect()))
+    }
+}
+
+/// Runs the test binaries one after another, with their output going
+/// directly to the terminal.
+fn run_binaries<'a>(
+    config: &Config,
+    options: &TestOptions,
+    binaries: &'a [TestBinary],
+    report: &mut TestReport,
+) -> CargoResult<Vec<(&'a TestBinary, ProcessError)>> {
+    let mut errors = Vec::new();
+    for binary in binaries {
+        print_running(config, binary)?;
+        let result = if options.report.is_enabled() {
+            let run = TestRun::new(binary.suite.clone(), binary.harness);
+            exec_reported(config, &binary.cmd, run, report)
+        } else {
+            binary.cmd.exec()
+        };
+        if let Err(e) = result {
+            errors.push((binary, e.downcast::<ProcessError>()?));
+            if !options.no_fail_fast {
+                break;
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// Runs the test binaries concurrently, as many at a time as the jobserver
+/// allows. The output of each binary is buffered, and printed in the order
+/// of `binaries` once it has finished. Unless `--no-fail-fast` is passed, no
+/// more binaries are started after one fails.
+fn run_binaries_parallel<'a>(
+    config: &Config,
+    options: &TestOptions,
+    binaries: &'a [TestBinary],
+    report: &mut TestReport,
+) -> CargoResult<Vec<(&'a TestBinary, ProcessError)>> {
+    // Like for compilation, an external jobserver is shared with it, and our
+    // own jobserver has one token less than the number of jobs, as this
+    // process holds one implicitly.
+    let jobs = options.compile_opts.build_config.jobs as usize;
+    let jobserver = match config.jobserver_from_env() {
+        Some(client) => client.clone(),
+        None => {
+            let client = Client::new(jobs).with_context(|| "failed to create jobserver")?;
+            client.acquire_raw()?;
+            client
+        }
+    };
+    let no_fail_fast = options.no_fail_fast;
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+
+    let mut outputs = BTreeMap::new();
+    let mut printed = 0;
+    let mut errors = Vec::new();
+    crossbeam_utils::thread::scope(|scope| -> CargoResult<()> {
+        for worker in 0..jobs.min(binaries.len()) {
+            let (tx, next, stop, jobserver) = (tx.clone(), &next, &stop, &jobserver);
+            scope.spawn(move |_| loop {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let binary = match binaries.get(i) {
+                    Some(binary) => binary,
+                    None => break,
+                };
+                // The first worker runs with the token of this process.
+                let token = if worker == 0 {
+                    None
+                } else {
+                    match jobserver.acquire() {
+                        Ok(token) => Some(token),
+                        Err(e) => {
+                            let e = ProcessError::new(
+                                &format!("failed to acquire jobserver token: {}", e),
+                                None,
+                                None,
+                            );
+                            let _ = tx.send((i, Err((None, e)), Duration::from_secs(0)));
+                            break;
+                        }
+                    }
+                };
+                let start = Instant::now();
+                let result = run_buffered(&binary.cmd);
+                let elapsed = start.elapsed();
+                drop(token);
+                if result.is_err() && !no_fail_fast {
+                    stop.store(true, Ordering::SeqCst);
+                }
+                if tx.send((i, result, elapsed)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, result, elapsed) in rx {
+            outputs.insert(i, (result, elapsed));
+            while let Some((result, elapsed)) = outputs.remove(&printed) {
+                let binary = &binaries[printed];
+                printed += 1;
+                print_running(config, binary)?;
+                let (output, error) = match result {
+                    Ok(output) => (Some(output), None),
+                    Err((output, e)) => (output, Some(e)),
+                };
+                match output {
+                    Some(output) if options.report.is_enabled() => {
+                        let mut run = TestRun::new(binary.suite.clone(), binary.harness);
+                        for line in String::from_utf8_lossy(&output.stdout).lines() {
+                            if let Some(text) = run.stdout_line(line) {
+                                writeln!(config.shell().out(), "{}", text)?;
+                            }
+                        }
+                        for line in String::from_utf8_lossy(&output.stderr).lines() {
+                            run.stderr_line(line);
+                        }
+                        config.shell().err().write_all(&output.stderr)?;
+                        run.finish(error.is_none(), elapsed, report);
+                    }
+                    Some(output) => {
+                        let mut shell = config.shell();
+                        shell.out().write_all(&output.stdout)?;
+                        shell.err().write_all(&output.stderr)?;
+                    }
+                    None => {}
+                }
+                if let Some(e) = error {
+                    errors.push((binary, e));
+                }
+            }
+        }
+        Ok(())
+    })
+    .expect("child threads shouldn't panic")?;
+
+    let mut shell = config.shell();
+    shell.status(
+        "Summary",
+        format!(
+            "{} of {} test binaries ran, {} failed",
+            printed,
+            binaries.len(),
+            errors.len()
+        ),
+    )?;
+    for (binary, _) in &errors {
+        shell.status_with_color("Failed", &binary.exe_display, Color::Red)?;
     }
+    Ok(errors)
+}
+
+/// Runs a test suite, printing its output as it is handled by `run`, and
+/// records its results in `report`.
+fn exec_reported(
+    config: &Config,
+    cmd: &ProcessBuilder,
+    run: TestRun,
+    report: &mut TestReport,
+) -> CargoResult<()> {
+    let start = Instant::now();
+    // Both callbacks handle the output, one after another.
+    let run = RefCell::new(run);
+    let result = cmd.exec_with_streaming(
+        &mut |line| {
+            if let Some(text) = run.borrow_mut().stdout_line(line) {
+                writeln!(config.shell().out(), "{}", text)?;
+            }
+            Ok(())
+        },
+        &mut |line| {
+            run.borrow_mut().stderr_line(line);
+            writeln!(config.shell().err(), "{}", line)?;
+            Ok(())
+        },
+        false,
+    );
+    run.into_inner()
+        .finish(result.is_ok(), start.elapsed(), report);
+    result?;
+    Ok(())
+}
+
+/// Runs a test binary with its output captured.
+fn run_buffered(cmd: &ProcessBuilder) -> Result<Output, (Option<Output>, ProcessError)> {
+    let output = match cmd.build_command().output() {
+        Ok(output) => output,
+        Err(e) => {
+            let msg = format!("could not execute process {}: {}", cmd, e);
+            return Err((None, ProcessError::new(&msg, None, None)));
+        }
+    };
+    if output.status.success() {
+        Ok(output)
+    } else {
+        // The output is printed separately, so it is not part of the error.
+        let msg = format!("process didn't exit successfully: {}", cmd);
+        let e = ProcessError::new(&msg, Some(output.status), None);
+        Err((Some(output), e))
+    }
+}
+
+fn print_running(config: &Config, binary: &TestBinary) -> CargoResult<()> {
+    config
+        .shell()
+        .concise(|shell| shell.status("Running", &binary.exe_display))?;
+    config
+        .shell()
+        .verbose(|shell| shell.status("Running", &binary.cmd))
 }
 
 fn run_doc_tests(
@@ -157,6 +567,9 @@ fn run_doc_tests(
     options: &TestOptions,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    selected: Option<&HashSet<TestSuite>>,
+    coverage: Option<&Coverage>,
+    report: &mut TestReport,
 ) -> CargoResult<(Test, Vec<ProcessError>)> {
     let config = ws.config();
     let mut errors = Vec::new();
@@ -172,6 +585,11 @@ fn run_doc_tests(
             script_meta,
         } = doctest_info;
 
+        let suite = doc_suite(unit);
+        if !is_selected(selected, &suite) {
+            continue;
+        }
+
         if !doctest_xcompile {
             match unit.kind {
                 CompileKind::Host => {}
@@ -182,6 +600,8 @@ fn run_doc_tests(
                     }
                 }
             }
+        } else if unrunnable_target(options, compilation, unit).is_some() {
+            continue;
         }
 
         config.shell().status("Doc-tests", unit.target.name())?;
@@ -217,6 +637,13 @@ fn run_doc_tests(
                 joined.push(linker);
                 p.arg("-C").arg(joined);
             }
+            if options.no_run {
+                // Keeps the doc test binaries, as the runner is not run.
+                let dir = compilation.root_output[&unit.kind]
+                    .join("doctests")
+                    .join(unit.target.crate_name());
+                p.arg("--no-run").arg("--persist-doctests").arg(dir);
+            }
         }
 
         for &rust_dep in &[
@@ -232,9 +659,22 @@ fn run_doc_tests(
             p.arg("-L").arg(native_dep);
         }
 
+        if let Some(coverage) = coverage {
+            // The doc tests link to the instrumented library, so they are
+            // instrumented too, but they are not part of the report.
+            p.args(&coverage.rustc_flag());
+            let name = format!("doctests-{}", unit.target.crate_name());
+            p.env("LLVM_PROFILE_FILE", coverage.profile_file(&name));
+        }
+
         for arg in test_args {
             p.arg("--test-args").arg(arg);
         }
+        if options.report.is_enabled() {
+            for arg in &["-Zunstable-options", "--format", "json", "--report-time"] {
+                p.arg("--test-args").arg(arg);
+            }
+        }
 
         p.args(args);
 
@@ -245,7 +685,12 @@ fn run_doc_tests(
         config
             .shell()
             .verbose(|shell| shell.status("Running", p.to_string()))?;
-        if let Err(e) = p.exec() {
+        let result = if options.report.is_enabled() {
+            exec_reported(config, &p, TestRun::new(suite, true), report)
+        } else {
+            p.exec()
+        };
+        if let Err(e) = result {
             let e = e.downcast::<ProcessError>()?;
             errors.push(e);
             if !options.no_fail_fast {
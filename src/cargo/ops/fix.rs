@@ -37,8 +37,15 @@
 //!   `--broken-code` is used).
 //! - If there are any warnings or errors, rustc will be run one last time to
 //!   show them to the user.
-
-use std::collections::{BTreeSet, HashMap, HashSet};
+//!
+//! When `--migrate-workspace` is used, the above process is run once per
+//! workspace member, in dependency order, instead of over all selected
+//! packages at once. The outcome for each member is saved to
+//! `MIGRATION_STATE_FILE` in the target directory so that a migration which
+//! stopped on an error can be resumed. Once every member has been migrated,
+//! the `edition` field of each `Cargo.toml` is updated.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
@@ -50,14 +57,16 @@ use cargo_util::{paths, ProcessBuilder};
 use log::{debug, trace, warn};
 use rustfix::diagnostics::Diagnostic;
 use rustfix::{self, CodeFix};
+use serde::{Deserialize, Serialize};
 
 use crate::core::compiler::RustcTargetData;
 use crate::core::resolver::features::{FeatureOpts, FeatureResolver};
 use crate::core::resolver::{HasDevUnits, ResolveBehavior};
-use crate::core::{Edition, MaybePackage, Workspace};
-use crate::ops::{self, CompileOptions};
+use crate::core::{Edition, MaybePackage, Package, PackageId, PackageIdSpec, Resolve, Workspace};
+use crate::ops::{self, CompileOptions, Packages};
 use crate::util::diagnostic_server::{Message, RustfixDiagnosticServer};
 use crate::util::errors::CargoResult;
+use crate::util::toml::edit::ManifestEditor;
 use crate::util::Config;
 use crate::util::{existing_vcs_repo, LockServer, LockServerClient};
 use crate::{drop_eprint, drop_eprintln};
@@ -67,6 +76,9 @@ const BROKEN_CODE_ENV: &str = "__CARGO_FIX_BROKEN_CODE";
 const EDITION_ENV: &str = "__CARGO_FIX_EDITION";
 const IDIOMS_ENV: &str = "__CARGO_FIX_IDIOMS";
 
+/// File in the target directory tracking the progress of `--migrate-workspace`.
+const MIGRATION_STATE_FILE: &str = ".edition-migration.json";
+
 pub struct FixOptions {
     pub edition: bool,
     pub idioms: bool,
@@ -75,14 +87,36 @@ pub struct FixOptions {
     pub allow_no_vcs: bool,
     pub allow_staged: bool,
     pub broken_code: bool,
+    /// Migrate each workspace member to the next edition one at a time (in
+    /// dependency order), and update the `edition` of each manifest when
+    /// done.
+    pub migrate_workspace: bool,
 }
 
 pub fn fix(ws: &Workspace<'_>, opts: &mut FixOptions) -> CargoResult<()> {
-    check_version_control(ws.config(), opts)?;
+    // A migration being resumed was checked when it started, and the working
+    // directory now has the changes it made so far.
+    let resuming_migration = opts.migrate_workspace
+        && ws
+            .target_dir()
+            .as_path_unlocked()
+            .join(MIGRATION_STATE_FILE)
+            .exists();
+    if !resuming_migration {
+        check_version_control(ws.config(), opts)?;
+    }
     if opts.edition {
         check_resolver_change(ws, opts)?;
     }
+    if opts.migrate_workspace {
+        return migrate_workspace(ws, opts);
+    }
+    fix_packages(ws, opts)
+}
 
+/// Runs `cargo check` with cargo acting as the rustc proxy for the packages
+/// selected in `opts`.
+fn fix_packages(ws: &Workspace<'_>, opts: &mut FixOptions) -> CargoResult<()> {
     // Spin up our lock server, which our subprocesses will use to synchronize fixes.
     let lock_server = LockServer::new()?;
     let mut wrapper = ProcessBuilder::new(env::current_exe()?);
@@ -129,6 +163,216 @@ pub fn fix(ws: &Workspace<'_>, opts: &mut FixOptions) -> CargoResult<()> {
     Ok(())
 }
 
+/// The outcome of migrating a single package with `--migrate-workspace`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum MigrationResult {
+    Migrated,
+    Failed,
+}
+
+/// The on-disk state of `--migrate-workspace`, stored in
+/// `MIGRATION_STATE_FILE`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MigrationState {
+    /// Results keyed by the package ID of each member attempted so far.
+    packages: BTreeMap<String, MigrationResult>,
+}
+
+/// Migrates the selected workspace members to the next edition, one package
+/// at a time.
+///
+/// Members are fixed in dependency order, so that a package is only migrated
+/// after the workspace members it depends on. Members already recorded as
+/// migrated from a previous run are skipped. If a member fails, the state is
+/// saved and the error is returned, so that running the command again resumes
+/// from that member.
+fn migrate_workspace(ws: &Workspace<'_>, opts: &mut FixOptions) -> CargoResult<()> {
+    let config = ws.config();
+    let pkgs = members_in_dependency_order(ws, &opts.compile_opts.spec)?;
+
+    let target_dir = ws.target_dir();
+    let state_lock = target_dir.open_rw(MIGRATION_STATE_FILE, config, "edition migration state")?;
+    let contents = paths::read(state_lock.path())?;
+    let mut state: MigrationState = if contents.is_empty() {
+        MigrationState::default()
+    } else {
+        serde_json::from_str(&contents).with_context(|| {
+            format!(
+                "failed to parse edition migration state `{}`",
+                state_lock.path().display()
+            )
+        })?
+    };
+    let save_state = |state: &MigrationState| -> CargoResult<()> {
+        let json = serde_json::to_string_pretty(state)?;
+        paths::write(state_lock.path(), json)
+    };
+
+    for pkg in &pkgs {
+        let key = pkg.package_id().to_string();
+        if state.packages.get(&key) == Some(&MigrationResult::Migrated) {
+            config.shell().status(
+                "Skipping",
+                format!("{} (already migrated)", pkg.package_id()),
+            )?;
+            continue;
+        }
+        let spec = PackageIdSpec::from_package_id(pkg.package_id());
+        opts.compile_opts.spec = Packages::Packages(vec![spec.to_string()]);
+        let result = fix_packages(ws, opts);
+        let outcome = if result.is_ok() {
+            MigrationResult::Migrated
+        } else {
+            MigrationResult::Failed
+        };
+        state.packages.insert(key, outcome);
+        save_state(&state)?;
+        if let Err(e) = result {
+            print_migration_summary(config, &pkgs, &state)?;
+            return Err(e.context(format!(
+                "failed to migrate `{}` to the next edition\n\
+                 Fix the errors above and run the command again to resume \
+                 the migration from this package.",
+                pkg.package_id()
+            )));
+        }
+    }
+
+    for pkg in &pkgs {
+        let current = pkg.manifest().edition();
+        let next = current.saturating_next();
+        if next == current {
+            continue;
+        }
+        update_manifest_edition(config, pkg.manifest_path(), next)?;
+        config.shell().status(
+            "Updated",
+            format!("{} edition from {} to {}", pkg.package_id(), current, next),
+        )?;
+    }
+    print_migration_summary(config, &pkgs, &state)?;
+    drop(state_lock);
+    paths::remove_file(target_dir.as_path_unlocked().join(MIGRATION_STATE_FILE))?;
+    Ok(())
+}
+
+/// Returns the packages selected by `spec`, ordered so that every package
+/// comes after the workspace members it depends on.
+fn members_in_dependency_order<'a>(
+    ws: &'a Workspace<'_>,
+    spec: &Packages,
+) -> CargoResult<Vec<&'a Package>> {
+    fn visit(
+        id: PackageId,
+        resolve: &Resolve,
+        selected: &HashSet<PackageId>,
+        visited: &mut HashSet<PackageId>,
+        order: &mut Vec<PackageId>,
+    ) {
+        if !visited.insert(id) {
+            return;
+        }
+        let mut deps: Vec<_> = resolve
+            .deps(id)
+            .map(|(dep_id, _)| dep_id)
+            .filter(|dep_id| selected.contains(dep_id))
+            .collect();
+        deps.sort();
+        for dep_id in deps {
+            visit(dep_id, resolve, selected, visited, order);
+        }
+        order.push(id);
+    }
+
+    let (_pkg_set, resolve) = ops::resolve_ws(ws)?;
+    let mut pkgs = spec.get_packages(ws)?;
+    pkgs.sort_by_key(|pkg| pkg.package_id());
+    let selected: HashSet<_> = pkgs.iter().map(|pkg| pkg.package_id()).collect();
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    for pkg in &pkgs {
+        visit(
+            pkg.package_id(),
+            &resolve,
+            &selected,
+            &mut visited,
+            &mut order,
+        );
+    }
+    Ok(order
+        .into_iter()
+        .map(|id| *pkgs.iter().find(|pkg| pkg.package_id() == id).unwrap())
+        .collect())
+}
+
+fn print_migration_summary(
+    config: &Config,
+    pkgs: &[&Package],
+    state: &MigrationState,
+) -> CargoResult<()> {
+    drop_eprintln!(config, "\nEdition migration summary:");
+    for pkg in pkgs {
+        let status = match state.packages.get(&pkg.package_id().to_string()) {
+            Some(MigrationResult::Migrated) => "migrated",
+            Some(MigrationResult::Failed) => "failed",
+            None => "pending",
+        };
+        drop_eprintln!(config, "  {} ({})", pkg.package_id(), status);
+    }
+    Ok(())
+}
+
+/// Sets the `edition` field of the `[package]` table in the given manifest.
+///
+/// The manifest is edited as text so that formatting and comments elsewhere
+/// in the file are preserved.
+fn update_manifest_edition(config: &Config, path: &Path, edition: Edition) -> CargoResult<()> {
+    let contents = paths::read(path)?;
+    let mut manifest = ManifestEditor::new(&contents);
+    let value = format!("\"{}\"", edition);
+    let tables = ["package", "project"];
+    match tables.iter().find_map(|t| manifest.find(&[*t, "edition"])) {
+        Some(span) => manifest.replace(span, &value),
+        None => match tables.iter().find_map(|t| manifest.table_header(&[*t])) {
+            Some(i) => manifest.insert_line(i + 1, format!("edition = {}", value)),
+            None => bail!(
+                "could not find a `[package]` table in `{}` to update the edition",
+                path.display()
+            ),
+        },
+    }
+
+    if !edition.is_stable() {
+        let feature = format!("\"edition{}\"", edition);
+        match manifest.find(&["cargo-features"]) {
+            None => manifest.insert_line(0, format!("cargo-features = [{}]", feature)),
+            Some(span) if manifest.get(span).contains(&feature) => {}
+            Some(span) => {
+                let features = manifest.get(span);
+                match features.strip_suffix(']') {
+                    Some(start) if start.trim_end() == "[" => {
+                        manifest.replace(span, &format!("[{}]", feature))
+                    }
+                    Some(start) => {
+                        let start = start.trim_end().trim_end_matches(',');
+                        let value = format!("{}, {}]", start, feature);
+                        manifest.replace(span, &value)
+                    }
+                    None => config.shell().warn(format!(
+                        "{} must be added to `cargo-features` in `{}` to use edition {}",
+                        feature,
+                        path.display(),
+                        edition
+                    ))?,
+                }
+            }
+        }
+    }
+
+    paths::write(path, manifest.to_string())
+}
+
 fn check_version_control(config: &Config, opts: &FixOptions) -> CargoResult<()> {
     if opts.allow_no_vcs {
         return Ok(());
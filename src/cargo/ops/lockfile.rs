@@ -1,6 +1,11 @@
 use std::io::prelude::*;
 
+use std::path::Path;
+
+use crate::core::registry::PackageRegistry;
+use crate::core::resolver::features::{CliFeatures, HasDevUnits};
 use crate::core::{resolver, Resolve, ResolveVersion, Workspace};
+use crate::ops;
 use crate::util::errors::CargoResult;
 use crate::util::toml as cargo_toml;
 use crate::util::Filesystem;
@@ -19,6 +24,23 @@ pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
     f.read_to_string(&mut s)
         .with_context(|| format!("failed to read file: {}", f.path().display()))?;
 
+    if ws.config().cli_unstable().lockfile_merge {
+        if let Some((ours, theirs)) = split_merge_conflict(&s) {
+            ws.config()
+                .shell()
+                .status("Merging", "conflicting versions of Cargo.lock")?;
+            let resolve = merge_encodable(ws, f.path(), &ours, &theirs)
+                .and_then(|merged| merged.into_resolve(&ours, ws))
+                .with_context(|| {
+                    format!(
+                        "failed to merge conflicts in lock file at: {}",
+                        f.path().display()
+                    )
+                })?;
+            return Ok(Some(resolve));
+        }
+    }
+
     let resolve = (|| -> CargoResult<Option<Resolve>> {
         let resolve: toml::Value = cargo_toml::parse(&s, f.path(), ws.config())?;
         let v: resolver::EncodableResolve = resolve.try_into()?;
@@ -28,6 +50,100 @@ pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
     Ok(resolve)
 }
 
+/// Splits a file with git merge conflict markers into the two sides of the
+/// conflicts, "ours" and "theirs". The common ancestor of a `diff3` style
+/// conflict is dropped.
+///
+/// Returns `None` if there are no conflict markers.
+fn split_merge_conflict(s: &str) -> Option<(String, String)> {
+    #[derive(PartialEq)]
+    enum Side {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    let mut side = Side::Both;
+    let mut has_conflict = false;
+    for line in s.lines() {
+        let next = if line.starts_with("<<<<<<<") && side == Side::Both {
+            Some(Side::Ours)
+        } else if line.starts_with("|||||||") && side == Side::Ours {
+            Some(Side::Base)
+        } else if line.starts_with("=======") && (side == Side::Ours || side == Side::Base) {
+            Some(Side::Theirs)
+        } else if line.starts_with(">>>>>>>") && side == Side::Theirs {
+            Some(Side::Both)
+        } else {
+            None
+        };
+        if let Some(next) = next {
+            has_conflict = true;
+            side = next;
+            continue;
+        }
+        if side == Side::Both || side == Side::Ours {
+            ours.push_str(line);
+            ours.push('\n');
+        }
+        if side == Side::Both || side == Side::Theirs {
+            theirs.push_str(line);
+            theirs.push('\n');
+        }
+    }
+    // Leave unterminated conflicts to the usual parse error.
+    if has_conflict && side == Side::Both {
+        Some((ours, theirs))
+    } else {
+        None
+    }
+}
+
+/// Parses both sides of a conflicting lock file and merges them.
+fn merge_encodable(
+    ws: &Workspace<'_>,
+    path: &Path,
+    ours: &str,
+    theirs: &str,
+) -> CargoResult<resolver::EncodableResolve> {
+    let parse = |s: &str, side: &str| -> CargoResult<resolver::EncodableResolve> {
+        (|| -> CargoResult<resolver::EncodableResolve> {
+            let resolve: toml::Value = cargo_toml::parse(s, path, ws.config())?;
+            Ok(resolve.try_into()?)
+        })()
+        .with_context(|| format!("failed to parse {} version of the lock file", side))
+    };
+    Ok(parse(ours, "our")?.merge(parse(theirs, "their")?))
+}
+
+/// Merges two versions of a lock file, as a git merge driver.
+///
+/// The packages of both versions are used as the previous resolve to resolve
+/// the workspace again, and the new contents of the lock file are returned.
+/// The workspace's own `Cargo.lock` is neither read nor written.
+pub fn merge_lockfiles(ws: &Workspace<'_>, ours: &str, theirs: &str) -> CargoResult<String> {
+    let path = ws.root().join("Cargo.lock");
+    let previous = merge_encodable(ws, &path, ours, theirs)?.into_resolve(ours, ws)?;
+    let mut registry = PackageRegistry::new(ws.config())?;
+    let mut resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous),
+        None,
+        &[],
+        true,
+    )?;
+    if resolve.version() < ResolveVersion::default() {
+        resolve.set_version(ResolveVersion::default());
+    }
+    Ok(serialize_resolve(&resolve, Some(ours)))
+}
+
 /// Generate a toml String of Cargo.lock from a Resolve.
 pub fn resolve_to_string(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoResult<String> {
     let (_orig, out, _ws_root) = resolve_to_string_orig(ws, resolve);
@@ -46,6 +162,13 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
     }
 
     if !ws.config().lock_update_allowed() {
+        if ws.config().cli_unstable().locked_checksums {
+            anyhow::bail!(
+                "the lock file {} needs to be updated but -Z locked-checksums was passed to \
+                 prevent this",
+                ws.root().to_path_buf().join("Cargo.lock").display()
+            );
+        }
         if ws.config().offline() {
             anyhow::bail!("can't update in the offline mode");
         }
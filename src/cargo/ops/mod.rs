@@ -1,3 +1,5 @@
+pub use self::affected::affected_packages;
+pub use self::cargo_audit::{audit, AuditOptions};
 pub use self::cargo_clean::{clean, CleanOptions};
 pub use self::cargo_compile::{
     compile, compile_with_exec, compile_ws, create_bcx, print, resolve_all_features, CompileOptions,
@@ -10,15 +12,18 @@ pub use self::cargo_generate_lockfile::update_lockfile;
 pub use self::cargo_generate_lockfile::UpdateOptions;
 pub use self::cargo_install::{install, install_list};
 pub use self::cargo_new::{init, new, NewOptions, VersionControl};
+pub use self::cargo_outdated::{outdated, OutdatedOptions};
 pub use self::cargo_output_metadata::{output_metadata, ExportInfo, OutputMetadataOptions};
 pub use self::cargo_package::{package, PackageOpts};
 pub use self::cargo_pkgid::pkgid;
 pub use self::cargo_read_manifest::{read_package, read_packages};
-pub use self::cargo_run::run;
+pub use self::cargo_run::{run, run_process};
 pub use self::cargo_test::{run_benches, run_tests, TestOptions};
 pub use self::cargo_uninstall::uninstall;
 pub use self::fix::{fix, fix_maybe_exec_rustc, FixOptions};
-pub use self::lockfile::{load_pkg_lockfile, resolve_to_string, write_pkg_lockfile};
+pub use self::lockfile::{
+    load_pkg_lockfile, merge_lockfiles, resolve_to_string, write_pkg_lockfile,
+};
 pub use self::registry::HttpTimeout;
 pub use self::registry::{configure_http_handle, http_handle, http_handle_and_timeout};
 pub use self::registry::{modify_owners, yank, OwnersOptions, PublishOpts};
@@ -27,8 +32,14 @@ pub use self::registry::{publish, registry_configuration, RegistryConfig};
 pub use self::resolve::{
     add_overrides, get_resolved_packages, resolve_with_previous, resolve_ws, resolve_ws_with_opts,
 };
+pub use self::test_coverage::{instrument_coverage_flag, CoverageFormat};
+pub use self::test_report::TestReportOptions;
+pub use self::test_shard::TestShard;
 pub use self::vendor::{vendor, VendorOptions};
+pub use self::watch::{watch, WatchRun};
 
+mod affected;
+mod cargo_audit;
 mod cargo_clean;
 mod cargo_compile;
 pub mod cargo_config;
@@ -37,6 +48,7 @@ mod cargo_fetch;
 mod cargo_generate_lockfile;
 mod cargo_install;
 mod cargo_new;
+mod cargo_outdated;
 mod cargo_output_metadata;
 mod cargo_package;
 mod cargo_pkgid;
@@ -47,7 +59,12 @@ mod cargo_uninstall;
 mod common_for_install_and_uninstall;
 mod fix;
 mod lockfile;
+pub mod policy;
 mod registry;
 mod resolve;
+mod test_coverage;
+mod test_report;
+mod test_shard;
 pub mod tree;
 mod vendor;
+mod watch;
@@ -0,0 +1,278 @@
+//! A parser for the SPDX license expressions used in the `license` field.
+//!
+//! See <https://spdx.github.io/spdx-spec/SPDX-license-expressions/> for the
+//! syntax. License identifiers are not checked against the SPDX license list,
+//! and the legacy `MIT/Apache-2.0` syntax is accepted as an `OR`, like
+//! crates.io does.
+
+use crate::util::CargoResult;
+use anyhow::bail;
+use std::fmt;
+
+/// A parsed SPDX license expression.
+#[derive(Debug, PartialEq)]
+pub(crate) enum LicenseExpr {
+    /// A single license, such as `GPL-2.0+ WITH Classpath-exception-2.0`.
+    License {
+        id: String,
+        /// Whether the identifier was followed by `+`.
+        or_later: bool,
+        exception: Option<String>,
+    },
+    And(Box<LicenseExpr>, Box<LicenseExpr>),
+    Or(Box<LicenseExpr>, Box<LicenseExpr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    And,
+    Or,
+    With,
+    Ident(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::With => f.write_str("WITH"),
+            Token::Ident(s) => f.write_str(s),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> CargoResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '/' => tokens.push(Token::Or),
+            c if c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == ':' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '+') {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(match &s[start..end] {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "WITH" => Token::With,
+                    ident => Token::Ident(ident),
+                });
+            }
+            c => bail!("unexpected character `{}`", c),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> CargoResult<Token<'a>> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(*token)
+            }
+            None => bail!("unexpected end of expression"),
+        }
+    }
+
+    fn or(&mut self) -> CargoResult<LicenseExpr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = LicenseExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> CargoResult<LicenseExpr> {
+        let mut expr = self.primary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = LicenseExpr::And(Box::new(expr), Box::new(self.primary()?));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> CargoResult<LicenseExpr> {
+        let id = match self.next()? {
+            Token::Open => {
+                let expr = self.or()?;
+                match self.next()? {
+                    Token::Close => return Ok(expr),
+                    token => bail!("expected `)`, found `{}`", token),
+                }
+            }
+            Token::Ident(id) => id,
+            token => bail!("expected a license identifier, found `{}`", token),
+        };
+        let (id, or_later) = match id.strip_suffix('+') {
+            Some(id) => (id, true),
+            None => (id, false),
+        };
+        let exception = if self.peek() == Some(&Token::With) {
+            self.pos += 1;
+            match self.next()? {
+                Token::Ident(exception) => Some(exception.to_string()),
+                token => bail!("expected a license exception, found `{}`", token),
+            }
+        } else {
+            None
+        };
+        Ok(LicenseExpr::License {
+            id: id.to_string(),
+            or_later,
+            exception,
+        })
+    }
+}
+
+impl LicenseExpr {
+    pub(crate) fn parse(s: &str) -> CargoResult<LicenseExpr> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected `{}`", token);
+        }
+        Ok(expr)
+    }
+
+    /// Returns whether the terms of this expression can be met by only
+    /// choosing licenses for which `allowed` returns `true`.
+    ///
+    /// `allowed` is called with the license identifier, and with the whole
+    /// term, including the `+` and the `WITH` exception, such as
+    /// `GPL-2.0+ WITH Classpath-exception-2.0`.
+    pub(crate) fn is_satisfied_by(&self, allowed: &dyn Fn(&str, &str) -> bool) -> bool {
+        match self {
+            LicenseExpr::License {
+                id,
+                or_later,
+                exception,
+            } => {
+                let mut term = id.clone();
+                if *or_later {
+                    term.push('+');
+                }
+                if let Some(exception) = exception {
+                    term.push_str(" WITH ");
+                    term.push_str(exception);
+                }
+                allowed(id, &term)
+            }
+            LicenseExpr::And(a, b) => a.is_satisfied_by(allowed) && b.is_satisfied_by(allowed),
+            LicenseExpr::Or(a, b) => a.is_satisfied_by(allowed) || b.is_satisfied_by(allowed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LicenseExpr;
+    use super::LicenseExpr::*;
+
+    fn license(id: &str) -> Box<LicenseExpr> {
+        Box::new(License {
+            id: id.to_string(),
+            or_later: false,
+            exception: None,
+        })
+    }
+
+    #[test]
+    fn good_parsing() {
+        fn ok(s: &str, expected: LicenseExpr) {
+            assert_eq!(LicenseExpr::parse(s).unwrap(), expected, "{}", s);
+        }
+
+        ok("MIT", *license("MIT"));
+        ok(
+            "MIT OR Apache-2.0",
+            Or(license("MIT"), license("Apache-2.0")),
+        );
+        ok("MIT/Apache-2.0", Or(license("MIT"), license("Apache-2.0")));
+        ok(
+            "MIT AND BSD-3-Clause OR Apache-2.0",
+            Or(
+                Box::new(And(license("MIT"), license("BSD-3-Clause"))),
+                license("Apache-2.0"),
+            ),
+        );
+        ok(
+            "MIT AND (BSD-3-Clause OR Apache-2.0)",
+            And(
+                license("MIT"),
+                Box::new(Or(license("BSD-3-Clause"), license("Apache-2.0"))),
+            ),
+        );
+        ok(
+            "GPL-2.0+ WITH Classpath-exception-2.0",
+            License {
+                id: "GPL-2.0".to_string(),
+                or_later: true,
+                exception: Some("Classpath-exception-2.0".to_string()),
+            },
+        );
+        ok("LicenseRef-Custom", *license("LicenseRef-Custom"));
+    }
+
+    #[test]
+    fn bad_parsing() {
+        fn err(s: &str, msg: &str) {
+            let e = LicenseExpr::parse(s).unwrap_err();
+            assert_eq!(e.to_string(), msg, "{}", s);
+        }
+
+        err("", "unexpected end of expression");
+        err("MIT OR", "unexpected end of expression");
+        err("MIT Apache-2.0", "unexpected `Apache-2.0`");
+        err("(MIT", "unexpected end of expression");
+        err("MIT)", "unexpected `)`");
+        err("AND MIT", "expected a license identifier, found `AND`");
+        err("MIT WITH (", "expected a license exception, found `(`");
+        err("MIT, Apache-2.0", "unexpected character `,`");
+    }
+
+    #[test]
+    fn satisfied_by() {
+        let allowed = |_: &str, term: &str| {
+            ["MIT", "Apache-2.0", "GPL-2.0 WITH Classpath-exception-2.0"].contains(&term)
+        };
+        let check = |s: &str| LicenseExpr::parse(s).unwrap().is_satisfied_by(&allowed);
+        assert!(check("MIT"));
+        assert!(check("MIT OR GPL-3.0"));
+        assert!(check("MIT AND Apache-2.0"));
+        assert!(!check("MIT AND GPL-3.0"));
+        assert!(!check("GPL-3.0"));
+        assert!(check("(GPL-3.0 OR MIT) AND (Apache-2.0 OR GPL-3.0)"));
+        assert!(!check("MIT+"));
+        assert!(!check("MIT WITH LLVM-exception"));
+        assert!(check("GPL-2.0 WITH Classpath-exception-2.0"));
+        assert!(!check("GPL-2.0+ WITH Classpath-exception-2.0"));
+    }
+}
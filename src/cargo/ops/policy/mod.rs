@@ -0,0 +1,386 @@
+//! Implementation of `cargo policy`.
+//!
+//! This checks every package in the resolved dependency graph of the
+//! workspace against the rules in the `[policy]` config table:
+//!
+//! * `deny-packages`: package ID specs of packages which may not be used.
+//! * `allow-licenses` and `deny-licenses`: SPDX license identifiers which
+//!   the `license` expression of each dependency must be satisfiable with.
+//! * `allow-registries` and `allow-git`: where dependencies may come from.
+//!   Path dependencies are always allowed.
+//! * `duplicates`: whether multiple semver-incompatible versions of the same
+//!   package are allowed, with `allow-duplicates` listing exceptions.
+//!
+//! The whole `Resolve` is checked, independently of the platform and of
+//! whether a dependency is a dev-dependency, since any of them may end up
+//! being built. Workspace members themselves are not checked.
+
+use crate::core::{Package, PackageId, PackageIdSpec, Resolve, SourceId, Workspace};
+use crate::ops;
+use crate::ops::tree::Charset;
+use crate::util::{CanonicalUrl, CargoResult, Config, IntoUrl, VersionExt};
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+mod license;
+
+use license::LicenseExpr;
+
+/// The `[policy]` config table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PolicyConfig {
+    pub deny_packages: Option<Vec<String>>,
+    /// If set, only these licenses may be used.
+    pub allow_licenses: Option<Vec<String>>,
+    pub deny_licenses: Option<Vec<String>>,
+    /// If set, only these registries may be used. `crates-io` is the default
+    /// registry.
+    pub allow_registries: Option<Vec<String>>,
+    /// If set, only git repositories under these URLs may be used.
+    pub allow_git: Option<Vec<String>>,
+    /// What to do with duplicate versions, `warn` by default.
+    pub duplicates: Option<PolicyLevel>,
+    /// Names of packages which are allowed to have duplicate versions.
+    pub allow_duplicates: Option<Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+pub struct PolicyOptions {
+    /// The characters used to draw the paths to the packages.
+    pub charset: Charset,
+}
+
+/// A package which does not comply with the policy.
+struct Violation {
+    level: PolicyLevel,
+    message: String,
+    /// The packages to display the paths to.
+    packages: Vec<PackageId>,
+}
+
+/// Checks the dependencies of the workspace against the `[policy]` table.
+///
+/// Returns an error if any rule with the `deny` level is violated.
+pub fn check_policy(ws: &Workspace<'_>, opts: &PolicyOptions) -> CargoResult<()> {
+    let config = ws.config();
+    let policy = config.get::<PolicyConfig>("policy")?;
+    let rules = Rules::new(config, &policy)?;
+
+    let (pkg_set, resolve) = ops::resolve_ws(ws)?;
+    let members: Vec<PackageId> = ws.members().map(|pkg| pkg.package_id()).collect();
+    let mut ids: Vec<PackageId> = resolve.iter().filter(|id| !members.contains(id)).collect();
+    ids.sort();
+    // The license is only available from the manifest.
+    let mut packages = if rules.checks_licenses() {
+        pkg_set.get_many(ids.iter().cloned())?
+    } else {
+        Vec::new()
+    };
+    packages.sort_by_key(|pkg| pkg.package_id());
+
+    let mut violations = Vec::new();
+    for id in &ids {
+        if let Some(spec) = rules.deny_packages.iter().find(|spec| spec.matches(*id)) {
+            violations.push(Violation {
+                level: PolicyLevel::Deny,
+                message: format!(
+                    "package `{}` is denied by `{}` in `policy.deny-packages`",
+                    id, spec
+                ),
+                packages: vec![*id],
+            });
+        }
+        if let Some(message) = rules.check_source(*id) {
+            violations.push(Violation {
+                level: PolicyLevel::Deny,
+                message,
+                packages: vec![*id],
+            });
+        }
+    }
+    for pkg in packages {
+        if let Some(message) = rules.check_license(pkg) {
+            violations.push(Violation {
+                level: PolicyLevel::Deny,
+                message,
+                packages: vec![pkg.package_id()],
+            });
+        }
+    }
+    if rules.duplicates != PolicyLevel::Allow {
+        violations.extend(find_duplicates(&ids, &rules));
+    }
+
+    let mut errors = 0;
+    for violation in &violations {
+        let mut message = violation.message.clone();
+        for id in &violation.packages {
+            message.push_str("\n\n");
+            message.push_str(&path_to_member(&resolve, &members, *id, opts.charset));
+        }
+        match violation.level {
+            PolicyLevel::Deny => {
+                errors += 1;
+                config.shell().error(message)?;
+            }
+            PolicyLevel::Warn => config.shell().warn(message)?,
+            PolicyLevel::Allow => {}
+        }
+    }
+    if errors > 0 {
+        bail!(
+            "found {} dependency policy violation{}",
+            errors,
+            if errors == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
+/// The `[policy]` table, validated and ready for checking.
+struct Rules {
+    deny_packages: Vec<PackageIdSpec>,
+    allow_licenses: Option<Vec<String>>,
+    deny_licenses: Vec<String>,
+    allow_registries: Option<Vec<SourceId>>,
+    allow_git: Option<Vec<CanonicalUrl>>,
+    duplicates: PolicyLevel,
+    allow_duplicates: HashSet<String>,
+}
+
+impl Rules {
+    fn new(config: &Config, policy: &PolicyConfig) -> CargoResult<Rules> {
+        let deny_packages = policy
+            .deny_packages
+            .iter()
+            .flatten()
+            .map(|spec| {
+                PackageIdSpec::parse(spec).with_context(|| {
+                    format!(
+                        "invalid package ID spec `{}` in `policy.deny-packages`",
+                        spec
+                    )
+                })
+            })
+            .collect::<CargoResult<_>>()?;
+        let allow_registries = match &policy.allow_registries {
+            Some(names) => Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        let source_id = if name == "crates-io" {
+                            SourceId::crates_io(config)
+                        } else {
+                            SourceId::alt_registry(config, name)
+                        };
+                        source_id.with_context(|| {
+                            format!("invalid registry `{}` in `policy.allow-registries`", name)
+                        })
+                    })
+                    .collect::<CargoResult<_>>()?,
+            ),
+            None => None,
+        };
+        let allow_git = match &policy.allow_git {
+            Some(urls) => Some(
+                urls.iter()
+                    .map(|url| {
+                        url.as_str()
+                            .into_url()
+                            .and_then(|url| CanonicalUrl::new(&url))
+                            .with_context(|| format!("invalid URL `{}` in `policy.allow-git`", url))
+                    })
+                    .collect::<CargoResult<_>>()?,
+            ),
+            None => None,
+        };
+        Ok(Rules {
+            deny_packages,
+            allow_licenses: policy.allow_licenses.clone(),
+            deny_licenses: policy.deny_licenses.clone().unwrap_or_default(),
+            allow_registries,
+            allow_git,
+            duplicates: policy.duplicates.unwrap_or(PolicyLevel::Warn),
+            allow_duplicates: policy.allow_duplicates.iter().flatten().cloned().collect(),
+        })
+    }
+
+    fn checks_licenses(&self) -> bool {
+        self.allow_licenses.is_some() || !self.deny_licenses.is_empty()
+    }
+
+    /// Whether the license term `term`, with the license identifier `id`,
+    /// may be used. Denying a license also denies its `+` and `WITH`
+    /// variants, but these must be allowed explicitly.
+    fn is_license_allowed(&self, id: &str, term: &str) -> bool {
+        !self
+            .deny_licenses
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(id) || l.eq_ignore_ascii_case(term))
+            && self.allow_licenses.as_ref().map_or(true, |allow| {
+                allow.iter().any(|l| l.eq_ignore_ascii_case(term))
+            })
+    }
+
+    fn check_license(&self, pkg: &Package) -> Option<String> {
+        let metadata = pkg.manifest().metadata();
+        let license = match &metadata.license {
+            Some(license) => license,
+            // Without an expression there is nothing to check against the
+            // deny list, but it can't be known to be allowed either.
+            None if self.allow_licenses.is_some() => {
+                let file = match &metadata.license_file {
+                    Some(_) => " (it only has a `license-file`)",
+                    None => "",
+                };
+                return Some(format!(
+                    "package `{}` does not specify a license expression{}",
+                    pkg.package_id(),
+                    file
+                ));
+            }
+            None => return None,
+        };
+        match LicenseExpr::parse(license) {
+            Ok(expr) if expr.is_satisfied_by(&|id, term| self.is_license_allowed(id, term)) => None,
+            Ok(_) => Some(format!(
+                "license `{}` of package `{}` is not allowed",
+                license,
+                pkg.package_id()
+            )),
+            Err(e) => Some(format!(
+                "package `{}` has an invalid license expression `{}`: {}",
+                pkg.package_id(),
+                license,
+                e
+            )),
+        }
+    }
+
+    fn check_source(&self, id: PackageId) -> Option<String> {
+        let source_id = id.source_id();
+        if source_id.is_registry() {
+            let allowed = self.allow_registries.as_ref().map_or(true, |allow| {
+                allow
+                    .iter()
+                    .any(|s| s.canonical_url() == source_id.canonical_url())
+            });
+            if !allowed {
+                return Some(format!(
+                    "package `{}` comes from registry `{}`, \
+                     which is not in `policy.allow-registries`",
+                    id,
+                    source_id.display_registry_name()
+                ));
+            }
+        } else if source_id.is_git() {
+            let url = source_id.canonical_url().raw_canonicalized_url().as_str();
+            let allowed = self.allow_git.as_ref().map_or(true, |allow| {
+                allow.iter().any(|prefix| {
+                    let prefix = prefix.raw_canonicalized_url().as_str();
+                    let prefix = prefix.trim_end_matches('/');
+                    url == prefix || url.starts_with(&format!("{}/", prefix))
+                })
+            });
+            if !allowed {
+                return Some(format!(
+                    "package `{}` comes from git repository `{}`, \
+                     which is not in `policy.allow-git`",
+                    id,
+                    source_id.url()
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Finds packages which are used with multiple semver-incompatible versions.
+fn find_duplicates(ids: &[PackageId], rules: &Rules) -> Vec<Violation> {
+    // The packages of each name, grouped by semver-compatible versions.
+    let mut by_name: BTreeMap<&str, Vec<Vec<PackageId>>> = BTreeMap::new();
+    for id in ids {
+        let groups = by_name.entry(id.name().as_str()).or_default();
+        match groups
+            .iter_mut()
+            .find(|group| group[0].version().is_compatible_with(id.version()))
+        {
+            Some(group) => group.push(*id),
+            None => groups.push(vec![*id]),
+        }
+    }
+    by_name
+        .into_iter()
+        .filter(|(name, groups)| groups.len() > 1 && !rules.allow_duplicates.contains(*name))
+        .map(|(name, groups)| {
+            let packages: Vec<PackageId> = groups.into_iter().flatten().collect();
+            let list: Vec<String> = packages
+                .iter()
+                .map(|id| format!("v{}", id.version()))
+                .collect();
+            Violation {
+                level: rules.duplicates,
+                message: format!(
+                    "found {} semver-incompatible versions of `{}`: {}",
+                    packages.len(),
+                    name,
+                    list.join(", ")
+                ),
+                packages,
+            }
+        })
+        .collect()
+}
+
+/// Returns a shortest path from a workspace member to `id`, displayed like
+/// `cargo tree --invert`.
+fn path_to_member(
+    resolve: &Resolve,
+    members: &[PackageId],
+    id: PackageId,
+    charset: Charset,
+) -> String {
+    let mut parents: HashMap<PackageId, Option<PackageId>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut sorted_members = members.to_vec();
+    sorted_members.sort();
+    for member in sorted_members {
+        parents.insert(member, None);
+        queue.push_back(member);
+    }
+    while let Some(pkg) = queue.pop_front() {
+        if pkg == id {
+            break;
+        }
+        for (dep, _) in resolve.deps(pkg) {
+            if !parents.contains_key(&dep) {
+                parents.insert(dep, Some(pkg));
+                queue.push_back(dep);
+            }
+        }
+    }
+
+    let symbols = charset.symbols();
+    let mut lines = vec![id.to_string()];
+    let mut current = parents.get(&id).cloned().flatten();
+    while let Some(pkg) = current {
+        lines.push(format!(
+            "{indent}{ell}{right}{right} {pkg}",
+            indent = "    ".repeat(lines.len() - 1),
+            ell = symbols.ell,
+            right = symbols.right,
+            pkg = pkg
+        ));
+        current = parents[&pkg];
+    }
+    lines.join("\n")
+}
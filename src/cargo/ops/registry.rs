@@ -106,6 +106,7 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         &ops::PackageOpts {
             config: opts.config,
             verify: opts.verify,
+            minimal_versions: false,
             list: false,
             check_metadata: true,
             allow_dirty: opts.allow_dirty,
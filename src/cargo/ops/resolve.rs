@@ -15,9 +15,10 @@ use crate::core::registry::{LockedPatchDependency, PackageRegistry};
 use crate::core::resolver::features::{
     CliFeatures, FeatureOpts, FeatureResolver, ForceAllTargets, RequestedFeatures, ResolvedFeatures,
 };
-use crate::core::resolver::{self, HasDevUnits, Resolve, ResolveOpts, ResolveVersion};
+use crate::core::resolver::{
+    self, ExplainFormat, HasDevUnits, Resolve, ResolveError, ResolveOpts, ResolveVersion,
+};
 use crate::core::summary::Summary;
-use crate::core::Feature;
 use crate::core::{
     GitReference, PackageId, PackageIdSpec, PackageSet, Source, SourceId, Workspace,
 };
@@ -64,6 +65,9 @@ pub fn resolve_ws<'a>(ws: &Workspace<'a>) -> CargoResult<(PackageSet<'a>, Resolv
     let mut registry = PackageRegistry::new(ws.config())?;
     let resolve = resolve_with_registry(ws, &mut registry)?;
     let packages = get_resolved_packages(&resolve, registry)?;
+    if ws.config().cli_unstable().locked_checksums {
+        packages.verify_locked(&resolve)?;
+    }
     Ok((packages, resolve))
 }
 
@@ -120,18 +124,34 @@ pub fn resolve_ws_with_opts<'cfg>(
         ops::load_pkg_lockfile(ws)?
     };
 
+    let feature_opts = FeatureOpts::new(ws, has_dev_units, force_all_targets)?;
+    // Unifying the features of all the members requires the dependencies of
+    // the members which are not selected as well.
+    let all_members: Vec<_>;
+    let (resolve_features, resolve_specs) = if feature_opts.unifies_workspace() {
+        all_members = ws
+            .members()
+            .map(|m| PackageIdSpec::from_package_id(m.package_id()))
+            .collect();
+        (CliFeatures::new_all(true), &all_members[..])
+    } else {
+        (cli_features.clone(), specs)
+    };
     let resolved_with_overrides = resolve_with_previous(
         &mut registry,
         ws,
-        cli_features,
+        &resolve_features,
         has_dev_units,
         resolve.as_ref(),
         None,
-        specs,
+        resolve_specs,
         add_patches,
     )?;
 
     let pkg_set = get_resolved_packages(&resolved_with_overrides, registry)?;
+    if ws.config().cli_unstable().locked_checksums {
+        pkg_set.verify_locked(&resolved_with_overrides)?;
+    }
 
     let member_ids = ws
         .members_with_features(specs, cli_features)?
@@ -147,7 +167,6 @@ pub fn resolve_ws_with_opts<'cfg>(
         force_all_targets,
     )?;
 
-    let feature_opts = FeatureOpts::new(ws, has_dev_units, force_all_targets)?;
     let resolved_features = FeatureResolver::resolve(
         ws,
         target_data,
@@ -421,16 +440,29 @@ pub fn resolve_with_previous<'cfg>(
     };
 
     ws.preload(registry);
-    let mut resolved = resolver::resolve(
+    let mut resolved = match resolver::resolve(
         &summaries,
         &replace,
         registry,
         &try_to_use,
         Some(ws.config()),
-        ws.unstable_features()
-            .require(Feature::public_dependency())
-            .is_ok(),
-    )?;
+        ws.uses_public_dependency(),
+        ws.direct_minimal_versions(),
+    ) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let config = ws.config();
+            if config.cli_unstable().resolve_explain == Some(ExplainFormat::Json) {
+                if let Some(explanation) = e
+                    .downcast_ref::<ResolveError>()
+                    .and_then(|e| e.explanation())
+                {
+                    config.shell().print_json(explanation)?;
+                }
+            }
+            return Err(e);
+        }
+    };
     resolved.register_used_patches(&registry.patches());
     if register_patches {
         // It would be good if this warning was more targeted and helpful
@@ -451,11 +483,63 @@ pub fn resolve_with_previous<'cfg>(
         }
     }
     if let Some(previous) = previous {
+        if ws.config().cli_unstable().locked_checksums {
+            verify_locked_checksums(previous, &resolved)?;
+        }
         resolved.merge_from(previous)?;
     }
     Ok(resolved)
 }
 
+/// Checks that every package of `resolved` is locked in `previous`, and that
+/// every registry package is locked with the checksum provided by its source,
+/// for `-Z locked-checksums`.
+///
+/// All the packages are checked before returning an error, so that it lists
+/// every package which needs to be fixed.
+fn verify_locked_checksums(previous: &Resolve, resolved: &Resolve) -> CargoResult<()> {
+    let locked: HashSet<PackageId> = previous.iter().collect();
+    let mut problems = Vec::new();
+    for id in resolved.iter() {
+        if id.source_id().is_path() {
+            continue;
+        }
+        if !locked.contains(&id) {
+            problems.push(format!("`{}` is not locked", id));
+            continue;
+        }
+        if !id.source_id().is_registry() {
+            continue;
+        }
+        let locked_checksum = previous.checksums().get(&id).and_then(|c| c.as_deref());
+        let checksum = resolved.checksums().get(&id).and_then(|c| c.as_deref());
+        match (locked_checksum, checksum) {
+            (None, _) => problems.push(format!("`{}` is locked without a checksum", id)),
+            (Some(_), None) => problems.push(format!(
+                "`{}` has no checksum in its source `{}`",
+                id,
+                id.source_id()
+            )),
+            (Some(locked), Some(actual)) if locked != actual => problems.push(format!(
+                "`{}` is locked with checksum {}, but its source `{}` provides {}",
+                id,
+                locked,
+                id.source_id(),
+                actual
+            )),
+            (Some(_), Some(_)) => {}
+        }
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    problems.sort();
+    anyhow::bail!(
+        "Cargo.lock cannot be verified with -Z locked-checksums:\n  {}",
+        problems.join("\n  ")
+    )
+}
+
 /// Read the `paths` configuration variable to discover all path overrides that
 /// have been configured.
 pub fn add_overrides<'a>(
@@ -0,0 +1,188 @@
+//! Code coverage of `cargo test`.
+//!
+//! The workspace members are built with `-C instrument-coverage` (or
+//! `-Z instrument-coverage` on nightly compilers before Rust 1.60), and each
+//! test binary writes its raw profiles to `target/coverage/profraw`. After
+//! the tests, the profiles are merged with `llvm-profdata`, and `llvm-cov`
+//! exports the coverage of the sources of the workspace from them. Both tools
+//! come with the `llvm-tools-preview` rustup component, or can be found in
+//! `PATH`.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use cargo_util::{paths, ProcessBuilder};
+
+use crate::core::Workspace;
+use crate::util::errors::CargoResult;
+use crate::util::Rustc;
+
+/// The format of a coverage report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoverageFormat {
+    /// An `lcov.info` tracefile.
+    Lcov,
+    /// The JSON export of `llvm-cov`.
+    Json,
+}
+
+impl CoverageFormat {
+    pub fn parse(s: &str) -> CargoResult<CoverageFormat> {
+        match s {
+            "lcov" => Ok(CoverageFormat::Lcov),
+            "json" => Ok(CoverageFormat::Json),
+            _ => anyhow::bail!("invalid coverage format `{}`, expected `lcov` or `json`", s),
+        }
+    }
+}
+
+/// Returns the rustc flag instrumenting a crate for coverage. It was
+/// stabilized as `-C instrument-coverage` in Rust 1.60, and is only available
+/// as `-Z instrument-coverage` on the nightly compilers before it.
+pub fn instrument_coverage_flag(rustc: &Rustc) -> CargoResult<[&'static str; 2]> {
+    let version = &rustc.version;
+    if (version.major, version.minor) >= (1, 60) {
+        return Ok(["-C", "instrument-coverage"]);
+    }
+    let channel = version.pre.split('.').next().unwrap_or("");
+    if channel == "nightly" || channel == "dev" {
+        return Ok(["-Z", "instrument-coverage"]);
+    }
+    anyhow::bail!(
+        "`--coverage` requires rustc 1.60 or newer, or a nightly rustc, \
+         but the current rustc is {}",
+        version
+    )
+}
+
+/// The coverage data of a test run, in `target/coverage`.
+pub struct Coverage {
+    format: CoverageFormat,
+    dir: PathBuf,
+    /// The rustc flag instrumenting the doc tests.
+    flag: [&'static str; 2],
+}
+
+impl Coverage {
+    /// Prepares to measure the coverage of the tests, removing the profiles
+    /// of a previous run.
+    pub fn new(ws: &Workspace<'_>, format: CoverageFormat) -> CargoResult<Coverage> {
+        let dir = ws.target_dir().join("coverage").into_path_unlocked();
+        let rustc = ws.config().load_global_rustc(Some(ws))?;
+        let flag = instrument_coverage_flag(&rustc)?;
+        let coverage = Coverage { format, dir, flag };
+        let profraw = coverage.profraw_dir();
+        if profraw.exists() {
+            paths::remove_dir_all(&profraw)?;
+        }
+        paths::create_dir_all(&profraw)?;
+        Ok(coverage)
+    }
+
+    /// Returns the rustc flag instrumenting a crate for coverage.
+    pub fn rustc_flag(&self) -> [&'static str; 2] {
+        self.flag
+    }
+
+    fn profraw_dir(&self) -> PathBuf {
+        self.dir.join("profraw")
+    }
+
+    /// Returns the value of `LLVM_PROFILE_FILE` for a test binary, so that
+    /// each of its processes writes its own profile.
+    pub fn profile_file(&self, name: &str) -> PathBuf {
+        self.profraw_dir().join(format!("{}-%p-%m.profraw", name))
+    }
+
+    /// Merges the profiles written by the test binaries in `objects`, and
+    /// writes the report.
+    pub fn report(
+        &self,
+        ws: &Workspace<'_>,
+        tools_dir: &Path,
+        objects: &[&Path],
+    ) -> CargoResult<()> {
+        let config = ws.config();
+        let profraw_dir = self.profraw_dir();
+        let mut profiles = Vec::new();
+        let entries = fs::read_dir(&profraw_dir)
+            .with_context(|| format!("failed to read directory `{}`", profraw_dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension() == Some(OsStr::new("profraw")) {
+                profiles.push(path);
+            }
+        }
+        profiles.sort();
+        if profiles.is_empty() || objects.is_empty() {
+            return config
+                .shell()
+                .warn("no coverage data was written by the tests");
+        }
+
+        let (profdata, cov) = match (
+            find_tool(tools_dir, "llvm-profdata"),
+            find_tool(tools_dir, "llvm-cov"),
+        ) {
+            (Some(profdata), Some(cov)) => (profdata, cov),
+            _ => {
+                return config.shell().warn(format!(
+                    "`llvm-profdata` and `llvm-cov` were not found, \
+                     the raw coverage data is left in `{}`\n\
+                     They can be installed with `rustup component add llvm-tools-preview`",
+                    profraw_dir.display()
+                ));
+            }
+        };
+
+        let merged = self.dir.join("coverage.profdata");
+        ProcessBuilder::new(profdata)
+            .arg("merge")
+            .arg("-sparse")
+            .args(&profiles)
+            .arg("-o")
+            .arg(&merged)
+            .exec_with_output()?;
+
+        let (format, file_name) = match self.format {
+            CoverageFormat::Lcov => ("lcov", "lcov.info"),
+            CoverageFormat::Json => ("text", "coverage.json"),
+        };
+        let mut export = ProcessBuilder::new(cov);
+        export
+            .arg("export")
+            .arg(format!("-format={}", format))
+            .arg("-instr-profile")
+            .arg(&merged)
+            .arg(objects[0]);
+        for object in &objects[1..] {
+            export.arg("-object").arg(object);
+        }
+        // Only the sources of the workspace are reported.
+        export.arg(ws.root());
+        let output = export.exec_with_output()?;
+
+        let report = self.dir.join(file_name);
+        paths::write(&report, output.stdout)?;
+        config.shell().status(
+            "Coverage",
+            format!("report written to `{}`", report.display()),
+        )
+    }
+}
+
+/// Finds an LLVM tool in the sysroot of rustc, or in `PATH`.
+fn find_tool(tools_dir: &Path, name: &str) -> Option<PathBuf> {
+    let exe = format!("{}{}", name, env::consts::EXE_SUFFIX);
+    let in_sysroot = tools_dir.join(&exe);
+    if in_sysroot.is_file() {
+        return Some(in_sysroot);
+    }
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(&exe))
+        .find(|path| path.is_file())
+}
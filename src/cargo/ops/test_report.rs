@@ -0,0 +1,330 @@
+//! Reports of the results of `cargo test`, as JUnit XML or JSON.
+//!
+//! To record the result of each test, the libtest harness is run with its
+//! unstable JSON output, which is parsed line by line and rendered back
+//! into the usual human-readable output. Test targets without a harness are
+//! recorded as a single test named after the target.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cargo_util::paths;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::util::errors::CargoResult;
+
+/// The version of the JSON report, increased on incompatible changes.
+const REPORT_VERSION: u32 = 1;
+
+/// Where to write the reports of a test run.
+#[derive(Clone, Debug, Default)]
+pub struct TestReportOptions {
+    /// The path of the JUnit XML report.
+    pub junit: Option<PathBuf>,
+    /// The path of the JSON report.
+    pub json: Option<PathBuf>,
+}
+
+impl TestReportOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.junit.is_some() || self.json.is_some()
+    }
+}
+
+/// A test binary, or the doc tests of a library.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TestSuite {
+    pub package: String,
+    pub target: String,
+    /// The kind of the target, like `lib` or `integration-test`, or
+    /// `doctest` for doc tests.
+    pub kind: &'static str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub package: String,
+    pub target: String,
+    pub kind: &'static str,
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// The duration of the test in seconds, if it is known.
+    pub duration: Option<f64>,
+    /// The captured output of a failed test.
+    pub output: Option<String>,
+}
+
+/// The results of all the tests run by `cargo test`.
+#[derive(Debug, Serialize)]
+pub struct TestReport {
+    version: u32,
+    pub tests: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn new() -> TestReport {
+        TestReport {
+            version: REPORT_VERSION,
+            tests: Vec::new(),
+        }
+    }
+
+    /// Writes the reports requested in `opts`.
+    pub fn write(&self, opts: &TestReportOptions) -> CargoResult<()> {
+        if let Some(path) = &opts.json {
+            paths::write(path, serde_json::to_string_pretty(self)?)?;
+        }
+        if let Some(path) = &opts.junit {
+            paths::write(path, self.to_junit())?;
+        }
+        Ok(())
+    }
+
+    fn to_junit(&self) -> String {
+        // The results of a suite are contiguous, as suites are run one after
+        // another, or their output is printed one after another.
+        let mut suites: Vec<(String, Vec<&TestResult>)> = Vec::new();
+        for result in &self.tests {
+            let name = format!("{}::{}::{}", result.package, result.kind, result.target);
+            match suites.last_mut() {
+                Some((last, results)) if *last == name => results.push(result),
+                _ => suites.push((name, vec![result])),
+            }
+        }
+
+        let count = |results: &[&TestResult], outcome| {
+            results.iter().filter(|r| r.outcome == outcome).count()
+        };
+        let all: Vec<&TestResult> = self.tests.iter().collect();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            xml,
+            "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            all.len(),
+            count(&all, TestOutcome::Failed),
+            count(&all, TestOutcome::Ignored)
+        )
+        .unwrap();
+        for (name, results) in &suites {
+            let time: f64 = results.iter().filter_map(|r| r.duration).sum();
+            writeln!(
+                xml,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+                escape(name),
+                results.len(),
+                count(results, TestOutcome::Failed),
+                count(results, TestOutcome::Ignored),
+                time
+            )
+            .unwrap();
+            for result in results {
+                write!(
+                    xml,
+                    "    <testcase classname=\"{}\" name=\"{}\"",
+                    escape(name),
+                    escape(&result.name)
+                )
+                .unwrap();
+                if let Some(duration) = result.duration {
+                    write!(xml, " time=\"{:.3}\"", duration).unwrap();
+                }
+                match result.outcome {
+                    TestOutcome::Passed => xml.push_str("/>\n"),
+                    TestOutcome::Ignored => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                    TestOutcome::Failed => {
+                        xml.push_str(">\n      <failure message=\"test failed\"/>\n");
+                        if let Some(output) = &result.output {
+                            writeln!(xml, "      <system-out>{}</system-out>", escape(output))
+                                .unwrap();
+                        }
+                        xml.push_str("    </testcase>\n");
+                    }
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Escapes text for XML, dropping the control characters XML cannot
+/// represent.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The output of a test suite while it runs, from which its results are
+/// recorded.
+pub struct TestRun {
+    suite: TestSuite,
+    harness: bool,
+    /// The output of a test target without a harness.
+    output: String,
+    /// The failed tests of the current run of the harness, with their
+    /// output, printed when the run finishes.
+    failures: Vec<(String, String)>,
+    results: Vec<TestResult>,
+}
+
+impl TestRun {
+    /// `harness` is whether the output is the JSON output of libtest.
+    pub fn new(suite: TestSuite, harness: bool) -> TestRun {
+        TestRun {
+            suite,
+            harness,
+            output: String::new(),
+            failures: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Handles a line of the standard output of the suite, returning the
+    /// text to print in its place.
+    pub fn stdout_line(&mut self, line: &str) -> Option<String> {
+        if !self.harness {
+            self.output.push_str(line);
+            self.output.push('\n');
+            return Some(line.to_string());
+        }
+        let event: Value = match serde_json::from_str(line) {
+            Ok(event @ Value::Object(_)) => event,
+            // The test printed it without capture.
+            _ => return Some(line.to_string()),
+        };
+        match (event["type"].as_str(), event["event"].as_str()) {
+            (Some("suite"), Some("started")) => {
+                let count = event["test_count"].as_u64().unwrap_or(0);
+                let plural = if count == 1 { "" } else { "s" };
+                Some(format!("\nrunning {} test{}", count, plural))
+            }
+            (Some("suite"), Some(event_name)) => Some(self.suite_finished(event_name, &event)),
+            (Some("test"), Some(event_name)) => self.test_finished(event_name, &event),
+            _ => Some(line.to_string()),
+        }
+    }
+
+    /// Handles a line of the standard error of the suite.
+    pub fn stderr_line(&mut self, line: &str) {
+        if !self.harness {
+            self.output.push_str(line);
+            self.output.push('\n');
+        }
+    }
+
+    /// Records the results of the suite in `report`. `duration` is the time
+    /// the whole suite took to run.
+    pub fn finish(self, success: bool, duration: Duration, report: &mut TestReport) {
+        if self.harness {
+            report.tests.extend(self.results);
+            return;
+        }
+        let TestSuite {
+            package,
+            target,
+            kind,
+        } = self.suite;
+        report.tests.push(TestResult {
+            package,
+            name: target.clone(),
+            target,
+            kind,
+            outcome: if success {
+                TestOutcome::Passed
+            } else {
+                TestOutcome::Failed
+            },
+            duration: Some(duration.as_secs_f64()),
+            output: if success { None } else { Some(self.output) },
+        });
+    }
+
+    fn test_finished(&mut self, event_name: &str, event: &Value) -> Option<String> {
+        let (outcome, status) = match event_name {
+            "ok" => (TestOutcome::Passed, "ok"),
+            "failed" => (TestOutcome::Failed, "FAILED"),
+            "ignored" => (TestOutcome::Ignored, "ignored"),
+            // `started`, and `timeout` for tests running for a long time.
+            _ => return None,
+        };
+        let name = event["name"].as_str().unwrap_or_default().to_string();
+        let output = if outcome == TestOutcome::Failed {
+            let mut output = event["stdout"].as_str().unwrap_or_default().to_string();
+            if let Some(message) = event["message"].as_str() {
+                output.push_str(message);
+                output.push('\n');
+            }
+            self.failures.push((name.clone(), output.clone()));
+            Some(output)
+        } else {
+            None
+        };
+        // Older versions of libtest write the time as a string like "0.001s".
+        let duration = match &event["exec_time"] {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim_end_matches('s').parse().ok(),
+            _ => None,
+        };
+        let line = format!("test {} ... {}", name, status);
+        self.results.push(TestResult {
+            package: self.suite.package.clone(),
+            target: self.suite.target.clone(),
+            kind: self.suite.kind,
+            name,
+            outcome,
+            duration,
+            output,
+        });
+        Some(line)
+    }
+
+    fn suite_finished(&mut self, event_name: &str, event: &Value) -> String {
+        let mut text = String::new();
+        if !self.failures.is_empty() {
+            text.push_str("\nfailures:\n");
+            for (name, output) in &self.failures {
+                write!(text, "\n---- {} stdout ----\n{}", name, output).unwrap();
+            }
+            text.push_str("\nfailures:\n");
+            for (name, _) in self.failures.drain(..) {
+                writeln!(text, "    {}", name).unwrap();
+            }
+        }
+        let count = |key: &str| event[key].as_u64().unwrap_or(0);
+        writeln!(
+            text,
+            "\ntest result: {}. {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            if event_name == "ok" { "ok" } else { "FAILED" },
+            count("passed"),
+            count("failed"),
+            count("ignored"),
+            count("measured"),
+            count("filtered_out")
+        )
+        .unwrap();
+        text
+    }
+}
@@ -0,0 +1,122 @@
+//! Splitting the tests of `cargo test` into shards, to run them on several
+//! machines.
+//!
+//! The unit of a shard is a test suite: a test binary, or the doc tests of a
+//! library. Every machine builds the same tests, and computes the same
+//! assignment of the suites to the shards. When a JSON report of a previous
+//! run is given, the suites are balanced by their durations in that report,
+//! otherwise they are counted as taking the same time.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use cargo_util::paths;
+use serde_json::Value;
+
+use crate::ops::test_report::TestSuite;
+use crate::util::errors::CargoResult;
+
+/// One of the shards the tests are split into.
+#[derive(Clone, Debug)]
+pub struct TestShard {
+    /// The shard to run, from 1 to `count`.
+    pub index: usize,
+    pub count: usize,
+    /// A JSON report of a previous run, with the durations of the tests.
+    pub durations: Option<PathBuf>,
+}
+
+impl TestShard {
+    /// Parses a shard in the `N/M` format of `--shard`.
+    pub fn parse(s: &str, durations: Option<PathBuf>) -> CargoResult<TestShard> {
+        let mut parts = s.splitn(2, '/');
+        let parsed = match (parts.next(), parts.next()) {
+            (Some(index), Some(count)) => index.parse().ok().zip(count.parse().ok()),
+            _ => None,
+        };
+        match parsed {
+            Some((index, count)) if 1 <= index && index <= count => Ok(TestShard {
+                index,
+                count,
+                durations,
+            }),
+            _ => bail!(
+                "invalid shard `{}`, expected `N/M` with N between 1 and M",
+                s
+            ),
+        }
+    }
+
+    /// Returns the suites of `suites` which belong to this shard.
+    pub fn select(&self, suites: &[TestSuite]) -> CargoResult<HashSet<TestSuite>> {
+        let durations = match &self.durations {
+            Some(path) => load_durations(path).with_context(|| {
+                format!("failed to read test durations from `{}`", path.display())
+            })?,
+            None => BTreeMap::new(),
+        };
+        // The same suite may be built for several targets.
+        let suites: BTreeSet<&TestSuite> = suites.iter().collect();
+        // Suites missing from the report are counted as taking the average
+        // time of the others.
+        let duration = |suite: &TestSuite| {
+            let key = (
+                suite.package.clone(),
+                suite.kind.to_string(),
+                suite.target.clone(),
+            );
+            durations.get(&key).copied()
+        };
+        let known: Vec<f64> = suites.iter().copied().filter_map(duration).collect();
+        let default = if known.is_empty() {
+            1.0
+        } else {
+            known.iter().sum::<f64>() / known.len() as f64
+        };
+        let mut weighted: Vec<(&TestSuite, f64)> = suites
+            .into_iter()
+            .map(|suite| (suite, duration(suite).unwrap_or(default)))
+            .collect();
+
+        // Assigns the longest suites first, each to the shard with the least
+        // total duration so far.
+        weighted.sort_by(|(a, a_weight), (b, b_weight)| {
+            b_weight
+                .partial_cmp(a_weight)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.cmp(b))
+        });
+        let mut loads = vec![0.0; self.count];
+        let mut selected = HashSet::new();
+        for (suite, weight) in weighted {
+            let mut shard = 0;
+            for (i, load) in loads.iter().enumerate() {
+                if *load < loads[shard] {
+                    shard = i;
+                }
+            }
+            loads[shard] += weight;
+            if shard + 1 == self.index {
+                selected.insert(suite.clone());
+            }
+        }
+        Ok(selected)
+    }
+}
+
+/// Loads the total duration of each suite from a JSON test report, by
+/// package, kind and target.
+fn load_durations(path: &Path) -> CargoResult<BTreeMap<(String, String, String), f64>> {
+    let report: Value = serde_json::from_str(&paths::read(path)?)?;
+    if report["version"] != 1 {
+        bail!("unsupported report version `{}`", report["version"]);
+    }
+    let mut durations = BTreeMap::new();
+    for test in report["tests"].as_array().into_iter().flatten() {
+        let field = |key: &str| test[key].as_str().unwrap_or_default().to_string();
+        let key = (field("package"), field("kind"), field("target"));
+        *durations.entry(key).or_default() += test["duration"].as_f64().unwrap_or(0.0);
+    }
+    Ok(durations)
+}
@@ -0,0 +1,252 @@
+//! Machine-readable output of the `cargo tree` graph (`--output-format`).
+
+use super::format::Pattern;
+use super::{EdgeKind, Graph, Node, TreeOptions, EDGE_KINDS};
+use crate::core::compiler::CompileKind;
+use crate::core::dependency::DepKind;
+use crate::core::{PackageId, PackageIdSpec};
+use crate::drop_println;
+use crate::util::interning::InternedString;
+use crate::util::{CargoResult, Config};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Version of the JSON output, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// The portion of the graph that would be displayed by the text output.
+///
+/// Nodes are renumbered in the order they are discovered, so that the output
+/// is stable for the same dependency graph.
+struct Subgraph {
+    /// Graph indexes of the included nodes.
+    nodes: Vec<usize>,
+    /// Outgoing edges of each entry in `nodes`, as `(to, kind)` where `to` is
+    /// a position in `nodes`.
+    edges: Vec<Vec<(usize, EdgeKind)>>,
+    /// Positions of the roots in `nodes`.
+    roots: Vec<usize>,
+}
+
+impl Subgraph {
+    /// Collects all nodes reachable from the given roots, honoring the
+    /// `--prune`, `--depth`, and `-e no-proc-macro` options.
+    fn new(
+        graph: &Graph<'_>,
+        roots: &[usize],
+        pkgs_to_prune: &[PackageIdSpec],
+        opts: &TreeOptions,
+    ) -> Subgraph {
+        let mut subgraph = Subgraph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            roots: Vec::new(),
+        };
+        let mut positions: HashMap<usize, usize> = HashMap::new();
+        // Breadth-first, so that each node is first seen at its minimum depth.
+        let mut queue = VecDeque::new();
+        for root in roots {
+            let pos = subgraph.add(&mut positions, *root);
+            subgraph.roots.push(pos);
+            queue.push_back((*root, 0));
+        }
+        while let Some((index, depth)) = queue.pop_front() {
+            if depth >= opts.max_display_depth {
+                continue;
+            }
+            let from = positions[&index];
+            for kind in &EDGE_KINDS {
+                for dep in graph.connected_nodes(index, kind) {
+                    if let Node::Package { package_id, .. } = graph.node(dep) {
+                        if opts.no_proc_macro && graph.package_for_id(*package_id).proc_macro() {
+                            continue;
+                        }
+                        if pkgs_to_prune.iter().any(|spec| spec.matches(*package_id)) {
+                            continue;
+                        }
+                    }
+                    let to = match positions.get(&dep) {
+                        Some(pos) => *pos,
+                        None => {
+                            queue.push_back((dep, depth + 1));
+                            subgraph.add(&mut positions, dep)
+                        }
+                    };
+                    subgraph.edges[from].push((to, *kind));
+                }
+            }
+        }
+        subgraph
+    }
+
+    fn add(&mut self, positions: &mut HashMap<usize, usize>, index: usize) -> usize {
+        let pos = self.nodes.len();
+        self.nodes.push(index);
+        self.edges.push(Vec::new());
+        positions.insert(index, pos);
+        pos
+    }
+}
+
+fn edge_kind_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Dep(DepKind::Normal) => "normal",
+        EdgeKind::Dep(DepKind::Build) => "build",
+        EdgeKind::Dep(DepKind::Development) => "dev",
+        EdgeKind::Feature => "feature",
+    }
+}
+
+/// Escapes a string for use inside a double-quoted DOT identifier.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Prints the graph in the Graphviz DOT language.
+///
+/// Package nodes are labeled with the `--format` pattern, followed by their
+/// enabled features and the target platform when built for a `--target`.
+/// Build and dev dependency edges are labeled with their kind, and feature
+/// edges are dotted.
+pub fn print_dot(
+    config: &Config,
+    opts: &TreeOptions,
+    roots: &[usize],
+    pkgs_to_prune: &[PackageIdSpec],
+    graph: &Graph<'_>,
+) -> CargoResult<()> {
+    let format = Pattern::new(&opts.format)
+        .with_context(|| format!("tree format `{}` not valid", opts.format))?;
+    let subgraph = Subgraph::new(graph, roots, pkgs_to_prune, opts);
+
+    drop_println!(config, "digraph {{");
+    for (pos, index) in subgraph.nodes.iter().enumerate() {
+        let mut label = dot_escape(&format.display(graph, *index).to_string());
+        match graph.node(*index) {
+            Node::Package { features, kind, .. } => {
+                if !features.is_empty() {
+                    label.push_str(&format!("\\nfeatures: {}", features.join(",")));
+                }
+                if let CompileKind::Target(target) = kind {
+                    label.push_str(&format!("\\ntarget: {}", dot_escape(target.short_name())));
+                }
+                let shape = if subgraph.roots.contains(&pos) {
+                    ", shape=box, style=bold"
+                } else {
+                    ""
+                };
+                drop_println!(config, "    {} [label=\"{}\"{}];", pos, label, shape);
+            }
+            Node::Feature { .. } => {
+                drop_println!(config, "    {} [label=\"{}\", shape=note];", pos, label);
+            }
+        }
+    }
+    for (from, edges) in subgraph.edges.iter().enumerate() {
+        for (to, kind) in edges {
+            let attrs = match kind {
+                EdgeKind::Dep(DepKind::Normal) => "",
+                EdgeKind::Dep(DepKind::Build) => " [label=\"build\"]",
+                EdgeKind::Dep(DepKind::Development) => " [label=\"dev\", style=dashed]",
+                EdgeKind::Feature => " [style=dotted]",
+            };
+            drop_println!(config, "    {} -> {}{};", from, to, attrs);
+        }
+    }
+    drop_println!(config, "}}");
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SerializedGraph {
+    version: u32,
+    roots: Vec<usize>,
+    nodes: Vec<SerializedNode>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum SerializedNode {
+    Package {
+        id: PackageId,
+        features: Vec<InternedString>,
+        /// The `--target` platform, `null` for the host.
+        platform: CompileKind,
+        proc_macro: bool,
+        dependencies: Vec<SerializedEdge>,
+    },
+    Feature {
+        /// The package this feature belongs to.
+        package_id: PackageId,
+        name: InternedString,
+        /// Whether the feature was enabled from the command-line.
+        command_line: bool,
+        dependencies: Vec<SerializedEdge>,
+    },
+}
+
+#[derive(Serialize)]
+struct SerializedEdge {
+    index: usize,
+    kind: &'static str,
+}
+
+/// Prints the graph as a single JSON object.
+///
+/// The format is similar to `--unit-graph`: a list of nodes, where each node
+/// lists its outgoing edges as indexes into that list.
+pub fn print_json(
+    config: &Config,
+    opts: &TreeOptions,
+    roots: &[usize],
+    pkgs_to_prune: &[PackageIdSpec],
+    graph: &Graph<'_>,
+) -> CargoResult<()> {
+    let subgraph = Subgraph::new(graph, roots, pkgs_to_prune, opts);
+    let nodes = subgraph
+        .nodes
+        .iter()
+        .zip(&subgraph.edges)
+        .map(|(index, edges)| {
+            let dependencies = edges
+                .iter()
+                .map(|(to, kind)| SerializedEdge {
+                    index: *to,
+                    kind: edge_kind_name(*kind),
+                })
+                .collect();
+            match graph.node(*index) {
+                Node::Package {
+                    package_id,
+                    features,
+                    kind,
+                } => SerializedNode::Package {
+                    id: *package_id,
+                    features: features.clone(),
+                    platform: *kind,
+                    proc_macro: graph.package_for_id(*package_id).proc_macro(),
+                    dependencies,
+                },
+                Node::Feature { node_index, name } => {
+                    let package_id = match graph.node(*node_index) {
+                        Node::Package { package_id, .. } => *package_id,
+                        node => panic!("unexpected feature node {:?}", node),
+                    };
+                    SerializedNode::Feature {
+                        package_id,
+                        name: *name,
+                        command_line: graph.is_cli_feature(*index),
+                        dependencies,
+                    }
+                }
+            }
+        })
+        .collect();
+    let s = SerializedGraph {
+        version: VERSION,
+        roots: subgraph.roots,
+        nodes,
+    };
+    config.shell().print_json(&s)
+}
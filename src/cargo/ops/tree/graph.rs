@@ -126,6 +126,11 @@ impl<'a> Graph<'a> {
         &self.nodes[index]
     }
 
+    /// Returns an iterator over all nodes along with their index.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = (usize, &Node)> {
+        self.nodes.iter().enumerate()
+    }
+
     /// Given a slice of PackageIds, returns the indexes of all nodes that match.
     pub fn indexes_from_ids(&self, package_ids: &[PackageId]) -> Vec<usize> {
         let mut result: Vec<(&Node, usize)> = self
@@ -161,6 +166,14 @@ impl<'a> Graph<'a> {
         self.cli_features.contains(&index)
     }
 
+    /// Returns the indexes of all feature nodes enabled via the command-line.
+    pub fn cli_feature_indexes(&self) -> Vec<usize> {
+        let mut result: Vec<usize> = self.cli_features.iter().copied().collect();
+        // Sort for consistent output.
+        result.sort_unstable_by(|a, b| self.nodes[*a].cmp(&self.nodes[*b]));
+        result
+    }
+
     /// Returns a new graph by removing all nodes not reachable from the
     /// given nodes.
     pub fn from_reachable(&self, roots: &[usize]) -> Graph<'a> {
@@ -13,10 +13,20 @@ use graph::Graph;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
+mod export;
 mod format;
 mod graph;
+mod why;
 
-pub use {graph::EdgeKind, graph::Node};
+pub use {graph::EdgeKind, graph::Node, why::WhyTarget};
+
+/// All edge kinds, in the order they are displayed for each node.
+const EDGE_KINDS: [EdgeKind; 4] = [
+    EdgeKind::Dep(DepKind::Normal),
+    EdgeKind::Dep(DepKind::Build),
+    EdgeKind::Dep(DepKind::Development),
+    EdgeKind::Feature,
+];
 
 pub struct TreeOptions {
     pub cli_features: CliFeatures,
@@ -49,6 +59,11 @@ pub struct TreeOptions {
     pub max_display_depth: u32,
     /// Exculdes proc-macro dependencies.
     pub no_proc_macro: bool,
+    /// How the graph is written to stdout.
+    pub output_format: OutputFormat,
+    /// If set, only display the shortest paths explaining why this package
+    /// or feature is included. Requires `graph_features`.
+    pub why: Option<WhyTarget>,
 }
 
 #[derive(PartialEq)]
@@ -68,11 +83,22 @@ impl Target {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum Charset {
     Utf8,
     Ascii,
 }
 
+impl Charset {
+    /// The symbols used to draw trees with this character set.
+    pub(crate) fn symbols(self) -> &'static Symbols {
+        match self {
+            Charset::Utf8 => &UTF8_SYMBOLS,
+            Charset::Ascii => &ASCII_SYMBOLS,
+        }
+    }
+}
+
 impl FromStr for Charset {
     type Err = &'static str;
 
@@ -85,6 +111,29 @@ impl FromStr for Charset {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The indented tree, with each package displayed with `format`.
+    Text,
+    /// A Graphviz DOT graph.
+    Dot,
+    /// A JSON object listing the nodes and their edges.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<OutputFormat, &'static str> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "dot" => Ok(OutputFormat::Dot),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("invalid output format"),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Prefix {
     None,
@@ -105,11 +154,11 @@ impl FromStr for Prefix {
     }
 }
 
-struct Symbols {
-    down: &'static str,
-    tee: &'static str,
-    ell: &'static str,
-    right: &'static str,
+pub(crate) struct Symbols {
+    pub(crate) down: &'static str,
+    pub(crate) tee: &'static str,
+    pub(crate) ell: &'static str,
+    pub(crate) right: &'static str,
 }
 
 static UTF8_SYMBOLS: Symbols = Symbols {
@@ -189,6 +238,10 @@ pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()
     let root_ids = ws_resolve.targeted_resolve.specs_to_ids(&root_specs)?;
     let root_indexes = graph.indexes_from_ids(&root_ids);
 
+    if let Some(why) = &opts.why {
+        return why::print_why(ws.config(), opts, why, &root_indexes, &graph);
+    }
+
     let root_indexes = if opts.duplicates {
         // `-d -p foo` will only show duplicates within foo's subtree
         graph = graph.from_reachable(root_indexes.as_slice());
@@ -213,7 +266,15 @@ pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()
         })
         .collect::<CargoResult<Vec<PackageIdSpec>>>()?;
 
-    print(ws.config(), opts, root_indexes, &pkgs_to_prune, &graph)?;
+    match opts.output_format {
+        OutputFormat::Text => print(ws.config(), opts, root_indexes, &pkgs_to_prune, &graph)?,
+        OutputFormat::Dot => {
+            export::print_dot(ws.config(), opts, &root_indexes, &pkgs_to_prune, &graph)?
+        }
+        OutputFormat::Json => {
+            export::print_json(ws.config(), opts, &root_indexes, &pkgs_to_prune, &graph)?
+        }
+    }
     Ok(())
 }
 
@@ -228,10 +289,7 @@ fn print(
     let format = Pattern::new(&opts.format)
         .with_context(|| format!("tree format `{}` not valid", opts.format))?;
 
-    let symbols = match opts.charset {
-        Charset::Utf8 => &UTF8_SYMBOLS,
-        Charset::Ascii => &ASCII_SYMBOLS,
-    };
+    let symbols = opts.charset.symbols();
 
     // The visited deps is used to display a (*) whenever a dep has
     // already been printed (ignored with --no-dedupe).
@@ -0,0 +1,232 @@
+//! Implementation of `cargo tree --why`.
+//!
+//! This finds the shortest path from each root (workspace members and
+//! features enabled on the command-line) to the requested package or feature
+//! in the feature graph, and prints each path with an explanation of every
+//! edge along the way. The feature graph is built from the results of the
+//! `FeatureResolver`, so each feature edge corresponds to an activation that
+//! the resolver made.
+
+use super::format::Pattern;
+use super::{EdgeKind, Graph, Node, TreeOptions, EDGE_KINDS};
+use crate::core::dependency::DepKind;
+use crate::core::PackageIdSpec;
+use crate::drop_println;
+use crate::util::interning::InternedString;
+use crate::util::{CargoResult, Config};
+use anyhow::{bail, Context};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// What `--why` is asking about.
+pub struct WhyTarget {
+    /// The package to explain.
+    spec: PackageIdSpec,
+    /// If set, explain why this feature of the package is enabled instead of
+    /// the package itself.
+    feature: Option<InternedString>,
+}
+
+impl WhyTarget {
+    /// Parses the `--why` argument, which is either a package ID spec, or
+    /// `SPEC/FEATURE` to ask about a feature.
+    pub fn parse(s: &str) -> CargoResult<WhyTarget> {
+        // URL specs contain slashes, and can't name a feature.
+        let (spec, feature) = match s.find('/') {
+            Some(pos) if !s.contains("://") => (&s[..pos], Some(&s[pos + 1..])),
+            _ => (s, None),
+        };
+        let spec = PackageIdSpec::parse(spec)?;
+        let feature = match feature {
+            Some("") => bail!("feature name in `--why {}` must not be empty", s),
+            Some(f) => Some(InternedString::new(f)),
+            None => None,
+        };
+        Ok(WhyTarget { spec, feature })
+    }
+
+    fn matches(&self, graph: &Graph<'_>, node: &Node) -> bool {
+        match (node, self.feature) {
+            (Node::Package { package_id, .. }, None) => self.spec.matches(*package_id),
+            (Node::Feature { node_index, name }, Some(feature)) => {
+                *name == feature
+                    && match graph.node(*node_index) {
+                        Node::Package { package_id, .. } => self.spec.matches(*package_id),
+                        _ => false,
+                    }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Prints the shortest paths from the roots to each node matching `why`.
+///
+/// `roots` are the workspace members selected on the command-line. Features
+/// enabled on the command-line are also treated as roots, since they are not
+/// reachable from the members.
+pub fn print_why(
+    config: &Config,
+    opts: &TreeOptions,
+    why: &WhyTarget,
+    roots: &[usize],
+    graph: &Graph<'_>,
+) -> CargoResult<()> {
+    let format = Pattern::new(&opts.format)
+        .with_context(|| format!("tree format `{}` not valid", opts.format))?;
+
+    let targets: HashSet<usize> = graph
+        .iter_nodes()
+        .filter(|(_, node)| why.matches(graph, node))
+        .map(|(i, _)| i)
+        .collect();
+    if targets.is_empty() {
+        match why.feature {
+            Some(feature) => bail!(
+                "feature `{}` is not enabled on any package matching `{}`",
+                feature,
+                why.spec
+            ),
+            None => bail!(
+                "package `{}` was not found in the dependency graph",
+                why.spec
+            ),
+        }
+    }
+
+    let mut paths: Vec<Vec<(usize, Option<EdgeKind>)>> = Vec::new();
+    for root in roots {
+        for path in shortest_paths(graph, *root, &targets) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    for root in graph.cli_feature_indexes() {
+        for path in shortest_paths(graph, root, &targets) {
+            // Paths through a member (such as from its "default" feature) are
+            // already explained by the paths from that member.
+            if path.iter().any(|(index, _)| roots.contains(index)) {
+                continue;
+            }
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    if paths.is_empty() {
+        bail!("`{}` is not reachable from the selected packages", why.spec);
+    }
+
+    for (i, path) in paths.iter().enumerate() {
+        if i != 0 {
+            drop_println!(config);
+        }
+        let mut prev = None;
+        for (index, kind) in path {
+            let display = format.display(graph, *index);
+            match (prev, kind) {
+                (Some(from), Some(kind)) => {
+                    let reason = explain_edge(graph, from, *index, *kind);
+                    drop_println!(config, "  -> {} ({})", display, reason);
+                }
+                _ => drop_println!(config, "{}", display),
+            }
+            prev = Some(*index);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the shortest path from `root` to each reachable node in `targets`.
+///
+/// Each path is a list of `(node, edge)` pairs, where `edge` is the kind of
+/// the edge leading into that node (`None` for the root).
+fn shortest_paths(
+    graph: &Graph<'_>,
+    root: usize,
+    targets: &HashSet<usize>,
+) -> Vec<Vec<(usize, Option<EdgeKind>)>> {
+    // Map of node to the node and edge it was first reached from.
+    let mut parents: HashMap<usize, Option<(usize, EdgeKind)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut found = Vec::new();
+    parents.insert(root, None);
+    queue.push_back(root);
+    while let Some(index) = queue.pop_front() {
+        if targets.contains(&index) {
+            found.push(index);
+            // Don't look further, the path would go through this target.
+            continue;
+        }
+        for kind in &EDGE_KINDS {
+            for dep in graph.connected_nodes(index, kind) {
+                if !parents.contains_key(&dep) {
+                    parents.insert(dep, Some((index, *kind)));
+                    queue.push_back(dep);
+                }
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|target| {
+            let mut path = vec![];
+            let mut current = (target, parents[&target]);
+            loop {
+                match current {
+                    (index, Some((parent, kind))) => {
+                        path.push((index, Some(kind)));
+                        current = (parent, parents[&parent]);
+                    }
+                    (index, None) => {
+                        path.push((index, None));
+                        break;
+                    }
+                }
+            }
+            path.reverse();
+            path
+        })
+        .collect()
+}
+
+/// Returns a description of why the edge from `from` to `to` exists.
+fn explain_edge(graph: &Graph<'_>, from: usize, to: usize, kind: EdgeKind) -> String {
+    let dep_kind = |kind: DepKind| match kind {
+        DepKind::Normal => "normal",
+        DepKind::Build => "build",
+        DepKind::Development => "dev",
+    };
+    match (graph.node(from), graph.node(to), kind) {
+        (Node::Package { .. }, Node::Package { .. }, EdgeKind::Dep(kind)) => {
+            format!("{} dependency", dep_kind(kind))
+        }
+        (Node::Package { package_id, .. }, Node::Feature { .. }, EdgeKind::Dep(kind)) => format!(
+            "requested by the {} dependency of `{}`",
+            dep_kind(kind),
+            package_id.name()
+        ),
+        (Node::Package { package_id, .. }, _, EdgeKind::Feature) => {
+            format!("enabled by `{}`", package_id.name())
+        }
+        (Node::Feature { name, .. }, Node::Package { .. }, _) => {
+            format!("activated by feature \"{}\"", name)
+        }
+        (
+            Node::Feature {
+                name,
+                node_index: from_pkg,
+            },
+            Node::Feature {
+                node_index: to_pkg, ..
+            },
+            _,
+        ) => match graph.node(*from_pkg) {
+            Node::Package { package_id, .. } if from_pkg != to_pkg => {
+                format!("enabled by feature \"{}\" of `{}`", name, package_id.name())
+            }
+            _ => format!("enabled by feature \"{}\"", name),
+        },
+    }
+}
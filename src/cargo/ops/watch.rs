@@ -0,0 +1,158 @@
+//! The watch mode of `cargo build`, `check`, `test` and `run`, which reruns
+//! the command whenever the sources of the workspace change.
+//!
+//! The files watched are the ones the fingerprints of the workspace members
+//! look at: the files listed in their dep-info, and the `rerun-if-changed`
+//! paths of their build scripts, with their manifests. Like fingerprints,
+//! changes are found by comparing mtimes, which are polled. The `Config` is
+//! kept between runs, but the workspace is loaded again each time.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use cargo_util::paths;
+use filetime::FileTime;
+
+use crate::core::Workspace;
+use crate::sources::PathSource;
+use crate::util::errors::CargoResult;
+use crate::util::Config;
+
+/// How often the files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the files must stay unchanged before the command is run again,
+/// so that a burst of writes, like saving several files, runs it once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The result of one run of a watched command.
+#[derive(Default)]
+pub struct WatchRun {
+    /// The files the command depends on, if it got far enough to know them.
+    pub files: Option<BTreeSet<PathBuf>>,
+    /// A process started by the command, like the program of `cargo run`,
+    /// which is killed before running the command again.
+    pub process: Option<Child>,
+}
+
+/// Runs `run`, then runs it again each time the files it depends on change,
+/// until cargo is interrupted.
+///
+/// Errors of `run` are printed, and the files of the workspace members are
+/// watched instead if it did not get to record its files. Errors while
+/// waiting for changes or for the process of `run` are reported as warnings,
+/// so that the session keeps going.
+pub fn watch(
+    config: &Config,
+    root_manifest: &Path,
+    mut run: impl FnMut(&mut WatchRun) -> CargoResult<()>,
+) -> CargoResult<()> {
+    let mut files = BTreeSet::new();
+    loop {
+        let start = FileTime::from_system_time(SystemTime::now());
+        let mut current = WatchRun::default();
+        if let Err(e) = run(&mut current) {
+            crate::display_error(&e, &mut config.shell());
+        }
+        match current.files {
+            Some(recorded) => files = recorded,
+            None => files.extend(member_files(config, root_manifest)),
+        }
+        files.insert(root_manifest.to_path_buf());
+
+        config.shell().status(
+            "Watching",
+            format!("{} files for changes, press Ctrl-C to stop", files.len()),
+        )?;
+        let mut process = current.process;
+        wait_for_change(config, &files, start, &mut process)?;
+        if let Some(mut process) = process {
+            // It may have exited already.
+            let _ = process.kill();
+            if let Err(e) = process.wait() {
+                config
+                    .shell()
+                    .warn(format!("failed to wait for the process to exit: {}", e))?;
+            }
+        }
+    }
+}
+
+/// Returns the files of the workspace members, to watch when a build fails
+/// before its files are known.
+fn member_files(config: &Config, root_manifest: &Path) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    // A broken manifest is watched by itself, until it is fixed.
+    if let Ok(ws) = Workspace::new(root_manifest, config) {
+        for pkg in ws.members() {
+            files.insert(pkg.manifest_path().to_path_buf());
+            let source = PathSource::new(pkg.root(), pkg.package_id().source_id(), config);
+            if let Ok(list) = source.list_files(pkg) {
+                files.extend(list);
+            }
+        }
+    }
+    files
+}
+
+fn mtimes(files: &BTreeSet<PathBuf>) -> HashMap<&Path, Option<FileTime>> {
+    files
+        .iter()
+        .map(|file| (file.as_path(), paths::mtime(file).ok()))
+        .collect()
+}
+
+/// Waits until one of `files` changes, or has changed since `start`, and
+/// then stays unchanged for the debounce delay. Reaps `process` if it exits
+/// in the meantime.
+///
+/// Files which can't be read, like a file being replaced by an editor, are
+/// seen as missing until they can be read again.
+fn wait_for_change(
+    config: &Config,
+    files: &BTreeSet<PathBuf>,
+    start: FileTime,
+    process: &mut Option<Child>,
+) -> CargoResult<()> {
+    let mut last = mtimes(files);
+    let changed_during_run = last
+        .values()
+        .any(|mtime| matches!(mtime, Some(mtime) if *mtime > start));
+    if !changed_during_run {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if let Some(child) = process {
+                match child.try_wait() {
+                    Ok(None) => {}
+                    Ok(Some(_)) => *process = None,
+                    Err(e) => {
+                        // Stops checking the process, which is still killed
+                        // before the next run.
+                        config
+                            .shell()
+                            .warn(format!("failed to check if the process exited: {}", e))?;
+                        if let Some(mut child) = process.take() {
+                            let _ = child.kill();
+                        }
+                    }
+                }
+            }
+            let current = mtimes(files);
+            if current != last {
+                last = current;
+                break;
+            }
+        }
+    }
+    loop {
+        thread::sleep(DEBOUNCE);
+        let current = mtimes(files);
+        if current == last {
+            return Ok(());
+        }
+        last = current;
+    }
+}
@@ -92,14 +92,17 @@ impl<'cfg> RegistryData for LocalRegistry<'cfg> {
         let mut crate_file = paths::open(&path)?;
 
         // If we've already got an unpacked version of this crate, then skip the
-        // checksum below as it is in theory already verified.
+        // checksum below as it is in theory already verified, unless
+        // `-Z locked-checksums` asks to verify it anyway.
         let dst = format!("{}-{}", pkg.name(), pkg.version());
         if self.src_path.join(dst).into_path_unlocked().exists() {
-            return Ok(MaybeLock::Ready(crate_file));
+            if !self.config.cli_unstable().locked_checksums {
+                return Ok(MaybeLock::Ready(crate_file));
+            }
+        } else {
+            self.config.shell().status("Unpacking", pkg)?;
         }
 
-        self.config.shell().status("Unpacking", pkg)?;
-
         // We don't actually need to download anything per-se, we just need to
         // verify the checksum matches the .crate file itself.
         let actual = Sha256::new().update_file(&crate_file)?.finish_hex();
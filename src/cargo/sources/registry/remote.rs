@@ -243,7 +243,7 @@ impl<'cfg> RegistryData for RemoteRegistry<'cfg> {
         Ok(())
     }
 
-    fn download(&mut self, pkg: PackageId, _checksum: &str) -> CargoResult<MaybeLock> {
+    fn download(&mut self, pkg: PackageId, checksum: &str) -> CargoResult<MaybeLock> {
         let filename = self.filename(pkg);
 
         // Attempt to open an read-only copy first to avoid an exclusive write
@@ -254,9 +254,22 @@ impl<'cfg> RegistryData for RemoteRegistry<'cfg> {
         // have to redownload the file.
         let path = self.cache_path.join(&filename);
         let path = self.config.assert_package_cache_locked(&path);
-        if let Ok(dst) = File::open(&path) {
+        if let Ok(mut dst) = File::open(&path) {
             let meta = dst.metadata()?;
             if meta.len() > 0 {
+                // The cached file was verified when it was downloaded, but
+                // `-Z locked-checksums` also guards against later changes.
+                if self.config.cli_unstable().locked_checksums {
+                    let actual = Sha256::new().update_file(&dst)?.finish_hex();
+                    if actual != checksum {
+                        anyhow::bail!(
+                            "failed to verify the checksum of `{}` in `{}`",
+                            pkg,
+                            path.display()
+                        )
+                    }
+                    dst.seek(SeekFrom::Start(0))?;
+                }
                 return Ok(MaybeLock::Ready(dst));
             }
         }
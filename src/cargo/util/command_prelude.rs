@@ -1,7 +1,9 @@
 use crate::core::compiler::{BuildConfig, MessageFormat};
 use crate::core::resolver::CliFeatures;
 use crate::core::{Edition, Workspace};
-use crate::ops::{CompileFilter, CompileOptions, NewOptions, Packages, VersionControl};
+use crate::ops::{
+    self, CompileFilter, CompileOptions, NewOptions, Packages, VersionControl, WatchRun,
+};
 use crate::sources::CRATES_IO_REGISTRY;
 use crate::util::important_paths::find_root_manifest_for_wd;
 use crate::util::interning::InternedString;
@@ -15,6 +17,8 @@ use crate::CargoResult;
 use anyhow::bail;
 use cargo_util::paths;
 use clap::{self, SubCommand};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
@@ -228,6 +232,23 @@ pub trait AppExt: Sized {
             "Outputs a future incompatibility report at the end of the build (unstable)",
         ))
     }
+
+    fn arg_affected(self) -> Self {
+        self._arg(
+            opt(
+                "affected",
+                "Select only the packages affected by the changes in a git revision range (unstable)",
+            )
+            .value_name("REVS"),
+        )
+    }
+
+    fn arg_watch(self) -> Self {
+        self._arg(opt(
+            "watch",
+            "Rerun the command when the sources of the workspace change (unstable)",
+        ))
+    }
 }
 
 impl AppExt for App {
@@ -472,6 +493,9 @@ pub trait ArgMatchesExt {
         build_config.build_plan = self._is_present("build-plan");
         build_config.unit_graph = self._is_present("unit-graph");
         build_config.future_incompat_report = self._is_present("future-incompat-report");
+        if self._is_present("watch") {
+            build_config.watched_files = RefCell::new(Some(BTreeSet::new()));
+        }
         if build_config.build_plan {
             config
                 .cli_unstable()
@@ -654,6 +678,57 @@ about this warning.";
         Ok(())
     }
 
+    /// Selects the workspace members affected by the changes given with
+    /// `--affected`. Returns `false` if no member is affected, so there is
+    /// nothing to do.
+    fn select_affected(
+        &self,
+        config: &Config,
+        workspace: &Workspace<'_>,
+        compile_opts: &mut CompileOptions,
+    ) -> CargoResult<bool> {
+        let revs = match self._value_of("affected") {
+            Some(revs) => revs,
+            None => return Ok(true),
+        };
+        if !config.cli_unstable().affected {
+            bail!("Usage of `--affected` requires `-Z affected`");
+        }
+        if !matches!(compile_opts.spec, Packages::Default) {
+            bail!("`--affected` cannot be used together with `--package`, `--workspace` or `--exclude`");
+        }
+        let affected = ops::affected_packages(workspace, revs)?;
+        if affected.is_empty() {
+            return Ok(false);
+        }
+        compile_opts.spec = Packages::Packages(affected);
+        Ok(true)
+    }
+
+    /// Runs `run` once, or with `--watch`, again each time the sources of the
+    /// workspace change. `run` is given the results of the run to fill in
+    /// when watching.
+    fn watch(
+        &self,
+        config: &Config,
+        mut run: impl FnMut(Option<&mut WatchRun>) -> CliResult,
+    ) -> CliResult {
+        if !self._is_present("watch") {
+            return run(None);
+        }
+        if !config.cli_unstable().watch {
+            return Err(anyhow::format_err!("Usage of `--watch` requires `-Z watch`").into());
+        }
+        let root_manifest = self.root_manifest(config)?;
+        ops::watch(config, &root_manifest, |current| {
+            run(Some(current)).map_err(|err| match err.error {
+                Some(err) => err,
+                None => anyhow::format_err!("the command failed with exit code {}", err.exit_code),
+            })
+        })?;
+        Ok(())
+    }
+
     fn is_present_with_zero_values(&self, name: &str) -> bool {
         self._is_present(name) && self._value_of(name).is_none()
     }
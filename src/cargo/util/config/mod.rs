@@ -61,6 +61,7 @@ use std::io::prelude::*;
 use std::io::{self, SeekFrom};
 use std::mem;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Once;
 use std::time::Instant;
@@ -73,7 +74,7 @@ use crate::ops;
 use crate::util::errors::CargoResult;
 use crate::util::toml as cargo_toml;
 use crate::util::validate_package_name;
-use crate::util::{FileLock, Filesystem, IntoUrl, IntoUrlWithBase, Rustc};
+use crate::util::{DaemonCache, FileLock, Filesystem, IntoUrl, IntoUrlWithBase, Rustc};
 use anyhow::{anyhow, bail, format_err, Context as _};
 use cargo_util::paths;
 use curl::easy::Easy;
@@ -184,6 +185,8 @@ pub struct Config {
     doc_extern_map: LazyCell<RustdocExternMap>,
     progress_config: ProgressConfig,
     env_config: LazyCell<EnvConfig>,
+    /// State kept between the commands run by `cargo daemon`.
+    daemon_cache: Option<Rc<DaemonCache>>,
     /// This should be false if:
     /// - this is an artifact of the rustc distribution process for "stable" or for "beta"
     /// - this is an `#[test]` that does not opt in with `enable_nightly_features`
@@ -284,6 +287,7 @@ impl Config {
             doc_extern_map: LazyCell::new(),
             progress_config: ProgressConfig::default(),
             env_config: LazyCell::new(),
+            daemon_cache: None,
             nightly_features_allowed: matches!(&*features::channel(), "nightly" | "dev"),
         }
     }
@@ -647,6 +651,16 @@ impl Config {
         self.env = env;
     }
 
+    /// Shares the state of `cargo daemon` with the command run with this
+    /// config.
+    pub fn set_daemon_cache(&mut self, cache: Rc<DaemonCache>) {
+        self.daemon_cache = Some(cache);
+    }
+
+    pub(crate) fn daemon_cache(&self) -> Option<&DaemonCache> {
+        self.daemon_cache.as_deref()
+    }
+
     /// Returns all environment variables.
     pub(crate) fn env(&self) -> &HashMap<String, String> {
         &self.env
@@ -985,11 +999,12 @@ impl Config {
     }
 
     pub fn offline(&self) -> bool {
-        self.offline
+        self.offline || self.unstable_flags.locked_checksums
     }
 
+    /// Whether `--frozen` was passed, which `-Z locked-checksums` implies.
     pub fn frozen(&self) -> bool {
-        self.frozen
+        self.frozen || self.unstable_flags.locked_checksums
     }
 
     pub fn locked(&self) -> bool {
@@ -997,7 +1012,7 @@ impl Config {
     }
 
     pub fn lock_update_allowed(&self) -> bool {
-        !self.frozen && !self.locked
+        !self.frozen() && !self.locked
     }
 
     /// Loads configuration from the filesystem.
@@ -2080,7 +2095,7 @@ pub struct CargoBuildConfig {
     pub dep_info_basedir: Option<ConfigRelativePath>,
     pub target_dir: Option<ConfigRelativePath>,
     pub incremental: Option<bool>,
-    pub target: Option<ConfigRelativePath>,
+    pub target: Option<BuildTargetConfig>,
     pub jobs: Option<u32>,
     pub rustflags: Option<StringList>,
     pub rustdocflags: Option<StringList>,
@@ -2091,6 +2106,44 @@ pub struct CargoBuildConfig {
     pub out_dir: Option<ConfigRelativePath>,
 }
 
+/// The `build.target` config value, a target or a list of targets.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct BuildTargetConfig {
+    inner: Value<BuildTargetConfigInner>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BuildTargetConfigInner {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl BuildTargetConfig {
+    /// Returns the targets, with the paths of `.json` target specifications
+    /// resolved relative to the config file defining them.
+    pub fn values(&self, config: &Config) -> Vec<String> {
+        let resolve = |target: &String| {
+            if target.ends_with(".json") {
+                self.inner
+                    .definition
+                    .root(config)
+                    .join(target)
+                    .to_str()
+                    .expect("must be utf-8 in toml")
+                    .to_string()
+            } else {
+                target.clone()
+            }
+        };
+        match &self.inner.val {
+            BuildTargetConfigInner::One(target) => vec![resolve(target)],
+            BuildTargetConfigInner::Many(targets) => targets.iter().map(resolve).collect(),
+        }
+    }
+}
+
 #[derive(Deserialize, Default)]
 struct TermConfig {
     verbose: Option<bool>,
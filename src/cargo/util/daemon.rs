@@ -0,0 +1,438 @@
+//! A long-lived server running `cargo build` and `cargo check` for the cargo
+//! processes started with `-Z daemon`, so that they don't have to load and
+//! parse all the manifests of the workspace each time.
+//!
+//! Like the `LockServer`, it is a TCP server on the loopback interface, with
+//! its address written to `$CARGO_HOME/daemon.json`. That file also holds a
+//! random token, which clients must send back, so that only the users who can
+//! read the file can run commands in the daemon. On unix, the file is only
+//! readable by its owner, and it is created that way before the token is
+//! written. On other platforms, it gets the permissions of `$CARGO_HOME`, and
+//! the daemon should not be used if other users can read it.
+//!
+//! The daemon serves one client at a time. A client sends its arguments,
+//! working directory and environment as a JSON line, and the daemon replies
+//! with the output of the command and then its exit code, one JSON message
+//! per line.
+//!
+//! Each command gets a new `Config`, but the manifests parsed by the previous
+//! commands are kept in a `DaemonCache`, and reused as long as the contents of
+//! their files and the layout of their packages are the same. Only manifests
+//! are kept: each command still reads the lock file, resolves the
+//! dependencies and checks the fingerprints, and the target information comes
+//! from the rustc info cache as usual. The daemon declines to run commands
+//! from clients with another version of cargo or another environment, which
+//! then run the command themselves.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use anyhow::{bail, Context as _};
+use cargo_util::paths;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{EitherManifest, Shell, SourceId};
+use crate::util::errors::CargoResult;
+use crate::util::Config;
+
+/// Environment variables set by shells, which don't affect commands.
+const IGNORED_ENV: &[&str] = &["OLDPWD", "PWD", "SHLVL", "_"];
+
+/// How long to wait for a client to send its command.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The state kept between the commands run by the daemon.
+///
+/// Only parsed manifests are kept. The `Workspace`, `Resolve`,
+/// `RustcTargetData` and fingerprints are built again by each command, as
+/// reusing them would need the daemon to notice changes to the lock file, the
+/// registry indexes, `rustc` and the build outputs.
+#[derive(Debug, Default)]
+pub struct DaemonCache {
+    manifests: RefCell<HashMap<PathBuf, CachedManifest>>,
+}
+
+#[derive(Debug)]
+struct CachedManifest {
+    contents: String,
+    source_id: SourceId,
+    /// The unstable flags the manifest was parsed with, which change what
+    /// is accepted in it.
+    unstable: String,
+    /// The files the targets, build script and readme of the package are
+    /// inferred from.
+    layout: Vec<PathBuf>,
+    manifest: EitherManifest,
+    nested_paths: Vec<PathBuf>,
+}
+
+impl DaemonCache {
+    pub fn new() -> DaemonCache {
+        DaemonCache::default()
+    }
+
+    /// Returns the manifest at `path` parsed by a previous command, if its
+    /// file still has the same contents and its package the same layout.
+    pub(crate) fn manifest(
+        &self,
+        path: &Path,
+        contents: &str,
+        source_id: SourceId,
+        config: &Config,
+    ) -> Option<(EitherManifest, Vec<PathBuf>)> {
+        let manifests = self.manifests.borrow();
+        let cached = manifests.get(path)?;
+        if cached.contents == contents
+            && cached.source_id == source_id
+            && cached.unstable == unstable_key(config)
+            && cached.layout == package_layout(path)
+        {
+            Some((cached.manifest.clone(), cached.nested_paths.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn insert_manifest(
+        &self,
+        path: &Path,
+        contents: String,
+        source_id: SourceId,
+        config: &Config,
+        (manifest, nested_paths): &(EitherManifest, Vec<PathBuf>),
+    ) {
+        self.manifests.borrow_mut().insert(
+            path.to_path_buf(),
+            CachedManifest {
+                contents,
+                source_id,
+                unstable: unstable_key(config),
+                layout: package_layout(path),
+                manifest: manifest.clone(),
+                nested_paths: nested_paths.clone(),
+            },
+        );
+    }
+}
+
+fn unstable_key(config: &Config) -> String {
+    format!(
+        "{:?} {}",
+        config.cli_unstable(),
+        config.nightly_features_allowed
+    )
+}
+
+/// Lists the files of the package of the manifest at `manifest_path` which
+/// are looked for when parsing it: the files in its root directory and in
+/// `src`, and the targets in the directories targets are inferred from.
+fn package_layout(manifest_path: &Path) -> Vec<PathBuf> {
+    let root = match manifest_path.parent() {
+        Some(root) => root,
+        None => return Vec::new(),
+    };
+    let list = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect()
+    };
+    let mut layout = list(root);
+    layout.extend(list(&root.join("src")));
+    for dir in &["src/bin", "examples", "tests", "benches"] {
+        for path in list(&root.join(dir)) {
+            let main = path.join("main.rs");
+            if main.exists() {
+                layout.push(main);
+            }
+            layout.push(path);
+        }
+    }
+    layout.sort();
+    layout
+}
+
+/// The contents of `$CARGO_HOME/daemon.json`.
+#[derive(Serialize, Deserialize)]
+struct DaemonInfo {
+    addr: SocketAddr,
+    token: String,
+    pid: u32,
+}
+
+/// A command sent to the daemon.
+#[derive(Serialize, Deserialize)]
+pub struct DaemonRequest {
+    token: String,
+    version: String,
+    /// Whether to stop the daemon instead of running a command.
+    stop: bool,
+    /// The arguments of the command, starting with the name of the program.
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    env: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum DaemonMessage {
+    /// Output of the command, to write to stderr.
+    Output(String),
+    /// The command finished with this exit code.
+    Exit(i32),
+    /// The command was not run, for this reason.
+    Declined(String),
+}
+
+fn info_path(config: &Config) -> PathBuf {
+    config.home().join("daemon.json").into_path_unlocked()
+}
+
+fn read_info(config: &Config) -> Option<DaemonInfo> {
+    let contents = paths::read(&info_path(config)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Writes the daemon info, making the file readable only by its owner before
+/// the token gets into it.
+fn write_info(path: &Path, info: &DaemonInfo) -> io::Result<()> {
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        opts.mode(0o600);
+        let file = opts.open(path)?;
+        // The mode only applies to new files, and the file may be left over
+        // from a previous daemon.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        write_info_to(file, info)
+    }
+    #[cfg(not(unix))]
+    {
+        write_info_to(opts.open(path)?, info)
+    }
+}
+
+fn write_info_to(mut file: fs::File, info: &DaemonInfo) -> io::Result<()> {
+    serde_json::to_writer(&mut file, info)?;
+    file.flush()
+}
+
+fn environment() -> BTreeMap<String, String> {
+    env::vars()
+        .filter(|(key, _)| !IGNORED_ENV.contains(&key.as_str()))
+        .collect()
+}
+
+fn send(stream: &mut TcpStream, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Writes the output of a command run by the daemon to its client.
+struct OutputWriter(TcpStream);
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf).into_owned();
+        send(&mut self.0, &DaemonMessage::Output(text))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+pub struct DaemonServer {
+    listener: TcpListener,
+    addr: SocketAddr,
+    token: String,
+    info_path: PathBuf,
+}
+
+impl DaemonServer {
+    /// Starts listening for commands, and writes the address of the daemon
+    /// to `$CARGO_HOME/daemon.json`.
+    pub fn new(config: &Config) -> CargoResult<DaemonServer> {
+        if let Some(info) = read_info(config) {
+            if TcpStream::connect(info.addr).is_ok() {
+                bail!(
+                    "a cargo daemon is already running on {}, with process ID {}",
+                    info.addr,
+                    info.pid
+                );
+            }
+        }
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .with_context(|| "failed to bind TCP listener for the cargo daemon")?;
+        let addr = listener.local_addr()?;
+        let token = format!("{:032x}", rand::random::<u128>());
+        let info = DaemonInfo {
+            addr,
+            token: token.clone(),
+            pid: process::id(),
+        };
+        let info_path = info_path(config);
+        config.home().create_dir()?;
+        write_info(&info_path, &info)
+            .with_context(|| format!("failed to write `{}`", info_path.display()))?;
+        Ok(DaemonServer {
+            listener,
+            addr,
+            token,
+            info_path,
+        })
+    }
+
+    pub fn addr(&self) -> &SocketAddr {
+        &self.addr
+    }
+
+    /// Serves commands until the daemon is stopped. `run` runs a command,
+    /// writing its output to the given shell, and returns its exit code.
+    pub fn run(
+        &self,
+        config: &Config,
+        mut run: impl FnMut(DaemonRequest, Shell) -> i32,
+    ) -> CargoResult<()> {
+        let env = environment();
+        let version = crate::version().to_string();
+        for stream in self.listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // A client which doesn't send its command would block the other
+            // ones.
+            if stream.set_read_timeout(Some(REQUEST_TIMEOUT)).is_err() {
+                continue;
+            }
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let request: DaemonRequest = match serde_json::from_str(&line) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+            if request.token != self.token {
+                continue;
+            }
+            if request.stop {
+                drop(send(&mut stream, &DaemonMessage::Exit(0)));
+                return Ok(());
+            }
+            let declined = if request.version != version {
+                Some(format!("the daemon is running cargo {}", version))
+            } else if request.env != env {
+                Some("the environment differs from the one of the daemon".to_string())
+            } else {
+                None
+            };
+            if let Some(reason) = declined {
+                drop(send(&mut stream, &DaemonMessage::Declined(reason)));
+                continue;
+            }
+
+            config.shell().verbose(|shell| {
+                shell.status(
+                    "Running",
+                    format!(
+                        "`{}` in `{}`",
+                        request.args.join(" "),
+                        request.cwd.display()
+                    ),
+                )
+            })?;
+            let shell = Shell::from_write(Box::new(OutputWriter(stream.try_clone()?)));
+            let code = run(request, shell);
+            // The client may have been interrupted.
+            drop(send(&mut stream, &DaemonMessage::Exit(code)));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DaemonServer {
+    fn drop(&mut self) {
+        // Another daemon may have been started since.
+        let is_ours = paths::read(&self.info_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<DaemonInfo>(&contents).ok())
+            .map_or(false, |info| info.token == self.token);
+        if is_ours {
+            drop(paths::remove_file(&self.info_path));
+        }
+    }
+}
+
+/// Sends a request to the running daemon, returning the connection to it, or
+/// `None` if there is no daemon.
+fn connect(config: &Config, args: Vec<String>, stop: bool) -> CargoResult<Option<TcpStream>> {
+    let info = match read_info(config) {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    // The daemon may have been killed without removing its file.
+    let mut stream = match TcpStream::connect(info.addr) {
+        Ok(stream) => stream,
+        Err(_) => return Ok(None),
+    };
+    let request = DaemonRequest {
+        token: info.token,
+        version: crate::version().to_string(),
+        stop,
+        args,
+        cwd: config.cwd().to_path_buf(),
+        env: environment(),
+    };
+    send(&mut stream, &request).with_context(|| "failed to send command to the cargo daemon")?;
+    Ok(Some(stream))
+}
+
+/// Runs the command with the arguments `args` in the daemon, returning its
+/// exit code, or `None` if the command must be run by this process instead.
+pub fn run_in_daemon(config: &Config, args: Vec<String>) -> CargoResult<Option<i32>> {
+    let stream = match connect(config, args, false)? {
+        Some(stream) => stream,
+        None => return Ok(None),
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = line.with_context(|| "failed to read from the cargo daemon")?;
+        match serde_json::from_str(&line)? {
+            DaemonMessage::Output(text) => write!(config.shell().err(), "{}", text)?,
+            DaemonMessage::Exit(code) => return Ok(Some(code)),
+            DaemonMessage::Declined(reason) => {
+                config.shell().verbose(|shell| {
+                    shell.note(format!("not running in the cargo daemon: {}", reason))
+                })?;
+                return Ok(None);
+            }
+        }
+    }
+    bail!("the cargo daemon stopped before the command finished")
+}
+
+/// Stops the running daemon, returning whether there was one.
+pub fn stop_daemon(config: &Config) -> CargoResult<bool> {
+    let stream = match connect(config, Vec::new(), true)? {
+        Some(stream) => stream,
+        None => return Ok(false),
+    };
+    // Waits for the daemon to acknowledge it.
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(true)
+}
@@ -4,6 +4,7 @@ use std::time::Duration;
 pub use self::canonical_url::CanonicalUrl;
 pub use self::config::{homedir, Config, ConfigValue};
 pub(crate) use self::counter::MetricsCounter;
+pub use self::daemon::{DaemonCache, DaemonServer};
 pub use self::dependency_queue::DependencyQueue;
 pub use self::diagnostic_server::RustfixDiagnosticServer;
 pub use self::errors::{internal, CargoResult, CliResult, Test};
@@ -33,6 +34,7 @@ pub mod command_prelude;
 pub mod config;
 mod counter;
 pub mod cpu;
+pub mod daemon;
 mod dependency_queue;
 pub mod diagnostic_server;
 pub mod errors;
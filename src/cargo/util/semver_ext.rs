@@ -9,6 +9,10 @@ pub enum OptVersionReq {
 
 pub trait VersionExt {
     fn is_prerelease(&self) -> bool;
+
+    /// Returns whether `self` and `other` have the same leftmost non-zero
+    /// component, that is, whether they are considered semver compatible.
+    fn is_compatible_with(&self, other: &Version) -> bool;
 }
 
 pub trait VersionReqExt {
@@ -19,6 +23,14 @@ impl VersionExt for Version {
     fn is_prerelease(&self) -> bool {
         !self.pre.is_empty()
     }
+
+    fn is_compatible_with(&self, other: &Version) -> bool {
+        match (self.major, self.minor) {
+            (0, 0) => other.major == 0 && other.minor == 0 && other.patch == self.patch,
+            (0, minor) => other.major == 0 && other.minor == minor,
+            (major, _) => other.major == major,
+        }
+    }
 }
 
 impl VersionReqExt for VersionReq {
@@ -0,0 +1,502 @@
+//! Editing of manifests which preserves their formatting and comments.
+//!
+//! Values are located by scanning the manifest line by line, which handles
+//! table headers, dotted keys and inline tables on a single line. This covers
+//! how package fields and dependencies are written in practice. Values
+//! spanning several lines, such as multi-line strings and arrays, are skipped
+//! over so that their contents are not mistaken for keys, but they cannot be
+//! found themselves.
+
+use std::fmt;
+
+/// The location of a value in a manifest: its line, and its byte range in
+/// that line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A manifest which can be edited as text.
+pub struct ManifestEditor {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+impl ManifestEditor {
+    pub fn new(contents: &str) -> ManifestEditor {
+        ManifestEditor {
+            lines: contents.lines().map(|line| line.to_string()).collect(),
+            trailing_newline: contents.ends_with('\n'),
+        }
+    }
+
+    /// Returns the index of the line with the `[table]` header of `table`.
+    pub fn table_header(&self, table: &[&str]) -> Option<usize> {
+        self.keyed_lines()
+            .find(|(_, line)| {
+                parse_header(line.trim_start()).map_or(false, |header| keys_eq(&header, table))
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Returns the lines which are not the continuation of a value spanning
+    /// several lines, with their index.
+    fn keyed_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        let mut open = None;
+        self.lines.iter().enumerate().filter_map(move |(i, line)| {
+            if let Some(value) = open {
+                open = scan_open(line, value);
+                return None;
+            }
+            open = opened_value(line);
+            Some((i, line.as_str()))
+        })
+    }
+
+    /// Finds the value of the key `path`, which may be written in a table,
+    /// as a dotted key or in an inline table.
+    ///
+    /// Key components are compared ignoring whitespace, so that `cfg()`
+    /// expressions are found regardless of their spacing.
+    pub fn find(&self, path: &[&str]) -> Option<Span> {
+        // The keys of the current table, or `None` if its header could not
+        // be parsed.
+        let mut table = Some(Vec::new());
+        for (i, line) in self.keyed_lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') {
+                table = parse_header(trimmed);
+                continue;
+            }
+            let table = match &table {
+                Some(table) => table,
+                None => continue,
+            };
+            let start = line.len() - trimmed.len();
+            if let Some((start, end)) = find_in_pair(line, start, table, path) {
+                return Some(Span {
+                    line: i,
+                    start,
+                    end,
+                });
+            }
+        }
+        None
+    }
+
+    /// Like [`ManifestEditor::find`], but only for string values, returning
+    /// the span of the contents of the string.
+    pub fn find_string(&self, path: &[&str]) -> Option<Span> {
+        let span = self.find(path)?;
+        let value = self.get(span);
+        let quoted = value.len() >= 2
+            && !value.starts_with("\"\"\"")
+            && !value.starts_with("'''")
+            && (value.starts_with('"') && value.ends_with('"')
+                || value.starts_with('\'') && value.ends_with('\''));
+        if !quoted {
+            return None;
+        }
+        Some(Span {
+            start: span.start + 1,
+            end: span.end - 1,
+            ..span
+        })
+    }
+
+    pub fn get(&self, span: Span) -> &str {
+        &self.lines[span.line][span.start..span.end]
+    }
+
+    pub fn replace(&mut self, span: Span, value: &str) {
+        self.lines[span.line].replace_range(span.start..span.end, value);
+    }
+
+    /// Inserts `line` before the line at `index`.
+    pub fn insert_line(&mut self, index: usize, line: String) {
+        self.lines.insert(index, line);
+    }
+}
+
+impl fmt::Display for ManifestEditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lines.join("\n"))?;
+        if self.trailing_newline {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+fn keys_eq<S: AsRef<str>>(a: &[S], b: &[&str]) -> bool {
+    let without_whitespace = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| without_whitespace(a.as_ref()) == without_whitespace(b))
+}
+
+fn skip_whitespace(s: &str, pos: usize) -> usize {
+    s.len() - s[pos..].trim_start().len()
+}
+
+/// Parses the dotted key at `pos`, returning its components and the position
+/// after it.
+fn parse_key(s: &str, mut pos: usize) -> Option<(Vec<String>, usize)> {
+    let mut key = Vec::new();
+    loop {
+        pos = skip_whitespace(s, pos);
+        let rest = &s[pos..];
+        let len = match rest.chars().next()? {
+            quote @ '"' | quote @ '\'' => {
+                let len = rest[1..].find(quote)?;
+                key.push(rest[1..1 + len].to_string());
+                len + 2
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return None;
+                }
+                key.push(rest[..len].to_string());
+                len
+            }
+        };
+        pos = skip_whitespace(s, pos + len);
+        if s[pos..].starts_with('.') {
+            pos += 1;
+        } else {
+            return Some((key, pos));
+        }
+    }
+}
+
+/// Parses a `[table]` or `[[array]]` header.
+fn parse_header(line: &str) -> Option<Vec<String>> {
+    let start = if line.starts_with("[[") {
+        2
+    } else if line.starts_with('[') {
+        1
+    } else {
+        return None;
+    };
+    let (key, end) = parse_key(line, start)?;
+    if line[end..].starts_with(']') {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// Parses the `key = value` pair at `pos`, returning the key and the range of
+/// the value.
+fn parse_pair(s: &str, pos: usize) -> Option<(Vec<String>, usize, usize)> {
+    let (key, pos) = parse_key(s, pos)?;
+    if !s[pos..].starts_with('=') {
+        return None;
+    }
+    let start = skip_whitespace(s, pos + 1);
+    let end = value_end(s, start)?;
+    Some((key, start, end))
+}
+
+/// Returns the end of the value starting at `start`, or `None` if it doesn't
+/// end on this line.
+fn value_end(s: &str, start: usize) -> Option<usize> {
+    let rest = &s[start..];
+    for delim in &["\"\"\"", "'''"] {
+        if let Some(contents) = rest.strip_prefix(delim) {
+            return contents
+                .find(delim)
+                .map(|len| start + 2 * delim.len() + len);
+        }
+    }
+    match rest.chars().next()? {
+        '"' => {
+            let mut escaped = false;
+            for (i, c) in rest.char_indices().skip(1) {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => return Some(start + i + 1),
+                    _ => {}
+                }
+            }
+            None
+        }
+        '\'' => rest[1..].find('\'').map(|len| start + len + 2),
+        '{' | '[' => {
+            let mut depth = 0;
+            let mut quote = None;
+            let mut escaped = false;
+            for (i, c) in rest.char_indices() {
+                match (quote, c) {
+                    (Some(_), _) if escaped => escaped = false,
+                    (Some('"'), '\\') => escaped = true,
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '"') | (None, '\'') => quote = Some(c),
+                    (None, '{') | (None, '[') => depth += 1,
+                    (None, '}') | (None, ']') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(start + i + 1);
+                        }
+                    }
+                    (None, _) => {}
+                }
+            }
+            None
+        }
+        _ => {
+            let len = rest
+                .find(|c| matches!(c, ',' | '}' | ']' | '#'))
+                .unwrap_or(rest.len());
+            Some(start + rest[..len].trim_end().len())
+        }
+    }
+}
+
+/// A value which continues on the next lines.
+#[derive(Clone, Copy, Debug)]
+enum Open {
+    /// A multi-line string, ending with this delimiter.
+    String(&'static str),
+    /// Arrays or inline tables, nested this many times.
+    Brackets(usize),
+}
+
+/// Returns the value opened by the `key = value` pair on `line` which
+/// continues on the next lines, if any.
+fn opened_value(line: &str) -> Option<Open> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('[') {
+        return None;
+    }
+    let (_, pos) = parse_key(line, line.len() - trimmed.len())?;
+    if !line[pos..].starts_with('=') {
+        return None;
+    }
+    let start = skip_whitespace(line, pos + 1);
+    if value_end(line, start).is_some() {
+        return None;
+    }
+    let rest = &line[start..];
+    for delim in &["\"\"\"", "'''"] {
+        if rest.starts_with(delim) {
+            return Some(Open::String(*delim));
+        }
+    }
+    if rest.starts_with('[') || rest.starts_with('{') {
+        return scan_open(rest, Open::Brackets(0));
+    }
+    None
+}
+
+/// Scans `line` as the continuation of the value `open`, returning what is
+/// still open at its end.
+fn scan_open(line: &str, open: Open) -> Option<Open> {
+    let mut depth = match open {
+        Open::String(delim) => {
+            return match line.find(delim) {
+                Some(_) => None,
+                None => Some(open),
+            }
+        }
+        Open::Brackets(depth) => depth,
+    };
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some('"'), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => break,
+            (None, '[') | (None, '{') => depth += 1,
+            (None, ']') | (None, '}') => depth -= 1,
+            (None, _) => {}
+        }
+    }
+    if depth > 0 {
+        Some(Open::Brackets(depth))
+    } else {
+        None
+    }
+}
+
+/// Finds the value of `path` in the `key = value` pair at `pos` in the table
+/// `table`, looking into inline tables.
+fn find_in_pair<S: AsRef<str>>(
+    s: &str,
+    pos: usize,
+    table: &[S],
+    path: &[&str],
+) -> Option<(usize, usize)> {
+    let (key, start, end) = parse_pair(s, pos)?;
+    let full: Vec<&str> = table
+        .iter()
+        .map(|k| k.as_ref())
+        .chain(key.iter().map(|k| k.as_str()))
+        .collect();
+    if keys_eq(&full, path) {
+        return Some((start, end));
+    }
+    if full.len() >= path.len()
+        || !keys_eq(&full, &path[..full.len()])
+        || !s[start..].starts_with('{')
+    {
+        return None;
+    }
+    let mut pos = start + 1;
+    loop {
+        pos = skip_whitespace(s, pos);
+        if pos >= end || s[pos..].starts_with('}') {
+            return None;
+        }
+        if let Some(found) = find_in_pair(s, pos, &full, path) {
+            return Some(found);
+        }
+        let (_, _, value_end) = parse_pair(s, pos)?;
+        pos = skip_whitespace(s, value_end);
+        if !s[pos..].starts_with(',') {
+            return None;
+        }
+        pos += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManifestEditor;
+
+    const MANIFEST: &str = r#"# A comment
+cargo-features = ["foo"]
+
+[package]
+name = "foo"
+edition = '2018' # the edition
+
+[dependencies]
+a = "1.0"
+b = { version = "0.2", features = ["x", "y"] }
+c = { path = "c", version = "=0.3.1" }
+d.version = "4"
+"e" = { git = "https://example.com/e" }
+
+[dependencies.f]
+version = "~1.2"
+
+[target.'cfg(target_os="linux")'.dev-dependencies]
+g = "5"
+"#;
+
+    fn find(path: &[&str]) -> Option<String> {
+        let editor = ManifestEditor::new(MANIFEST);
+        editor
+            .find_string(path)
+            .map(|span| editor.get(span).to_string())
+    }
+
+    #[test]
+    fn finds_values() {
+        assert_eq!(find(&["cargo-features"]), None);
+        assert_eq!(find(&["package", "name"]).as_deref(), Some("foo"));
+        assert_eq!(find(&["package", "edition"]).as_deref(), Some("2018"));
+        assert_eq!(find(&["dependencies", "a"]).as_deref(), Some("1.0"));
+        assert_eq!(
+            find(&["dependencies", "b", "version"]).as_deref(),
+            Some("0.2")
+        );
+        assert_eq!(
+            find(&["dependencies", "c", "version"]).as_deref(),
+            Some("=0.3.1")
+        );
+        assert_eq!(
+            find(&["dependencies", "d", "version"]).as_deref(),
+            Some("4")
+        );
+        assert_eq!(find(&["dependencies", "e", "version"]), None);
+        assert_eq!(
+            find(&["dependencies", "f", "version"]).as_deref(),
+            Some("~1.2")
+        );
+        assert_eq!(
+            find(&[
+                "target",
+                "cfg(target_os = \"linux\")",
+                "dev-dependencies",
+                "g"
+            ])
+            .as_deref(),
+            Some("5")
+        );
+        assert_eq!(find(&["dependencies", "g"]), None);
+
+        let editor = ManifestEditor::new(MANIFEST);
+        let span = editor.find(&["cargo-features"]).unwrap();
+        assert_eq!(editor.get(span), r#"["foo"]"#);
+        let span = editor.find(&["dependencies", "b"]).unwrap();
+        assert_eq!(
+            editor.get(span),
+            r#"{ version = "0.2", features = ["x", "y"] }"#
+        );
+        assert_eq!(editor.table_header(&["dependencies", "f"]), Some(14));
+        assert_eq!(editor.table_header(&["dev-dependencies"]), None);
+    }
+
+    #[test]
+    fn skips_multi_line_values() {
+        let manifest = r#"[package]
+name = "foo"
+description = """
+[dependencies]
+a = "0.1"
+"""
+keywords = [
+    "[dependencies]",
+    # a = "0.2"
+]
+
+[dependencies]
+a = { version = "1.0", features = ["x"] }
+b = { version = """2.0""" }
+"#;
+        let editor = ManifestEditor::new(manifest);
+        let span = editor
+            .find_string(&["dependencies", "a", "version"])
+            .unwrap();
+        assert_eq!(span.line, 12);
+        assert_eq!(editor.get(span), "1.0");
+        assert_eq!(editor.table_header(&["dependencies"]), Some(11));
+        assert_eq!(editor.find_string(&["dependencies", "b", "version"]), None);
+        assert_eq!(editor.find_string(&["package", "description"]), None);
+        assert_eq!(editor.find(&["package", "keywords"]), None);
+    }
+
+    #[test]
+    fn edits_preserve_formatting() {
+        let mut editor = ManifestEditor::new(MANIFEST);
+        let span = editor.find_string(&["package", "edition"]).unwrap();
+        editor.replace(span, "2021");
+        let span = editor
+            .find_string(&["dependencies", "b", "version"])
+            .unwrap();
+        editor.replace(span, "0.3");
+        let header = editor.table_header(&["package"]).unwrap();
+        editor.insert_line(header + 1, "version = \"0.1.0\"".to_string());
+
+        let expected = MANIFEST
+            .replace("edition = '2018'", "edition = '2021'")
+            .replace("version = \"0.2\"", "version = \"0.3\"")
+            .replace("[package]\n", "[package]\nversion = \"0.1.0\"\n");
+        assert_eq!(editor.to_string(), expected);
+        assert_eq!(ManifestEditor::new("[package]").to_string(), "[package]");
+    }
+}
@@ -20,7 +20,8 @@ use crate::core::dependency::DepKind;
 use crate::core::manifest::{ManifestMetadata, TargetSourcePath, Warnings};
 use crate::core::resolver::ResolveBehavior;
 use crate::core::{Dependency, Manifest, PackageId, Summary, Target};
-use crate::core::{Edition, EitherManifest, Feature, Features, VirtualManifest, Workspace};
+use crate::core::{Edition, EitherManifest, Feature, FeatureConstraints, FeatureMetadata};
+use crate::core::{Features, VirtualManifest, Workspace};
 use crate::core::{GitReference, PackageIdSpec, SourceId, WorkspaceConfig, WorkspaceRootConfig};
 use crate::sources::{CRATES_IO_INDEX, CRATES_IO_REGISTRY};
 use crate::util::errors::{CargoResult, ManifestError};
@@ -29,6 +30,7 @@ use crate::util::{
     self, config::ConfigRelativePath, validate_package_name, Config, IntoUrl, VersionReqExt,
 };
 
+pub mod edit;
 mod targets;
 use self::targets::targets;
 
@@ -51,10 +53,19 @@ pub fn read_manifest(
         source_id
     );
     let contents = paths::read(path).map_err(|err| ManifestError::new(err, path.into()))?;
+    if let Some(cache) = config.daemon_cache() {
+        if let Some(manifest) = cache.manifest(path, &contents, source_id, config) {
+            return Ok(manifest);
+        }
+    }
 
-    do_read_manifest(&contents, path, source_id, config)
+    let manifest = do_read_manifest(&contents, path, source_id, config)
         .with_context(|| format!("failed to parse manifest at `{}`", path.display()))
-        .map_err(|err| ManifestError::new(err, path.into()))
+        .map_err(|err| ManifestError::new(err, path.into()))?;
+    if let Some(cache) = config.daemon_cache() {
+        cache.insert_manifest(path, contents, source_id, config, &manifest);
+    }
+    Ok(manifest)
 }
 
 fn do_read_manifest(
@@ -337,7 +348,7 @@ pub struct TomlManifest {
     build_dependencies: Option<BTreeMap<String, TomlDependency>>,
     #[serde(rename = "build_dependencies")]
     build_dependencies2: Option<BTreeMap<String, TomlDependency>>,
-    features: Option<BTreeMap<InternedString, Vec<InternedString>>>,
+    features: Option<BTreeMap<InternedString, TomlFeature>>,
     target: Option<BTreeMap<String, TomlPlatform>>,
     replace: Option<BTreeMap<String, TomlDependency>>,
     patch: Option<BTreeMap<String, BTreeMap<String, TomlDependency>>>,
@@ -345,6 +356,78 @@ pub struct TomlManifest {
     badges: Option<BTreeMap<String, BTreeMap<String, String>>>,
 }
 
+/// A feature in `[features]`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum TomlFeature {
+    /// In the simple format, only the enabled features are specified, eg.
+    /// `feature = ["dep", "other"]`
+    Simple(Vec<InternedString>),
+    /// The detailed format also declares constraints or metadata on the
+    /// feature, eg. `feature = { enables = ["dep"], conflicts = ["other"] }`
+    Detailed(DetailedTomlFeature),
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DetailedTomlFeature {
+    #[serde(default)]
+    enables: Vec<InternedString>,
+    conflicts: Option<Vec<InternedString>>,
+    requires: Option<Vec<InternedString>>,
+    doc: Option<String>,
+    unstable: Option<bool>,
+    public: Option<bool>,
+}
+
+impl TomlFeature {
+    /// The features and dependencies enabled by this feature.
+    pub fn enables(&self) -> &[InternedString] {
+        match self {
+            TomlFeature::Simple(enables) => enables,
+            TomlFeature::Detailed(detailed) => &detailed.enables,
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for TomlFeature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct TomlFeatureVisitor;
+
+        impl<'de> de::Visitor<'de> for TomlFeatureVisitor {
+            type Value = TomlFeature;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str(
+                    "a list of features like [\"dep\"] or a \
+                     detailed feature like { enables = [\"dep\"] }",
+                )
+            }
+
+            fn visit_seq<V>(self, v: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::SeqAccess<'de>,
+            {
+                let seq = de::value::SeqAccessDeserializer::new(v);
+                Vec::deserialize(seq).map(TomlFeature::Simple)
+            }
+
+            fn visit_map<V>(self, map: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mvd = de::value::MapAccessDeserializer::new(map);
+                DetailedTomlFeature::deserialize(mvd).map(TomlFeature::Detailed)
+            }
+        }
+
+        deserializer.deserialize_any(TomlFeatureVisitor)
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct TomlProfiles(BTreeMap<InternedString, TomlProfile>);
 
@@ -1270,18 +1353,66 @@ impl TomlManifest {
 
         let exclude = project.exclude.clone().unwrap_or_default();
         let include = project.include.clone().unwrap_or_default();
-        let empty_features = BTreeMap::new();
 
         let summary = Summary::new(
             config,
             pkgid,
             deps,
-            me.features.as_ref().unwrap_or(&empty_features),
+            &me.features().unwrap_or_default(),
             project.links.as_deref(),
         )?;
         let unstable = config.cli_unstable();
         summary.unstable_gate(unstable.namespaced_features, unstable.weak_dep_features)?;
 
+        let mut feature_constraints = BTreeMap::new();
+        let mut feature_metadata = BTreeMap::new();
+        for (name, feature) in me.features.iter().flatten() {
+            let detailed = match feature {
+                TomlFeature::Simple(_) => continue,
+                TomlFeature::Detailed(detailed) => detailed,
+            };
+            let has_metadata =
+                detailed.doc.is_some() || detailed.unstable.is_some() || detailed.public.is_some();
+            if has_metadata {
+                features.require(Feature::feature_metadata())?;
+                feature_metadata.insert(
+                    *name,
+                    FeatureMetadata {
+                        doc: detailed.doc.clone(),
+                        unstable: detailed.unstable.unwrap_or(false),
+                        public: detailed.public.unwrap_or(true),
+                    },
+                );
+                if detailed.conflicts.is_none() && detailed.requires.is_none() {
+                    continue;
+                }
+            }
+            features.require(Feature::feature_constraints())?;
+            let constraints = FeatureConstraints {
+                conflicts: detailed.conflicts.clone().unwrap_or_default(),
+                requires: detailed.requires.clone().unwrap_or_default(),
+            };
+            for (kind, other) in constraints
+                .conflicts
+                .iter()
+                .map(|f| ("conflicts with", f))
+                .chain(constraints.requires.iter().map(|f| ("requires", f)))
+            {
+                if !summary.features().contains_key(other) {
+                    bail!(
+                        "feature `{}` {} `{}` which is not another feature",
+                        name,
+                        kind,
+                        other
+                    );
+                }
+                if other == name {
+                    bail!("feature `{}` {} itself", name, kind);
+                }
+            }
+            feature_constraints.insert(*name, constraints);
+        }
+
         let metadata = ManifestMetadata {
             description: project.description.clone(),
             homepage: project.homepage.clone(),
@@ -1380,6 +1511,8 @@ impl TomlManifest {
             Rc::clone(me),
             project.metabuild.clone().map(|sov| sov.0),
             resolve_behavior,
+            feature_constraints,
+            feature_metadata,
         );
         if project.license_file.is_some() && project.license.is_some() {
             manifest.warnings_mut().add_warning(
@@ -1600,8 +1733,14 @@ impl TomlManifest {
         self.profile.is_some()
     }
 
-    pub fn features(&self) -> Option<&BTreeMap<InternedString, Vec<InternedString>>> {
-        self.features.as_ref()
+    /// Returns the features and dependencies enabled by each feature.
+    pub fn features(&self) -> Option<BTreeMap<InternedString, Vec<InternedString>>> {
+        self.features.as_ref().map(|features| {
+            features
+                .iter()
+                .map(|(name, feature)| (*name, feature.enables().to_vec()))
+                .collect()
+        })
     }
 }
 
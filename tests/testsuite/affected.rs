@@ -0,0 +1,175 @@
+//! Tests for selecting the packages affected by git changes with `--affected`.
+
+use cargo_test_support::git;
+use cargo_test_support::{basic_lib_manifest, basic_manifest, Project};
+
+/// A workspace where `b` depends on `a`, and `c` is independent.
+fn workspace() -> (Project, git2::Repository) {
+    git::new_repo("foo", |project| {
+        project
+            .file(
+                "Cargo.toml",
+                r#"
+                    [workspace]
+                    members = ["a", "b", "c"]
+                "#,
+            )
+            .file(".gitignore", "/target\n/Cargo.lock\n")
+            .file("a/Cargo.toml", &basic_lib_manifest("a"))
+            .file("a/src/lib.rs", "")
+            .file(
+                "b/Cargo.toml",
+                r#"
+                    [package]
+                    name = "b"
+                    version = "0.5.0"
+
+                    [dependencies]
+                    a = { path = "../a" }
+                "#,
+            )
+            .file("b/src/lib.rs", "")
+            .file("c/Cargo.toml", &basic_manifest("c", "0.5.0"))
+            .file("c/src/lib.rs", "")
+    })
+}
+
+#[cargo_test]
+fn requires_flag() {
+    let (p, _repo) = workspace();
+
+    p.cargo("check --affected HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--affected` requires `-Z affected`")
+        .run();
+}
+
+#[cargo_test]
+fn conflicts_with_package_selection() {
+    let (p, _repo) = workspace();
+
+    p.cargo("check -Zaffected --affected HEAD -p a")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] `--affected` cannot be used together with \
+             `--package`, `--workspace` or `--exclude`",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn reverse_dependents() {
+    let (p, repo) = workspace();
+    p.change_file("a/src/lib.rs", "pub fn a() {}");
+    git::add(&repo);
+    git::commit(&repo);
+
+    p.cargo("check -Zaffected --affected HEAD~1..HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_unordered(
+            "\
+[AFFECTED] 2 of 3 workspace members by changes in `HEAD~1..HEAD`
+[CHECKING] a v0.5.0 ([CWD]/a)
+[CHECKING] b v0.5.0 ([CWD]/b)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.change_file("c/src/lib.rs", "pub fn c() {}");
+    git::add(&repo);
+    git::commit(&repo);
+
+    p.cargo("check -Zaffected --affected HEAD~1..HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[AFFECTED] 1 of 3 workspace members by changes in `HEAD~1..HEAD`
+[CHECKING] c v0.5.0 ([CWD]/c)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // `a` and `c` changed since the first commit.
+    p.cargo("check -Zaffected --affected HEAD~2...HEAD -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[AFFECTED] 3 of 3 workspace members by changes in `HEAD~2...HEAD`")
+        .with_stderr_contains("[AFFECTED] a, b, c")
+        .run();
+}
+
+#[cargo_test]
+fn working_directory() {
+    let (p, _repo) = workspace();
+
+    p.cargo("check -Zaffected --affected HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[AFFECTED] 0 of 3 workspace members by changes in `HEAD`")
+        .run();
+
+    p.change_file("b/src/lib.rs", "pub fn b() {}");
+    p.cargo("test -Zaffected --affected HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[AFFECTED] 1 of 3 workspace members by changes in `HEAD`
+[COMPILING] a v0.5.0 ([CWD]/a)
+[COMPILING] b v0.5.0 ([CWD]/b)
+[FINISHED] [..]
+[RUNNING] [..] (target/debug/deps/b-[..][EXE])
+[DOCTEST] b
+",
+        )
+        .run();
+
+    // Files which are not part of a package do not affect it.
+    p.change_file("b/src/lib.rs", "");
+    p.change_file("notes.txt", "");
+    p.cargo("build -Zaffected --affected HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[AFFECTED] 0 of 3 workspace members by changes in `HEAD`")
+        .run();
+}
+
+#[cargo_test]
+fn workspace_files() {
+    let (p, repo) = workspace();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [workspace]
+            members = ["a", "b", "c"]
+
+            [profile.dev]
+            opt-level = 1
+        "#,
+    );
+    git::add(&repo);
+    git::commit(&repo);
+
+    p.cargo("build -Zaffected --affected HEAD~1..HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[AFFECTED] 3 of 3 workspace members by changes in `HEAD~1..HEAD`")
+        .run();
+}
+
+#[cargo_test]
+fn bad_revisions() {
+    let (p, _repo) = workspace();
+
+    p.cargo("check -Zaffected --affected nope..HEAD")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse the git revisions `nope..HEAD`
+
+Caused by:
+  [..]
+",
+        )
+        .run();
+}
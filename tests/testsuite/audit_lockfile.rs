@@ -0,0 +1,244 @@
+//! Tests for the `cargo audit-lockfile` command.
+
+use cargo_test_support::paths;
+use cargo_test_support::registry::Package;
+use cargo_test_support::{project, Project};
+use std::fs;
+
+fn make_project() -> Project {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").dep("bar", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                baz = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("advisory-db/README.md", "# Advisory database\n")
+        .file(
+            "advisory-db/crates/bar/RUSTSEC-2021-0001.md",
+            r#"```toml
+[advisory]
+id = "RUSTSEC-2021-0001"
+package = "bar"
+date = "2021-01-01"
+url = "https://example.com/RUSTSEC-2021-0001"
+
+[versions]
+patched = [">= 1.2.0", ">= 0.9.5, < 1.0.0"]
+unaffected = ["< 0.5.0"]
+```
+
+# Use after free in `bar`
+
+Details about the vulnerability.
+"#,
+        )
+        .file(
+            "advisory-db/crates/baz/RUSTSEC-2021-0002.toml",
+            r#"
+                [advisory]
+                id = "RUSTSEC-2021-0002"
+                package = "baz"
+                title = "baz is unmaintained"
+                informational = "unmaintained"
+                patched_versions = []
+            "#,
+        )
+        .file(
+            "advisory-db/crates/baz/RUSTSEC-2021-0003.md",
+            r#"```toml
+[advisory]
+id = "RUSTSEC-2021-0003"
+package = "baz"
+withdrawn = "2021-02-01"
+```
+
+# Withdrawn advisory
+"#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p
+}
+
+#[cargo_test]
+fn requires_z_flag() {
+    let p = make_project();
+
+    p.cargo("audit-lockfile --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo audit-lockfile` command is unstable, pass `-Z audit` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn vulnerable() {
+    let p = make_project();
+
+    p.cargo("audit-lockfile -Zaudit --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stdout(
+            "\
+RUSTSEC-2021-0001: Use after free in `bar`
+  package: bar v1.0.0
+  date: 2021-01-01
+  url: https://example.com/RUSTSEC-2021-0001
+  patched: >=1.2.0 or >=0.9.5, <1.0.0
+  solution: upgrade to 1.2.0 (`cargo update -p bar:1.0.0 --precise 1.2.0`)
+
+bar v1.0.0
+└── baz v1.0.0
+    └── foo v0.1.0 ([..]/foo)
+
+RUSTSEC-2021-0002: baz is unmaintained (unmaintained)
+  package: baz v1.0.0
+  solution: no patched version is available
+
+baz v1.0.0
+└── foo v0.1.0 ([..]/foo)
+",
+        )
+        .with_stderr(
+            "\
+[SCANNING] 3 locked packages against 2 advisories
+[WARNING] found 1 informational advisory for locked packages
+[ERROR] found 1 vulnerability in locked packages
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn only_reads_lockfile() {
+    let p = make_project();
+    let lockfile = p.read_lockfile();
+    // Nothing may be downloaded, nor read from the registry.
+    fs::remove_dir_all(paths::home().join(".cargo/registry")).unwrap();
+
+    p.cargo("audit-lockfile -Zaudit --db advisory-db --offline")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stdout_contains("bar v1.0.0\n└── baz v1.0.0\n    └── foo v0.1.0 ([..]/foo)")
+        .with_stderr_contains("[ERROR] found 1 vulnerability in locked packages")
+        .run();
+    assert_eq!(p.read_lockfile(), lockfile);
+}
+
+#[cargo_test]
+fn patched_and_unaffected() {
+    let p = make_project();
+    Package::new("bar", "1.2.0").publish();
+    Package::new("bar", "0.4.0").publish();
+    p.cargo("update -p bar").run();
+    p.change_file(
+        "advisory-db/crates/baz/RUSTSEC-2021-0002.toml",
+        "# Not an advisory\n",
+    );
+
+    p.cargo("audit-lockfile -Zaudit --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_stdout("")
+        .with_stderr("[SCANNING] 3 locked packages against 1 advisory")
+        .run();
+
+    // Unaffected versions are not reported either.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            bar = "0.4"
+        "#,
+    );
+    p.cargo("generate-lockfile").run();
+    p.cargo("audit-lockfile -Zaudit --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_stdout("")
+        .with_stderr("[SCANNING] 2 locked packages against 1 advisory")
+        .run();
+}
+
+#[cargo_test]
+fn db_from_config() {
+    let p = make_project();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [audit]
+            db = "advisory-db"
+        "#,
+    );
+
+    p.cargo("audit-lockfile -Zaudit")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] found 1 vulnerability in locked packages")
+        .run();
+}
+
+#[cargo_test]
+fn errors() {
+    let p = make_project();
+
+    p.cargo("audit-lockfile -Zaudit")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] no advisory database was specified
+Pass the path to a local advisory database with `--db`, or set `audit.db` in the config.
+",
+        )
+        .run();
+
+    p.cargo("audit-lockfile -Zaudit --db missing")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] advisory database `[..]missing` is not a directory")
+        .run();
+
+    p.change_file(
+        "advisory-db/crates/bar/RUSTSEC-2021-0001.md",
+        "```toml\n[advisory]\nid = \"RUSTSEC-2021-0001\"\n",
+    );
+    p.cargo("audit-lockfile -Zaudit --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse advisory `[..]RUSTSEC-2021-0001.md`
+
+Caused by:
+  the ```toml block is not closed
+",
+        )
+        .run();
+
+    std::fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    p.change_file("advisory-db/crates/bar/RUSTSEC-2021-0001.md", "");
+    p.cargo("audit-lockfile -Zaudit --db advisory-db")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] a Cargo.lock must exist for this command
+Run `cargo generate-lockfile` to create it.
+",
+        )
+        .run();
+}
@@ -3,7 +3,7 @@
 //! See `cargo_test_support::cross_compile` for more detail.
 
 use cargo_test_support::{basic_bin_manifest, basic_manifest, cross_compile, project};
-use cargo_test_support::{is_nightly, rustc_host};
+use cargo_test_support::{is_nightly, rustc_host, ProjectBuilder};
 
 #[cargo_test]
 fn simple_cross() {
@@ -169,8 +169,6 @@ fn per_crate_target_test(
             "Cargo.toml",
             &format!(
                 r#"
-                    cargo-features = ["per-package-target"]
-
                     [package]
                     name = "foo"
                     version = "0.0.0"
@@ -216,7 +214,7 @@ fn per_crate_target_test(
     if let Some(t) = arg_target {
         cmd.arg("--target").arg(&t);
     }
-    cmd.masquerade_as_nightly_cargo().run();
+    cmd.run();
     assert!(p.target_bin(cross_compile::alternate(), "foo").is_file());
 
     if cross_compile::can_run_on_host() {
@@ -270,8 +268,6 @@ fn workspace_with_multiple_targets() {
         .file(
             "native/Cargo.toml",
             r#"
-                cargo-features = ["per-package-target"]
-
                 [package]
                 name = "native"
                 version = "0.0.0"
@@ -306,8 +302,6 @@ fn workspace_with_multiple_targets() {
             "cross/Cargo.toml",
             &format!(
                 r#"
-                    cargo-features = ["per-package-target"]
-
                     [package]
                     name = "cross"
                     version = "0.0.0"
@@ -343,8 +337,7 @@ fn workspace_with_multiple_targets() {
         )
         .build();
 
-    let mut cmd = p.cargo("build -v");
-    cmd.masquerade_as_nightly_cargo().run();
+    p.cargo("build -v").run();
 
     assert!(p.bin("native").is_file());
     assert!(p.target_bin(cross_compile::alternate(), "cross").is_file());
@@ -356,6 +349,160 @@ fn workspace_with_multiple_targets() {
     }
 }
 
+/// A workspace with a member always built for `cross_compile::alternate()`.
+fn forced_target_workspace() -> ProjectBuilder {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["native", "firmware"]
+            "#,
+        )
+        .file("native/Cargo.toml", &basic_manifest("native", "0.0.0"))
+        .file("native/src/lib.rs", "#[test] fn native_test() {}")
+        .file(
+            "firmware/Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "firmware"
+                    version = "0.0.0"
+                    authors = []
+                    forced-target = "{}"
+                "#,
+                cross_compile::alternate(),
+            ),
+        )
+        .file("firmware/src/lib.rs", "#[test] fn firmware_test() {}")
+}
+
+#[cargo_test]
+fn test_skips_forced_target() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let p = forced_target_workspace().build();
+
+    p.cargo("test")
+        .with_stderr_contains(&format!(
+            "[NOTE] skipping tests of `firmware` (target/{}/debug/deps/firmware-[..]) \
+             built for `{}`, which cannot run on the host",
+            cross_compile::alternate(),
+            cross_compile::alternate(),
+        ))
+        .with_stderr_contains("[RUNNING] [..]native-[..]")
+        .with_stdout_contains("test native_test ... ok")
+        .with_stdout_does_not_contain("[..]firmware_test[..]")
+        .run();
+}
+
+#[cargo_test]
+fn test_runs_forced_target_with_runner() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let p = forced_target_workspace()
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [target.{}]
+                    runner = "nonexistent-runner"
+                "#,
+                cross_compile::alternate(),
+            ),
+        )
+        .build();
+
+    p.cargo("test -p firmware -v")
+        .with_status(101)
+        .with_stderr_contains("[RUNNING] `nonexistent-runner [..]firmware-[..]`")
+        .run();
+}
+
+#[cargo_test]
+fn forced_target_dependency_rejected() {
+    if cross_compile::disabled() {
+        return;
+    }
+
+    let p = forced_target_workspace()
+        .file(
+            "native/Cargo.toml",
+            r#"
+                [package]
+                name = "native"
+                version = "0.0.0"
+                authors = []
+
+                [dependencies]
+                firmware = { path = "../firmware" }
+            "#,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr(&format!(
+            "[ERROR] package `native` depends on `firmware`, which has \
+             `package.forced-target = \"{}\"`, but `native` is built for `{}`",
+            cross_compile::alternate(),
+            rustc_host(),
+        ))
+        .run();
+}
+
+#[cargo_test]
+fn forced_target_member_dependency_of_other_member() {
+    // Building `app` doesn't need the standard library of the forced target,
+    // only its target information, so this runs without cross compiling.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["app", "firmware"]
+            "#,
+        )
+        .file(
+            "app/Cargo.toml",
+            r#"
+                [package]
+                name = "app"
+                version = "0.0.0"
+                authors = []
+
+                [dependencies]
+                firmware = { path = "../firmware" }
+            "#,
+        )
+        .file("app/src/main.rs", "fn main() {}")
+        .file(
+            "firmware/Cargo.toml",
+            r#"
+                [package]
+                name = "firmware"
+                version = "0.0.0"
+                authors = []
+                forced-target = "thumbv7em-none-eabihf"
+            "#,
+        )
+        .file("firmware/src/lib.rs", "#![no_std]")
+        .build();
+
+    p.cargo("build -p app")
+        .with_status(101)
+        .with_stderr(&format!(
+            "[ERROR] package `app` depends on `firmware`, which has \
+             `package.forced-target = \"thumbv7em-none-eabihf\"`, but `app` is built for `{}`",
+            rustc_host(),
+        ))
+        .run();
+}
+
 #[cargo_test]
 fn linker() {
     if cross_compile::disabled() {
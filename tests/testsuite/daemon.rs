@@ -0,0 +1,183 @@
+//! Tests for `cargo daemon`.
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+
+use cargo_test_support::{basic_manifest, paths, project};
+
+#[cargo_test]
+fn requires_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("daemon")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo daemon` command is unstable, pass `-Z daemon` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn stop_without_daemon() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("daemon -Zdaemon --stop")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[WARNING] no cargo daemon is running")
+        .run();
+}
+
+#[cargo_test]
+fn runs_commands() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["foo", "bar"]
+            "#,
+        )
+        .file("foo/Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file("foo/src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    let mut daemon = p
+        .cargo("daemon -Zdaemon")
+        .masquerade_as_nightly_cargo()
+        .build_command();
+    daemon.stdout(Stdio::null()).stderr(Stdio::piped());
+    let mut daemon = daemon.spawn().unwrap();
+    let mut stderr = BufReader::new(daemon.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    assert!(line.contains("Listening"), "{}", line);
+
+    p.cargo("check -Zdaemon -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_unordered(
+            "\
+[CHECKING] foo v0.1.0 ([CWD]/foo)
+[CHECKING] bar v0.1.0 ([CWD]/bar)
+[RUNNING] `rustc --crate-name foo [..]`
+[RUNNING] `rustc --crate-name bar [..]`
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // A changed manifest is parsed again.
+    p.change_file(
+        "bar/Cargo.toml",
+        r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+
+            [dependencies]
+            foo = { path = "../foo" }
+        "#,
+    );
+    p.cargo("check -Zdaemon -p bar")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[CHECKING] bar v0.1.0 ([CWD]/bar)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Targets added without changing the manifest are found.
+    p.change_file("bar/src/bin/extra.rs", "fn main() {}");
+    p.cargo("check -Zdaemon -p bar --bin extra")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[CHECKING] bar v0.1.0 ([CWD]/bar)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Errors of the command are reported to the client.
+    p.cargo("check -Zdaemon -p baz")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] package ID specification `baz` did not match any packages")
+        .run();
+
+    p.cargo("daemon -Zdaemon --stop")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[STOPPED] the cargo daemon")
+        .run();
+    assert!(daemon.wait().unwrap().success());
+}
+
+#[cargo_test]
+fn declines_other_environments() {
+    let p = project().file("src/lib.rs", "").build();
+
+    let mut daemon = p
+        .cargo("daemon -Zdaemon")
+        .masquerade_as_nightly_cargo()
+        .build_command();
+    daemon.stdout(Stdio::null()).stderr(Stdio::piped());
+    let mut daemon = daemon.spawn().unwrap();
+    let mut stderr = BufReader::new(daemon.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+
+    p.cargo("check -Zdaemon -v")
+        .masquerade_as_nightly_cargo()
+        .env("RUSTFLAGS", "--cfg foo")
+        .with_stderr(
+            "\
+[NOTE] not running in the cargo daemon: the environment differs from the one of the daemon
+[CHECKING] foo v0.0.1 ([CWD])
+[RUNNING] `rustc --crate-name foo [..]--cfg foo`
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("daemon -Zdaemon --stop")
+        .masquerade_as_nightly_cargo()
+        .run();
+    assert!(daemon.wait().unwrap().success());
+}
+
+#[cfg(unix)]
+#[cargo_test]
+fn info_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let p = project().file("src/lib.rs", "").build();
+    // A file left over from a previous daemon, readable by everyone.
+    let info_path = paths::home().join(".cargo/daemon.json");
+    fs::create_dir_all(info_path.parent().unwrap()).unwrap();
+    fs::write(&info_path, "{}").unwrap();
+    fs::set_permissions(&info_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let mut daemon = p
+        .cargo("daemon -Zdaemon")
+        .masquerade_as_nightly_cargo()
+        .build_command();
+    daemon.stdout(Stdio::null()).stderr(Stdio::piped());
+    let mut daemon = daemon.spawn().unwrap();
+    let mut stderr = BufReader::new(daemon.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    assert!(line.contains("Listening"), "{}", line);
+
+    let mode = fs::metadata(&info_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    p.cargo("daemon -Zdaemon --stop")
+        .masquerade_as_nightly_cargo()
+        .run();
+    assert!(daemon.wait().unwrap().success());
+}
@@ -0,0 +1,216 @@
+//! Tests for conflicting and required features in `[features]`.
+
+use cargo_test_support::{project, Project};
+
+const SYS_MANIFEST: &str = r#"
+    cargo-features = ["feature-constraints"]
+
+    [package]
+    name = "sys"
+    version = "0.1.0"
+
+    [features]
+    backend-a = { conflicts = ["backend-b"] }
+    backend-b = []
+    tls = { enables = ["backend-a"], requires = ["rustls"] }
+    rustls = []
+"#;
+
+/// A project depending on `sys` directly and through `a`, with the given
+/// features enabled by each.
+fn project_with(foo_features: &str, a_features: &str) -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+
+                    [features]
+                    use-a = ["sys/backend-a"]
+
+                    [dependencies]
+                    a = {{ path = "a" }}
+                    sys = {{ path = "sys", features = [{}] }}
+                "#,
+                foo_features
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "a/Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "a"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    sys = {{ path = "../sys", features = [{}] }}
+                "#,
+                a_features
+            ),
+        )
+        .file("a/src/lib.rs", "")
+        .file("sys/Cargo.toml", SYS_MANIFEST)
+        .file("sys/src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn requires_cargo_feature() {
+    let p = project_with("", "");
+    p.change_file(
+        "sys/Cargo.toml",
+        &SYS_MANIFEST.replace("cargo-features", "# "),
+    );
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("  feature `feature-constraints` is required")
+        .run();
+}
+
+#[cargo_test]
+fn unknown_feature() {
+    let p = project_with("", "");
+    p.change_file(
+        "sys/Cargo.toml",
+        &SYS_MANIFEST.replace("conflicts = [\"backend-b\"]", "conflicts = [\"backend-c\"]"),
+    );
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "  feature `backend-a` conflicts with `backend-c` which is not another feature",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn compatible_features() {
+    let p = project_with("\"backend-a\"", "\"tls\", \"rustls\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[CHECKING] sys v0.1.0 ([..])")
+        .run();
+}
+
+#[cargo_test]
+fn conflicting_features() {
+    let p = project_with("\"backend-b\"", "\"backend-a\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] features `backend-a` and `backend-b` of package `sys v0.1.0 ([..])` cannot be enabled together
+
+feature `backend-a` is enabled by:
+  foo v0.1.0 ([CWD]) (selected)
+  a v0.1.0 ([CWD]/a)
+  sys v0.1.0 ([CWD]/sys) feature `backend-a`
+
+feature `backend-b` is enabled by:
+  foo v0.1.0 ([CWD]) (selected)
+  sys v0.1.0 ([CWD]/sys) feature `backend-b`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn conflicting_features_from_command_line() {
+    let p = project_with("\"backend-b\"", "");
+
+    p.cargo("check").masquerade_as_nightly_cargo().run();
+
+    p.cargo("check --features use-a")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+feature `backend-a` is enabled by:
+  foo v0.1.0 ([CWD]) feature `use-a` (enabled with --features)
+  sys v0.1.0 ([CWD]/sys) feature `backend-a`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn missing_required_feature() {
+    let p = project_with("", "\"tls\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] feature `tls` of package `sys v0.1.0 ([..])` requires feature `rustls`, which is not enabled
+
+feature `tls` is enabled by:
+  foo v0.1.0 ([CWD]) (selected)
+  a v0.1.0 ([CWD]/a)
+  sys v0.1.0 ([CWD]/sys) feature `tls`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn per_root_unification() {
+    // Members which are never built together may enable conflicting features.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+                resolver = "2"
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                sys = { path = "../sys", features = ["backend-a"] }
+            "#,
+        )
+        .file("a/src/lib.rs", "")
+        .file(
+            "b/Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+
+                [dependencies]
+                sys = { path = "../sys", features = ["backend-b"] }
+            "#,
+        )
+        .file("b/src/lib.rs", "")
+        .file("sys/Cargo.toml", SYS_MANIFEST)
+        .file("sys/src/lib.rs", "")
+        .build();
+
+    p.cargo("check -p a -p b")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] features `backend-a` and `backend-b` of package `sys v0.1.0 ([..])` cannot be enabled together")
+        .run();
+
+    p.cargo("check -p a -p b -Zfeatures=root")
+        .masquerade_as_nightly_cargo()
+        .run();
+}
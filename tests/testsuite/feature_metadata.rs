@@ -0,0 +1,124 @@
+//! Tests for the documentation and stability of features in `[features]`.
+
+use cargo_test_support::{project, Project};
+
+const SYS_MANIFEST: &str = r#"
+    cargo-features = ["feature-metadata"]
+
+    [package]
+    name = "sys"
+    version = "0.1.0"
+    license = "MIT"
+    description = "sys"
+
+    [features]
+    default = ["std"]
+    std = { doc = "Use the standard library." }
+    simd = { enables = ["internal"], doc = "Use SIMD instructions.", unstable = true }
+    internal = { public = false }
+"#;
+
+/// A project depending on `sys` with the given features enabled.
+fn project_with(features: &str) -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    sys = {{ path = "sys", features = [{}] }}
+                "#,
+                features
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file("sys/Cargo.toml", SYS_MANIFEST)
+        .file("sys/src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn requires_cargo_feature() {
+    let p = project_with("");
+    p.change_file(
+        "sys/Cargo.toml",
+        &SYS_MANIFEST.replace("cargo-features", "# "),
+    );
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("  feature `feature-metadata` is required")
+        .run();
+}
+
+#[cargo_test]
+fn metadata() {
+    let p = project_with("");
+
+    p.cargo("metadata --format-version 1 --no-deps --manifest-path sys/Cargo.toml")
+        .masquerade_as_nightly_cargo()
+        .with_stdout_contains(
+            "[..]\"feature_metadata\":{\
+             \"internal\":{\"doc\":null,\"unstable\":false,\"public\":false},\
+             \"simd\":{\"doc\":\"Use SIMD instructions.\",\"unstable\":true,\"public\":true},\
+             \"std\":{\"doc\":\"Use the standard library.\",\"unstable\":false,\"public\":true}\
+             }[..]",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn published_manifest() {
+    let p = project_with("");
+
+    p.cargo("package --manifest-path sys/Cargo.toml")
+        .masquerade_as_nightly_cargo()
+        .run();
+
+    let manifest = p.read_file("sys/target/package/sys-0.1.0/Cargo.toml");
+    assert!(manifest.contains("doc = \"Use SIMD instructions.\""));
+    assert!(manifest.contains("unstable = true"));
+    assert!(manifest.contains("public = false"));
+}
+
+#[cargo_test]
+fn no_warning_for_public_features() {
+    let p = project_with("\"std\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .run();
+}
+
+#[cargo_test]
+fn private_and_unstable_features() {
+    let p = project_with("\"simd\", \"internal\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(
+            "[WARNING] feature `internal` of package `sys v0.1.0 ([CWD]/sys)` is private, \
+             but is enabled by `foo v0.1.0 ([CWD])`",
+        )
+        .with_stderr_contains(
+            "[WARNING] feature `simd` of package `sys v0.1.0 ([CWD]/sys)` is unstable, \
+             but is enabled by `foo v0.1.0 ([CWD])`",
+        )
+        .run();
+
+    // A private feature enabled by another feature of the same package is
+    // only reported for the feature enabled by the dependent.
+    let p = project_with("\"simd\"");
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[WARNING] feature `simd` of package [..]")
+        .with_stderr_does_not_contain("[WARNING] feature `internal` of package [..]")
+        .run();
+}
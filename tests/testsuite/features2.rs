@@ -2398,3 +2398,189 @@ foo v0.1.0 [..]
         )
         .run();
 }
+
+/// A workspace where the members `a` and `b` enable different features of a
+/// shared dependency.
+fn shared_dep_ws() -> Project {
+    Package::new("common", "1.0.0")
+        .feature("f1", &[])
+        .feature("f2", &[])
+        .file("src/lib.rs", "")
+        .publish();
+
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "b"]
+            resolver = "2"
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+
+            [dependencies]
+            common = {version="1.0", features=["f1"]}
+            "#,
+        )
+        .file("a/src/lib.rs", "")
+        .file(
+            "b/Cargo.toml",
+            r#"
+            [package]
+            name = "b"
+            version = "0.1.0"
+
+            [dependencies]
+            common = {version="1.0", features=["f2"]}
+            "#,
+        )
+        .file("b/src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn feature_unification_per_root() {
+    // `-Zfeatures=root` builds each selected member as if it was selected alone.
+    let p = shared_dep_ws();
+
+    p.cargo("check -p a -p b -v")
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]--cfg [..]f2[..]",
+        )
+        .run();
+
+    p.cargo("clean").run();
+    p.cargo("check -p a -p b -v -Zfeatures=root")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name common [..]--cfg [..]f2[..]")
+        .with_stderr_does_not_contain(
+            "[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]--cfg [..]f2[..]",
+        )
+        .run();
+
+    // Building a member alone reuses the same artifacts.
+    p.cargo("check -p a -v")
+        .with_stderr(
+            "\
+[FRESH] common v1.0.0
+[FRESH] a v0.1.0 [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn feature_unification_per_root_diamond() {
+    // The members `a` and `b` share `mid`, whose features are the same for
+    // both, but the features of its dependency `common` differ. Each member
+    // must link a `mid` built against its own `common`.
+    Package::new("common", "1.0.0")
+        .feature("f1", &[])
+        .feature("f2", &[])
+        .file(
+            "src/lib.rs",
+            r#"
+                pub struct Thing;
+                #[cfg(feature = "f1")]
+                pub fn f1(_: Thing) {}
+                #[cfg(feature = "f2")]
+                pub fn f2(_: Thing) {}
+            "#,
+        )
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "b", "mid"]
+            resolver = "2"
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+            [package]
+            name = "a"
+            version = "0.1.0"
+
+            [dependencies]
+            mid = {path="../mid"}
+            common = {version="1.0", features=["f1"]}
+            "#,
+        )
+        .file("a/src/lib.rs", "pub fn a() { common::f1(mid::thing()) }")
+        .file(
+            "b/Cargo.toml",
+            r#"
+            [package]
+            name = "b"
+            version = "0.1.0"
+
+            [dependencies]
+            mid = {path="../mid"}
+            common = {version="1.0", features=["f2"]}
+            "#,
+        )
+        .file("b/src/lib.rs", "pub fn b() { common::f2(mid::thing()) }")
+        .file(
+            "mid/Cargo.toml",
+            r#"
+            [package]
+            name = "mid"
+            version = "0.1.0"
+
+            [dependencies]
+            common = "1.0"
+            "#,
+        )
+        .file(
+            "mid/src/lib.rs",
+            "pub fn thing() -> common::Thing { common::Thing }",
+        )
+        .build();
+
+    p.cargo("check -p a -p b -v -Zfeatures=root")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name common [..]--cfg [..]f2[..]")
+        .with_stderr_does_not_contain(
+            "[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]--cfg [..]f2[..]",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn feature_unification_workspace() {
+    // `-Zfeatures=ws` builds a member as if the whole workspace was selected.
+    let p = shared_dep_ws();
+
+    p.cargo("check -p a -v")
+        .with_stderr_line_without(&["[RUNNING] `rustc --crate-name common"], &["f2"])
+        .run();
+
+    p.cargo("clean").run();
+    p.cargo("check -p a -v -Zfeatures=ws")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name common [..]--cfg [..]f1[..]--cfg [..]f2[..]",
+        )
+        .with_stderr_does_not_contain("[CHECKING] b [..]")
+        .run();
+
+    // Building the whole workspace reuses the same artifacts.
+    p.cargo("check --workspace -v")
+        .with_stderr_contains("[FRESH] common v1.0.0")
+        .with_stderr_contains("[FRESH] a v0.1.0 [..]")
+        .with_stderr_contains("[CHECKING] b v0.1.0 [..]")
+        .run();
+}
@@ -1495,3 +1495,219 @@ The following differences were detected with the current configuration:
 ")
         .run();
 }
+
+#[cargo_test]
+fn migrate_workspace_requires_z_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("fix --edition --migrate-workspace --allow-no-vcs")
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--migrate-workspace` requires `-Z edition-migration`")
+        .run();
+}
+
+#[cargo_test]
+fn migrate_workspace_in_dependency_order() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = { path = "../b" }
+            "#,
+        )
+        .file(
+            "a/src/lib.rs",
+            r#"
+                extern crate b;
+
+                mod inner {
+                    pub fn value() -> u32 { ::b::b() }
+                }
+
+                pub fn a() -> u32 {
+                    ::inner::value()
+                }
+            "#,
+        )
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file(
+            "b/src/lib.rs",
+            r#"
+                mod inner {
+                    pub fn value() -> u32 { 1 }
+                }
+
+                pub fn b() -> u32 {
+                    ::inner::value()
+                }
+            "#,
+        )
+        .build();
+
+    let output = p
+        .cargo("fix --edition --migrate-workspace --workspace --allow-no-vcs -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .exec_with_output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // `b` must be migrated before `a`, since `a` depends on it.
+    let b_pos = stderr
+        .find("Migrating b/src/lib.rs from 2015 edition to 2018")
+        .unwrap();
+    let a_pos = stderr
+        .find("Migrating a/src/lib.rs from 2015 edition to 2018")
+        .unwrap();
+    assert!(b_pos < a_pos, "unexpected order:\n{}", stderr);
+    assert!(stderr.contains("Edition migration summary:"));
+
+    assert!(p
+        .read_file("b/src/lib.rs")
+        .contains("crate::inner::value()"));
+    assert!(p
+        .read_file("a/src/lib.rs")
+        .contains("crate::inner::value()"));
+    assert!(p.read_file("a/Cargo.toml").contains("edition = \"2018\""));
+    assert!(p.read_file("b/Cargo.toml").contains("edition = \"2018\""));
+    assert!(!p.root().join("target/.edition-migration.json").exists());
+}
+
+#[cargo_test]
+fn migrate_workspace_resumes_after_failure() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = { path = "../b" }
+            "#,
+        )
+        .file("a/src/lib.rs", "pub fn a() { let x: u32 = \"\"; }")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file("b/src/lib.rs", "pub fn b() {}")
+        .build();
+
+    p.cargo("fix --edition --migrate-workspace --workspace --allow-no-vcs -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("  b v0.1.0 ([..]) (migrated)")
+        .with_stderr_contains("  a v0.1.0 ([..]) (failed)")
+        .with_stderr_contains("[ERROR] failed to migrate `a v0.1.0 ([..])` to the next edition")
+        .run();
+    assert!(p.root().join("target/.edition-migration.json").exists());
+    assert!(!p.read_file("b/Cargo.toml").contains("edition"));
+
+    p.change_file("a/src/lib.rs", "pub fn a() {}");
+    p.cargo("fix --edition --migrate-workspace --workspace --allow-no-vcs -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[SKIPPING] b v0.1.0 ([..]) (already migrated)")
+        .with_stderr_contains("[MIGRATING] a/src/lib.rs from 2015 edition to 2018")
+        .run();
+    assert!(p.read_file("a/Cargo.toml").contains("edition = \"2018\""));
+    assert!(p.read_file("b/Cargo.toml").contains("edition = \"2018\""));
+}
+
+#[cargo_test]
+fn migrate_workspace_resumes_with_uncommitted_changes() {
+    let p = git::new("foo", |p| {
+        p.file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = { path = "../b" }
+            "#,
+        )
+        .file("a/src/lib.rs", "pub fn a() { let x: u32 = \"\"; }")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file(
+            "b/src/lib.rs",
+            "mod m { pub fn f() {} } pub fn b() { ::m::f() }",
+        )
+    });
+
+    p.cargo("fix --edition --migrate-workspace --workspace -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to migrate `a v0.1.0 ([..])` to the next edition")
+        .run();
+    assert!(p.read_file("b/src/lib.rs").contains("crate::m::f()"));
+
+    // The changes of the migration, and the fix of `a`, are not committed.
+    p.change_file("a/src/lib.rs", "pub fn a() {}");
+    p.cargo("fix --edition --migrate-workspace --workspace -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[SKIPPING] b v0.1.0 ([..]) (already migrated)")
+        .run();
+    assert!(p.read_file("a/Cargo.toml").contains("edition = \"2018\""));
+}
+
+#[cargo_test]
+fn migrate_workspace_member_with_same_name_as_dependency() {
+    Package::new("b", "1.0.0")
+        .file("src/lib.rs", "pub fn registry_b() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = "1.0"
+            "#,
+        )
+        .file("a/src/lib.rs", "pub fn a() {}")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file("b/src/lib.rs", "pub fn b() {}")
+        .build();
+
+    p.cargo("fix --edition --migrate-workspace --workspace --allow-no-vcs -Zedition-migration")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[MIGRATING] b/src/lib.rs from 2015 edition to 2018")
+        .with_stderr_contains("[MIGRATING] a/src/lib.rs from 2015 edition to 2018")
+        .run();
+    assert!(p.read_file("a/Cargo.toml").contains("edition = \"2018\""));
+    assert!(p.read_file("b/Cargo.toml").contains("edition = \"2018\""));
+}
@@ -0,0 +1,145 @@
+//! Tests for `-Z locked-checksums`.
+
+use cargo_test_support::install::cargo_home;
+use cargo_test_support::registry::Package;
+use cargo_test_support::{project, Project};
+use std::fs;
+
+fn make_project() -> Project {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").publish();
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+
+                [target.'cfg(any())'.dependencies]
+                baz = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn verified() {
+    let p = make_project();
+    p.cargo("fetch").run();
+    let lock = p.read_lockfile();
+
+    p.cargo("build -Zlocked-checksums")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[COMPILING] bar v1.0.0
+[COMPILING] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+    assert_eq!(p.read_lockfile(), lock);
+}
+
+#[cargo_test]
+fn missing_sources() {
+    let p = make_project();
+    p.cargo("generate-lockfile").run();
+
+    // Packages for other platforms must be available as well.
+    p.cargo("build -Zlocked-checksums")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the sources of the locked packages cannot be verified with -Z locked-checksums:
+  `bar v1.0.0`: attempting to make an HTTP request, but --frozen was specified
+  `baz v1.0.0`: attempting to make an HTTP request, but --frozen was specified
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn missing_checksums() {
+    let p = make_project();
+    p.cargo("fetch").run();
+    let lock = p.read_lockfile();
+    let stripped: String = lock
+        .lines()
+        .filter(|line| !line.starts_with("checksum = "))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    p.change_file("Cargo.lock", &stripped);
+
+    p.cargo("build -Zlocked-checksums")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] Cargo.lock cannot be verified with -Z locked-checksums:
+  `bar v1.0.0` is locked without a checksum
+  `baz v1.0.0` is locked without a checksum
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn not_locked() {
+    let p = make_project();
+    Package::new("qux", "1.0.0").publish();
+    p.cargo("fetch").run();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            bar = "1.0"
+            qux = "1.0"
+        "#,
+    );
+
+    p.cargo("build -Zlocked-checksums")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] Cargo.lock cannot be verified with -Z locked-checksums:")
+        .with_stderr_contains("  `qux v1.0.0` is not locked")
+        .run();
+}
+
+#[cargo_test]
+fn modified_cache() {
+    let p = make_project();
+    p.cargo("fetch").run();
+    p.cargo("build").run();
+
+    let cache = cargo_home().join("registry/cache");
+    let registry = fs::read_dir(&cache).unwrap().next().unwrap().unwrap();
+    let crate_file = registry.path().join("bar-1.0.0.crate");
+    let mut contents = fs::read(&crate_file).unwrap();
+    contents.extend_from_slice(b"modified");
+    fs::write(&crate_file, contents).unwrap();
+
+    // The cached file is only verified on request.
+    p.cargo("build").with_stderr("[FINISHED] [..]").run();
+
+    p.cargo("build -Zlocked-checksums")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the sources of the locked packages cannot be verified with -Z locked-checksums:
+  `bar v1.0.0`: failed to verify the checksum of `bar v1.0.0 (registry [..])` in `[..]bar-1.0.0.crate`
+",
+        )
+        .run();
+}
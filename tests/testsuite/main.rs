@@ -9,7 +9,9 @@
 extern crate cargo_test_macro;
 
 mod advanced_env;
+mod affected;
 mod alt_registry;
+mod audit_lockfile;
 mod bad_config;
 mod bad_manifest_path;
 mod bench;
@@ -38,12 +40,15 @@ mod credential_process;
 mod cross_compile;
 mod cross_publish;
 mod custom_target;
+mod daemon;
 mod death;
 mod dep_info;
 mod directory;
 mod doc;
 mod edition;
 mod error;
+mod feature_constraints;
+mod feature_metadata;
 mod features;
 mod features2;
 mod features_namespaced;
@@ -64,6 +69,7 @@ mod jobserver;
 mod list_availables;
 mod local_registry;
 mod locate_project;
+mod locked_checksums;
 mod lockfile_compat;
 mod login;
 mod logout;
@@ -72,6 +78,7 @@ mod member_discovery;
 mod member_errors;
 mod message_format;
 mod metabuild;
+mod merge_lockfile;
 mod metadata;
 mod minimal_versions;
 mod multitarget;
@@ -80,6 +87,7 @@ mod new;
 mod offline;
 mod old_cargos;
 mod out_dir;
+mod outdated_deps;
 mod owner;
 mod package;
 mod package_features;
@@ -88,6 +96,7 @@ mod path;
 mod paths;
 mod pkgid;
 mod plugins;
+mod policy;
 mod proc_macro;
 mod profile_config;
 mod profile_custom;
@@ -103,6 +112,7 @@ mod registry;
 mod rename_deps;
 mod replace;
 mod required_features;
+mod resolve_explain;
 mod run;
 mod rust_version;
 mod rustc;
@@ -125,6 +135,7 @@ mod vendor;
 mod verify_project;
 mod version;
 mod warn_on_failure;
+mod watch;
 mod weak_dep_features;
 mod workspaces;
 mod yank;
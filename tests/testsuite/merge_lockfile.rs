@@ -0,0 +1,133 @@
+//! Tests for resolving merge conflicts in `Cargo.lock` with `-Z lockfile-merge`.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{project, Project};
+
+const BOTH_DEPS: &str = r#"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+
+    [dependencies]
+    bar = "1.0"
+    baz = "1.0"
+"#;
+
+/// Returns a project with both dependencies, and the two versions of its
+/// lock file: one which added `baz`, and one which updated `bar`.
+fn make_project() -> (Project, String, String) {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    let base = p.read_lockfile();
+
+    p.change_file("Cargo.toml", BOTH_DEPS);
+    p.cargo("fetch").run();
+    let ours = p.read_lockfile();
+
+    p.change_file("Cargo.lock", &base);
+    p.change_file("Cargo.toml", &BOTH_DEPS.replace("baz = \"1.0\"", ""));
+    Package::new("bar", "1.0.1").publish();
+    p.cargo("update -p bar").run();
+    let theirs = p.read_lockfile();
+    assert!(theirs.contains("1.0.1"));
+
+    p.change_file("Cargo.toml", BOTH_DEPS);
+    std::fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    (p, ours, theirs)
+}
+
+fn assert_merged(lock: &str) {
+    assert!(!lock.contains("<<<<<<<"));
+    assert!(!lock.contains("======="));
+    assert_eq!(lock.matches("name = \"bar\"").count(), 1);
+    assert!(lock.contains("name = \"baz\""));
+}
+
+#[cargo_test]
+fn conflict_markers() {
+    let (p, ours, theirs) = make_project();
+    p.change_file(
+        "Cargo.lock",
+        &format!(
+            "<<<<<<< HEAD\n{}||||||| merged common ancestors\n\
+             [[package]]\n=======\n{}>>>>>>> update-bar\n",
+            ours, theirs
+        ),
+    );
+
+    p.cargo("tree")
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to parse lock file at: [..]Cargo.lock")
+        .run();
+
+    p.cargo("tree -Zlockfile-merge")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[MERGING] conflicting versions of Cargo.lock")
+        .run();
+    assert_merged(&p.read_lockfile());
+
+    // Once merged, the lock file is left alone.
+    p.cargo("tree -Zlockfile-merge --locked")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("")
+        .run();
+}
+
+#[cargo_test]
+fn locked() {
+    let (p, ours, theirs) = make_project();
+    p.change_file(
+        "Cargo.lock",
+        &format!(
+            "<<<<<<< HEAD\n{}=======\n{}>>>>>>> update-bar\n",
+            ours, theirs
+        ),
+    );
+
+    p.cargo("tree -Zlockfile-merge --locked")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the lock file [..] needs to be updated but --locked was passed[..]",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn merge_driver() {
+    let (p, ours, theirs) = make_project();
+    p.change_file("base.lock", "");
+    p.change_file("ours.lock", &ours);
+    p.change_file("theirs.lock", &theirs);
+
+    p.cargo("merge-lockfile base.lock ours.lock theirs.lock")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo merge-lockfile` command is unstable, \
+             pass `-Z lockfile-merge` to enable it",
+        )
+        .run();
+
+    p.cargo("-Zlockfile-merge merge-lockfile base.lock ours.lock theirs.lock")
+        .masquerade_as_nightly_cargo()
+        .run();
+    assert_merged(&p.read_file("ours.lock"));
+    assert_eq!(p.read_file("theirs.lock"), theirs);
+    assert!(!p.root().join("Cargo.lock").exists());
+}
@@ -36,3 +36,99 @@ fn minimal_version_cli() {
 
     assert!(!lock.contains("1.1.0"));
 }
+
+// Ensure that "-Z direct-minimal-versions" only resolves the direct
+// dependencies of workspace members to their minimal versions.
+#[cargo_test]
+fn direct_minimal_version_cli() {
+    Package::new("indirect", "1.0.0").publish();
+    Package::new("indirect", "1.1.0").publish();
+    Package::new("dep", "1.0.0")
+        .dep("indirect", "1.0")
+        .publish();
+    Package::new("dep", "1.1.0")
+        .dep("indirect", "1.0")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                authors = []
+                version = "0.0.1"
+
+                [dependencies]
+                dep = "1.0"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("generate-lockfile -Zdirect-minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .run();
+
+    let lock = p.read_lockfile();
+
+    assert!(lock.contains("name = \"dep\"\nversion = \"1.0.0\""));
+    assert!(lock.contains("name = \"indirect\"\nversion = \"1.1.0\""));
+}
+
+// `cargo package --minimal-versions` verifies the package with the minimal
+// versions of its direct dependencies, catching untruthful lower bounds.
+#[cargo_test]
+fn package_verify_minimal_versions() {
+    Package::new("dep", "1.0.0")
+        .file("src/lib.rs", "pub fn old() {}")
+        .publish();
+    Package::new("dep", "1.1.0")
+        .file("src/lib.rs", "pub fn old() {} pub fn new() {}")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                license = "MIT"
+                description = "foo"
+
+                [dependencies]
+                dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "pub fn f() { dep::new() }")
+        .build();
+
+    p.cargo("package --minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--minimal-versions` flag is unstable, pass `-Z unstable-options` to enable it
+See https://github.com/rust-lang/cargo/issues/5657 for more information about \
+the `--minimal-versions` flag.
+",
+        )
+        .run();
+
+    // The verification normally uses the latest version.
+    p.cargo("package").run();
+
+    p.cargo("package -Zunstable-options --minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[COMPILING] dep v1.0.0")
+        .with_stderr_contains("[..]cannot find function `new` in crate `dep`[..]")
+        .run();
+
+    p.change_file("src/lib.rs", "pub fn f() { dep::old() }");
+    p.cargo("package -Zunstable-options --minimal-versions")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[COMPILING] dep v1.0.0")
+        .run();
+}
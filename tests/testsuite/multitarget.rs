@@ -2,19 +2,6 @@
 
 use cargo_test_support::{basic_manifest, cross_compile, project, rustc_host};
 
-#[cargo_test]
-fn double_target_rejected() {
-    let p = project()
-        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
-        .file("src/main.rs", "fn main() {}")
-        .build();
-
-    p.cargo("build --target a --target b")
-        .with_stderr("error: specifying multiple `--target` flags requires `-Zmultitarget`")
-        .with_status(101)
-        .run();
-}
-
 #[cargo_test]
 fn simple_build() {
     if cross_compile::disabled() {
@@ -27,12 +14,11 @@ fn simple_build() {
         .file("src/main.rs", "fn main() {}")
         .build();
 
-    p.cargo("build -Z multitarget")
+    p.cargo("build")
         .arg("--target")
         .arg(&t1)
         .arg("--target")
         .arg(&t2)
-        .masquerade_as_nightly_cargo()
         .run();
 
     assert!(p.target_bin(t1, "foo").is_file());
@@ -51,12 +37,11 @@ fn simple_test() {
         .file("src/lib.rs", "fn main() {}")
         .build();
 
-    p.cargo("test -Z multitarget")
+    p.cargo("test")
         .arg("--target")
         .arg(&t1)
         .arg("--target")
         .arg(&t2)
-        .masquerade_as_nightly_cargo()
         .with_stderr_contains(&format!("[RUNNING] [..]{}[..]", t1))
         .with_stderr_contains(&format!("[RUNNING] [..]{}[..]", t2))
         .run();
@@ -69,10 +54,34 @@ fn simple_run() {
         .file("src/main.rs", "fn main() {}")
         .build();
 
-    p.cargo("run -Z multitarget --target a --target b")
+    p.cargo("run --target a --target b")
         .with_stderr("error: only one `--target` argument is supported")
         .with_status(101)
-        .masquerade_as_nightly_cargo()
+        .run();
+}
+
+#[cargo_test]
+fn run_with_build_target_config_list() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                target = ["a", "b"]
+            "#,
+        )
+        .build();
+
+    p.cargo("run")
+        .with_stderr(
+            "\
+[ERROR] only one target is supported, but the `build.target` config value lists several targets
+Pass `--target` to choose one of them.
+",
+        )
+        .with_status(101)
         .run();
 }
 
@@ -88,12 +97,11 @@ fn simple_doc() {
         .file("src/lib.rs", "//! empty lib")
         .build();
 
-    p.cargo("doc -Z multitarget")
+    p.cargo("doc")
         .arg("--target")
         .arg(&t1)
         .arg("--target")
         .arg(&t2)
-        .masquerade_as_nightly_cargo()
         .run();
 
     assert!(p.build_dir().join(&t1).join("doc/foo/index.html").is_file());
@@ -112,12 +120,11 @@ fn simple_check() {
         .file("src/main.rs", "fn main() {}")
         .build();
 
-    p.cargo("check -Z multitarget")
+    p.cargo("check")
         .arg("--target")
         .arg(&t1)
         .arg("--target")
         .arg(&t2)
-        .masquerade_as_nightly_cargo()
         .run();
 }
 
@@ -132,13 +139,181 @@ fn same_value_twice() {
         .file("src/main.rs", "fn main() {}")
         .build();
 
-    p.cargo("build -Z multitarget")
+    p.cargo("build")
         .arg("--target")
         .arg(&t)
         .arg("--target")
         .arg(&t)
-        .masquerade_as_nightly_cargo()
         .run();
 
     assert!(p.target_bin(t, "foo").is_file());
 }
+
+#[cargo_test]
+fn build_target_config_list() {
+    if cross_compile::disabled() {
+        return;
+    }
+    let t1 = cross_compile::alternate();
+    let t2 = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [build]
+                    target = ["{}", "{}"]
+                "#,
+                t1, t2
+            ),
+        )
+        .build();
+
+    p.cargo("build").run();
+
+    assert!(p.target_bin(t1, "foo").is_file());
+    assert!(p.target_bin(t2, "foo").is_file());
+}
+
+#[cargo_test]
+fn target_flag_overrides_config_list() {
+    let t = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                target = ["a", "b"]
+            "#,
+        )
+        .build();
+
+    p.cargo("build --target").arg(&t).run();
+
+    assert!(p.target_bin(t, "foo").is_file());
+}
+
+#[cargo_test]
+fn out_dir_per_target() {
+    if cross_compile::disabled() {
+        return;
+    }
+    let t1 = cross_compile::alternate();
+    let t2 = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -Z unstable-options --out-dir out")
+        .arg("--target")
+        .arg(&t1)
+        .arg("--target")
+        .arg(&t2)
+        .masquerade_as_nightly_cargo()
+        .run();
+
+    let bin = format!("foo{}", std::env::consts::EXE_SUFFIX);
+    assert!(p.root().join("out").join(&t1).join(&bin).is_file());
+    assert!(p.root().join("out").join(&t2).join(&bin).is_file());
+}
+
+#[cargo_test]
+fn test_runner_per_target() {
+    if cross_compile::disabled() {
+        return;
+    }
+    let t1 = cross_compile::alternate();
+    let t2 = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [target.{}]
+                    runner = "nonexistent-runner-one"
+
+                    [target.{}]
+                    runner = "nonexistent-runner-two"
+                "#,
+                t1, t2
+            ),
+        )
+        .build();
+
+    p.cargo("test --no-fail-fast --verbose")
+        .arg("--target")
+        .arg(&t1)
+        .arg("--target")
+        .arg(&t2)
+        .with_status(101)
+        .with_stderr_contains("[RUNNING] `nonexistent-runner-one [..]`")
+        .with_stderr_contains("[RUNNING] `nonexistent-runner-two [..]`")
+        .run();
+}
+
+#[cargo_test]
+fn message_format_json() {
+    if cross_compile::disabled() {
+        return;
+    }
+    let t1 = cross_compile::alternate();
+    let t2 = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    let artifact = |target: &str| {
+        format!(
+            r#"
+                {{
+                    "reason": "compiler-artifact",
+                    "package_id": "foo 1.0.0 ([..])",
+                    "manifest_path": "[..]",
+                    "target": {{
+                        "kind": ["bin"],
+                        "crate_types": ["bin"],
+                        "doc": true,
+                        "doctest": false,
+                        "edition": "2015",
+                        "name": "foo",
+                        "src_path": "[..]",
+                        "test": true
+                    }},
+                    "profile": {{
+                        "debug_assertions": true,
+                        "debuginfo": 2,
+                        "opt_level": "0",
+                        "overflow_checks": true,
+                        "test": false
+                    }},
+                    "executable": "[..]/target/{}/debug/foo[EXE]",
+                    "features": [],
+                    "filenames": "{{...}}",
+                    "fresh": false
+                }}
+            "#,
+            target
+        )
+    };
+
+    p.cargo("build --message-format=json")
+        .arg("--target")
+        .arg(&t1)
+        .arg("--target")
+        .arg(&t2)
+        .with_json_contains_unordered(&format!(
+            "{}\n\n{}\n\n{}",
+            artifact(t1),
+            artifact(t2),
+            r#"{"reason": "build-finished", "success": true}"#
+        ))
+        .run();
+}
@@ -0,0 +1,288 @@
+//! Tests for the `cargo outdated-deps` command.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project, Project};
+
+fn make_project() -> Project {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "0.1.0").publish();
+    Package::new("qux", "0.3.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["member"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = { version = "0.1", features = [] }
+
+                [dev-dependencies.qux2]
+                package = "qux"
+                version = "=0.3.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "member/Cargo.toml",
+            r#"
+                [package]
+                name = "member"
+                version = "0.1.0"
+
+                [target.'cfg(unix)'.build-dependencies]
+                bar = ">= 0.5, < 1.1"
+
+                [dependencies]
+                foo = { path = ".." }
+            "#,
+        )
+        .file("member/src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    Package::new("bar", "1.0.1").publish();
+    Package::new("bar", "2.0.0").publish();
+    Package::new("bar", "3.0.0-alpha.1").publish();
+    Package::new("baz", "0.2.0").publish();
+    Package::new("qux", "0.3.1").publish();
+    p
+}
+
+#[cargo_test]
+fn requires_z_flag() {
+    let p = make_project();
+
+    p.cargo("outdated-deps")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo outdated-deps` command is unstable, pass `-Z outdated` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn table() {
+    let p = make_project();
+
+    p.cargo("outdated-deps -Zoutdated")
+        .masquerade_as_nightly_cargo()
+        .with_stdout(
+            "\
+Member  Name  Requirement  Locked  Compatible  Latest  Kind
+foo     bar   ^1.0         1.0.0   1.0.1       2.0.0   normal
+foo     baz   ^0.1         0.1.0   0.1.0       0.2.0   normal
+foo     qux2  =0.3.0       0.3.0   0.3.0       0.3.1   dev
+member  bar   >=0.5, <1.1  1.0.0   1.0.1       2.0.0   build (cfg(unix))
+",
+        )
+        .with_stderr("[UPDATING] `[..]` index")
+        .run();
+
+    // Nothing is reported once everything is up to date.
+    let lock = p.read_lockfile();
+    p.change_file("Cargo.toml", &basic_manifest("foo", "0.1.0"));
+    p.change_file("member/Cargo.toml", &basic_manifest("member", "0.1.0"));
+    p.cargo("outdated-deps -Zoutdated --manifest-path member/Cargo.toml")
+        .masquerade_as_nightly_cargo()
+        .with_stdout("")
+        .with_stderr("[NOTE] all dependencies are up to date")
+        .run();
+    // The report does not write `Cargo.lock`.
+    assert_eq!(p.read_lockfile(), lock);
+}
+
+#[cargo_test]
+fn json() {
+    let p = make_project();
+
+    p.cargo("outdated-deps -Zoutdated --output-format json")
+        .masquerade_as_nightly_cargo()
+        .with_json(
+            r#"
+            {
+              "version": 1,
+              "dependencies": [
+                {
+                  "member": "foo",
+                  "name": "bar",
+                  "package": "bar",
+                  "kind": "normal",
+                  "target": null,
+                  "req": "^1.0",
+                  "locked": "1.0.0",
+                  "compatible": "1.0.1",
+                  "latest": "2.0.0"
+                },
+                {
+                  "member": "foo",
+                  "name": "baz",
+                  "package": "baz",
+                  "kind": "normal",
+                  "target": null,
+                  "req": "^0.1",
+                  "locked": "0.1.0",
+                  "compatible": "0.1.0",
+                  "latest": "0.2.0"
+                },
+                {
+                  "member": "foo",
+                  "name": "qux2",
+                  "package": "qux",
+                  "kind": "dev",
+                  "target": null,
+                  "req": "=0.3.0",
+                  "locked": "0.3.0",
+                  "compatible": "0.3.0",
+                  "latest": "0.3.1"
+                },
+                {
+                  "member": "member",
+                  "name": "bar",
+                  "package": "bar",
+                  "kind": "build",
+                  "target": "cfg(unix)",
+                  "req": ">=0.5, <1.1",
+                  "locked": "1.0.0",
+                  "compatible": "1.0.1",
+                  "latest": "2.0.0"
+                }
+              ]
+            }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn upgrade() {
+    let p = make_project();
+
+    p.cargo("outdated-deps -Zoutdated --upgrade")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[WARNING] could not upgrade `bar` in `member/Cargo.toml`: \
+the requirement `>= 0.5, < 1.1` is not a plain version
+[UPGRADING] bar from `1.0` to `2.0` in Cargo.toml
+[UPGRADING] baz from `0.1` to `0.2` in Cargo.toml
+[UPGRADING] qux2 from `=0.3.0` to `=0.3.1` in Cargo.toml
+",
+        )
+        .run();
+
+    assert_eq!(
+        p.read_file("Cargo.toml"),
+        r#"
+                [workspace]
+                members = ["member"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "2.0"
+                baz = { version = "0.2", features = [] }
+
+                [dev-dependencies.qux2]
+                package = "qux"
+                version = "=0.3.1"
+            "#
+    );
+    let lock = p.read_lockfile();
+    assert!(lock.contains("name = \"bar\"\nversion = \"2.0.0\""));
+    assert!(lock.contains("name = \"bar\"\nversion = \"1.0.0\""));
+    assert!(lock.contains("name = \"baz\"\nversion = \"0.2.0\""));
+    assert!(lock.contains("name = \"qux\"\nversion = \"0.3.1\""));
+}
+
+#[cargo_test]
+fn upgrade_inline_tables_and_multi_line_values() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "0.1.0").feature("x", &[]).publish();
+    let manifest = |bar: &str, baz: &str| {
+        format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = """
+                [dependencies]
+                bar = "1.0"
+                """
+
+                [dependencies]
+                bar = {{default-features=false,version='{}'}}
+
+                [dependencies.baz]
+                features = [
+                    "x", # [dependencies]
+                ]
+                version = "{}"
+            "#,
+            bar, baz
+        )
+    };
+    let p = project()
+        .file("Cargo.toml", &manifest("1.0", "0.1"))
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    Package::new("bar", "2.0.0").publish();
+    Package::new("baz", "0.2.0").feature("x", &[]).publish();
+
+    p.cargo("outdated-deps -Zoutdated --upgrade")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPGRADING] bar from `1.0` to `2.0` in Cargo.toml
+[UPGRADING] baz from `0.1` to `0.2` in Cargo.toml
+",
+        )
+        .run();
+    assert_eq!(p.read_file("Cargo.toml"), manifest("2.0", "0.2"));
+}
+
+#[cargo_test]
+fn upgrade_fails_on_requirement_not_found() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").publish();
+    let manifest = r#"
+        [package]
+        name = "foo"
+        version = "0.1.0"
+
+        [dependencies]
+        bar = "1.0"
+        baz = { version = """1.0""" }
+    "#;
+    let p = project()
+        .file("Cargo.toml", manifest)
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    Package::new("bar", "2.0.0").publish();
+    Package::new("baz", "2.0.0").publish();
+
+    p.cargo("outdated-deps -Zoutdated --upgrade")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] could not find the requirement of `baz` in `Cargo.toml` to upgrade it
+Only requirements written on a single line, as a string or as the `version` \
+of the dependency, can be upgraded. No manifest was changed.
+",
+        )
+        .with_stderr_does_not_contain("[UPGRADING] [..]")
+        .run();
+    assert_eq!(p.read_file("Cargo.toml"), manifest);
+}
@@ -0,0 +1,393 @@
+//! Tests for the `cargo policy` command.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, git, project};
+
+/// Publishes a package with the given license expression.
+fn publish_with_license(name: &str, vers: &str, license: &str) {
+    Package::new(name, vers)
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "{}"
+                    version = "{}"
+                    license = "{}"
+                "#,
+                name, vers, license
+            ),
+        )
+        .file("src/lib.rs", "")
+        .publish();
+}
+
+#[cargo_test]
+fn requires_z_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo policy` command is unstable, \
+             pass `-Z dependency-policy` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn no_policy() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("[UPDATING] `[..]` index")
+        .run();
+}
+
+#[cargo_test]
+fn licenses() {
+    publish_with_license("bar", "1.0.0", "GPL-3.0");
+    publish_with_license("baz", "1.0.0", "MIT OR GPL-3.0");
+    publish_with_license("qux", "1.0.0", "MIT AND GPL-3.0");
+    Package::new("unlicensed", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = "1.0"
+                qux = "1.0"
+                unlicensed = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allow-licenses = ["MIT", "Apache-2.0"]
+            "#,
+        )
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[ERROR] license `GPL-3.0` of package `bar v1.0.0` is not allowed
+
+bar v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] license `MIT AND GPL-3.0` of package `qux v1.0.0` is not allowed
+
+qux v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] package `unlicensed v1.0.0` does not specify a license expression
+
+unlicensed v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] found 3 dependency policy violations
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn deny_licenses() {
+    publish_with_license("bar", "1.0.0", "MIT/Apache-2.0");
+    publish_with_license("baz", "1.0.0", "mit");
+    publish_with_license("qux", "1.0.0", "MIT or Apache-2.0");
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = "1.0"
+                qux = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                deny-licenses = ["MIT"]
+            "#,
+        )
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[ERROR] license `mit` of package `baz v1.0.0` is not allowed
+
+baz v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] package `qux v1.0.0` has an invalid license expression `MIT or Apache-2.0`: unexpected `or`
+
+qux v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] found 2 dependency policy violations
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn license_exceptions_and_later_versions() {
+    publish_with_license("bar", "1.0.0", "GPL-2.0+");
+    publish_with_license("baz", "1.0.0", "GPL-2.0 WITH Classpath-exception-2.0");
+    publish_with_license("qux", "1.0.0", "Apache-2.0 WITH LLVM-exception");
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = "1.0"
+                qux = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allow-licenses = ["GPL-2.0", "Apache-2.0 WITH LLVM-exception"]
+            "#,
+        )
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[ERROR] license `GPL-2.0+` of package `bar v1.0.0` is not allowed
+
+bar v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] license `GPL-2.0 WITH Classpath-exception-2.0` of package `baz v1.0.0` is not allowed
+
+baz v1.0.0
+└── foo v0.1.0 ([..]/foo)
+[ERROR] found 2 dependency policy violations
+",
+        )
+        .run();
+
+    // Denying a license also denies its variants.
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [policy]
+            deny-licenses = ["GPL-2.0"]
+        "#,
+    );
+    p.cargo("policy -Zdependency-policy --charset ascii")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] license `GPL-2.0+` of package `bar v1.0.0` is not allowed
+
+bar v1.0.0
+`-- foo v0.1.0 ([..]/foo)
+[ERROR] license `GPL-2.0 WITH Classpath-exception-2.0` of package `baz v1.0.0` is not allowed
+
+baz v1.0.0
+`-- foo v0.1.0 ([..]/foo)
+[ERROR] found 2 dependency policy violations
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn packages_and_sources() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").dep("bar", "1.0").publish();
+    let git_project = git::new("gitdep", |project| {
+        project
+            .file("Cargo.toml", &basic_manifest("gitdep", "0.5.0"))
+            .file("src/lib.rs", "")
+    });
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    baz = "1.0"
+                    gitdep = {{ git = "{}" }}
+                    pathdep = {{ path = "pathdep" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file("pathdep/Cargo.toml", &basic_manifest("pathdep", "0.1.0"))
+        .file("pathdep/src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                deny-packages = ["bar"]
+                allow-registries = ["crates-io"]
+                allow-git = ["https://example.com/allowed"]
+            "#,
+        )
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[UPDATING] git repository `[..]`
+[ERROR] package `bar v1.0.0` is denied by `bar` in `policy.deny-packages`
+
+bar v1.0.0
+└── baz v1.0.0
+    └── foo v0.1.0 ([..]/foo)
+[ERROR] package `gitdep v0.5.0 ([..]/gitdep#[..])` comes from git repository `[..]/gitdep`, \
+which is not in `policy.allow-git`
+
+gitdep v0.5.0 ([..]/gitdep#[..])
+└── foo v0.1.0 ([..]/foo)
+[ERROR] found 2 dependency policy violations
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn duplicates() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("bar", "2.0.0").publish();
+    Package::new("baz", "1.0.0").dep("bar", "2.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[WARNING] found 2 semver-incompatible versions of `bar`: v1.0.0, v2.0.0
+
+bar v1.0.0
+└── foo v0.1.0 ([..]/foo)
+
+bar v2.0.0
+└── baz v1.0.0
+    └── foo v0.1.0 ([..]/foo)
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [policy]
+            duplicates = "deny"
+        "#,
+    );
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] found 2 semver-incompatible versions of `bar`: v1.0.0, v2.0.0
+
+bar v1.0.0
+└── foo v0.1.0 ([..]/foo)
+
+bar v2.0.0
+└── baz v1.0.0
+    └── foo v0.1.0 ([..]/foo)
+[ERROR] found 1 dependency policy violation
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [policy]
+            duplicates = "deny"
+            allow-duplicates = ["bar"]
+        "#,
+    );
+    p.cargo("policy -Zdependency-policy")
+        .masquerade_as_nightly_cargo()
+        .with_stderr("")
+        .run();
+}
@@ -1,6 +1,6 @@
 //! Tests for public/private dependencies.
 
-use cargo_test_support::registry::Package;
+use cargo_test_support::registry::{Dependency, Package};
 use cargo_test_support::{is_nightly, project};
 
 #[cargo_test]
@@ -201,3 +201,140 @@ Caused by:
         )
         .run()
 }
+
+#[cargo_test]
+fn public_dep_conflict() {
+    Package::new("dep", "0.1.0").publish();
+    Package::new("dep", "0.2.0").publish();
+    Package::new("a", "0.1.0")
+        .add_dep(Dependency::new("dep", "0.1.0").public(true))
+        .publish();
+
+    // The feature is only enabled by a member of the workspace.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["foo"]
+            "#,
+        )
+        .file(
+            "foo/Cargo.toml",
+            r#"
+                cargo-features = ["public-dependency"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                a = "0.1.0"
+                dep = "0.2.0"
+            "#,
+        )
+        .file("foo/src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[..]failed to select a version for `dep`.")
+        .with_stderr_contains(
+            "the package `foo` can already see `dep v0.[..]`, directly or through public \
+             dependencies, and can only see one version of `dep`:",
+        )
+        .run();
+
+    // Without the public dependency, each package sees its own version.
+    Package::new("a", "0.1.1").dep("dep", "0.1.0").publish();
+    p.change_file(
+        "foo/Cargo.toml",
+        r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            a = "0.1.1"
+            dep = "0.2.0"
+        "#,
+    );
+
+    p.cargo("generate-lockfile")
+        .masquerade_as_nightly_cargo()
+        .run();
+}
+
+#[cargo_test]
+fn package_exported_priv_dep() {
+    if !is_nightly() {
+        // exported_private_dependencies lint is unstable
+        return;
+    }
+    Package::new("priv_dep", "0.1.0")
+        .file("src/lib.rs", "pub struct FromPriv;")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["public-dependency"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                license = "MIT"
+                description = "foo"
+
+                [dependencies]
+                priv_dep = "0.1.0"
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            "
+            extern crate priv_dep;
+            pub fn use_priv(_: priv_dep::FromPriv) {}
+        ",
+        )
+        .build();
+
+    // The lint is only a warning when building, but it is denied when
+    // verifying the package.
+    p.cargo("build --message-format=short")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]warning: type `[..]FromPriv` from private dependency[..]")
+        .run();
+    p.cargo("package --no-verify")
+        .masquerade_as_nightly_cargo()
+        .run();
+    p.cargo("package")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] type `[..]FromPriv` from private dependency 'priv_dep' in public interface",
+        )
+        .with_stderr_contains("[ERROR] failed to verify package tarball")
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            cargo-features = ["public-dependency"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            priv_dep = { version = "0.1.0", public = true }
+        "#,
+    );
+    p.cargo("package").masquerade_as_nightly_cargo().run();
+}
@@ -0,0 +1,227 @@
+//! Tests for `-Z resolve-explain`.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{git, project, Project};
+
+fn conflict_project() -> Project {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("bar", "1.1.0").publish();
+    Package::new("baz", "1.0.0").dep("bar", "^1.1").publish();
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "=1.0.0"
+                baz = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn relax_requirement() {
+    let p = conflict_project();
+
+    p.cargo("generate-lockfile -Zresolve-explain")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] [..]
+[ERROR] failed to select a version for `bar`.
+    ... required by package `baz v1.0.0`
+    ... which is depended on by `foo v0.1.0 ([..])`
+versions that meet the requirements `^1.1` are: 1.1.0
+
+all possible versions conflict with previously selected packages.
+
+  previously selected package `bar v1.0.0`
+    ... which is depended on by `foo v0.1.0 ([..])`
+
+failed to select a version for `bar` which could resolve this conflict
+
+conflicting requirements:
+  `bar = \"^1.1\"` required by `baz v1.0.0`
+      introduced by `foo v0.1.0 ([..])`
+      in `[..]/foo/Cargo.toml`
+  `bar = \"=1.0.0\"` required by `foo v0.1.0 ([..])`, which selected version 1.0.0
+      in `[..]/foo/Cargo.toml`
+
+help: relax the requirement `bar = \"=1.0.0\"` in `[..]/foo/Cargo.toml` to allow version 1.1.0
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn update_locked_package() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").dep("bar", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                baz = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    // A new git dependency leaves the registry packages locked.
+    Package::new("bar", "1.1.0").publish();
+    let qux = git::new("qux", |project| {
+        project
+            .file(
+                "Cargo.toml",
+                r#"
+                    [package]
+                    name = "qux"
+                    version = "1.0.0"
+
+                    [dependencies]
+                    bar = "^1.1"
+                "#,
+            )
+            .file("src/lib.rs", "")
+    });
+    p.change_file(
+        "Cargo.toml",
+        &format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                baz = "1.0"
+                qux = {{ git = "{}" }}
+            "#,
+            qux.url()
+        ),
+    );
+
+    p.cargo("tree -Zresolve-explain")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+conflicting requirements:
+  `bar = \"^1.1\"` required by `qux v1.0.0 ([..])`
+      introduced by `foo v0.1.0 ([..])`
+      in `[..]/foo/Cargo.toml`
+  `bar = \"=1.0.0\"` required by `baz v1.0.0`, which selected version 1.0.0
+      introduced by `foo v0.1.0 ([..])`
+      in `[..]/foo/Cargo.toml`
+
+help: run `cargo update -p bar:1.0.0 --precise 1.1.0`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn json() {
+    let p = conflict_project();
+
+    p.cargo("generate-lockfile -Zresolve-explain=json")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_json(
+            r#"
+            {
+              "version": 1,
+              "package": "bar",
+              "candidates": ["1.1.0"],
+              "requirements": [
+                {
+                  "package": "bar",
+                  "req": "^1.1",
+                  "required_by": "baz 1.0.0 (registry+[..])",
+                  "selected": null,
+                  "reason": "failed",
+                  "member": "foo 0.1.0 (path+[..])",
+                  "manifest": "[..]/foo/Cargo.toml"
+                },
+                {
+                  "package": "bar",
+                  "req": "=1.0.0",
+                  "required_by": "foo 0.1.0 (path+[..])",
+                  "selected": "1.0.0",
+                  "reason": "semver",
+                  "member": "foo 0.1.0 (path+[..])",
+                  "manifest": "[..]/foo/Cargo.toml"
+                }
+              ],
+              "suggestions": [
+                "relax the requirement `bar = \"=1.0.0\"` in `[..]/foo/Cargo.toml` to allow version 1.1.0"
+              ]
+            }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn no_matching_version() {
+    Package::new("bar", "0.2.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile -Zresolve-explain")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] [..]
+[ERROR] failed to select a version for the requirement `bar = \"^0.1\"`
+candidate versions found which didn't match: 0.2.0
+location searched: `[..]` index (which is replacing registry `[..]`)
+required by package `foo v0.1.0 ([..])`
+perhaps a crate was updated and forgotten to be re-vendored?
+
+conflicting requirements:
+  `bar = \"^0.1\"` required by `foo v0.1.0 ([..])`
+      in `[..]/foo/Cargo.toml`
+
+help: relax the requirement `bar = \"^0.1\"` in `[..]/foo/Cargo.toml` to allow version 0.2.0
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn bad_format() {
+    let p = conflict_project();
+
+    p.cargo("generate-lockfile -Zresolve-explain=yaml")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] unknown `-Z resolve-explain` format `yaml`, expected `text` or `json`",
+        )
+        .run();
+}
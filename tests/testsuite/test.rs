@@ -3,7 +3,7 @@
 use cargo_test_support::paths::CargoPathExt;
 use cargo_test_support::registry::Package;
 use cargo_test_support::{
-    basic_bin_manifest, basic_lib_manifest, basic_manifest, cargo_exe, project,
+    basic_bin_manifest, basic_lib_manifest, basic_manifest, cargo_exe, project, Project,
 };
 use cargo_test_support::{cross_compile, is_nightly, paths};
 use cargo_test_support::{rustc_host, sleep_ms};
@@ -4075,6 +4075,58 @@ fn cargo_test_doctest_xcompile_no_runner() {
     .run();
 }
 
+#[cargo_test]
+fn cargo_test_doctest_xcompile_no_run() {
+    if !cross_compile::can_run_on_host() {
+        return;
+    }
+    if !is_nightly() {
+        // -Zdoctest-xcompile is unstable
+        return;
+    }
+
+    let p = project()
+        .file("Cargo.toml", &basic_lib_manifest("foo"))
+        .file(
+            "src/lib.rs",
+            r#"
+            ///```
+            ///panic!("doc tests are not run with --no-run");
+            ///```
+            pub fn foo() -> u8 {
+                4
+            }
+            "#,
+        )
+        .build();
+
+    p.cargo("test --doc --no-run")
+        .with_status(101)
+        .with_stderr("[ERROR] Can't skip running doc tests with --no-run")
+        .run();
+
+    let target = cross_compile::alternate();
+    p.cargo(&format!(
+        "test --target {} -Zdoctest-xcompile --no-run",
+        target
+    ))
+    .masquerade_as_nightly_cargo()
+    .with_stderr_does_not_contain("[DOCTEST] foo")
+    .run();
+    let doctests = p.build_dir().join(target).join("debug/doctests/foo");
+    assert!(!doctests.exists());
+
+    p.cargo(&format!(
+        "test --target {} -Zdoctest-xcompile --doc --no-run",
+        target
+    ))
+    .masquerade_as_nightly_cargo()
+    .with_stderr_contains("[DOCTEST] foo")
+    .run();
+    assert!(doctests.is_dir());
+    assert_eq!(fs::read_dir(&doctests).unwrap().count(), 1);
+}
+
 #[cargo_test]
 fn panic_abort_tests() {
     if !is_nightly() {
@@ -4388,3 +4440,410 @@ fn test_workspaces_cwd() {
         .with_stdout_contains("test test_integration_deep_cwd ... ok")
         .run();
 }
+
+#[cargo_test]
+fn parallel_tests() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn lib_test() {}")
+        .file(
+            "tests/a.rs",
+            r#"
+                #[test]
+                fn a_test() {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                }
+            "#,
+        )
+        .file("tests/b.rs", "#[test] fn b_test() {}")
+        .build();
+
+    // The output of `b` is printed after `a`, although it finishes first.
+    let output = p
+        .cargo("test -j2 -Zparallel-tests")
+        .masquerade_as_nightly_cargo()
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] test [unoptimized + debuginfo] target(s) in [..]
+[RUNNING] [..] (target/debug/deps/foo-[..][EXE])
+[RUNNING] [..] (target/debug/deps/a-[..][EXE])
+[RUNNING] [..] (target/debug/deps/b-[..][EXE])
+     Summary 3 of 3 test binaries ran, 0 failed
+[DOCTEST] foo
+",
+        )
+        .exec_with_output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lib = stdout.find("test lib_test ... ok").unwrap();
+    let a = stdout.find("test a_test ... ok").unwrap();
+    let b = stdout.find("test b_test ... ok").unwrap();
+    assert!(lib < a && a < b, "unexpected order:\n{}", stdout);
+}
+
+#[cargo_test]
+fn parallel_tests_fail_fast() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("tests/a.rs", "#[test] fn a_test() { panic!() }")
+        .file("tests/b.rs", "#[test] fn b_test() {}")
+        .file("tests/c.rs", "#[test] fn c_test() {}")
+        .build();
+
+    // With a single job, the binaries after the failing one are not run.
+    p.cargo("test -j1 -Zparallel-tests")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] test [unoptimized + debuginfo] target(s) in [..]
+[RUNNING] [..] (target/debug/deps/foo-[..][EXE])
+[RUNNING] [..] (target/debug/deps/a-[..][EXE])
+     Summary 2 of 4 test binaries ran, 1 failed
+      Failed tests/a.rs (target/debug/deps/a-[..][EXE])
+[ERROR] test failed, to rerun pass '--test a'
+",
+        )
+        .with_stdout_contains("test a_test ... FAILED")
+        .run();
+
+    p.cargo("test -j2 -Zparallel-tests --no-fail-fast")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("     Summary 4 of 4 test binaries ran, 1 failed")
+        .with_stderr_contains("      Failed tests/a.rs (target/debug/deps/a-[..][EXE])")
+        .with_stdout_contains("test c_test ... ok")
+        .run();
+}
+
+#[cargo_test]
+fn test_report_requires_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test --report-json report.json")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] Usage of `--report-junit` and `--report-json` requires `-Z test-report`",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn test_report() {
+    if !is_nightly() {
+        // libtest's JSON output is unstable
+        return;
+    }
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [[test]]
+                name = "custom"
+                harness = false
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                /// ```
+                /// assert_eq!(foo::one(), 1);
+                /// ```
+                pub fn one() -> i32 { 1 }
+
+                #[test]
+                fn pass() {}
+
+                #[test]
+                fn fail() {
+                    println!("some output");
+                    panic!("<oops>");
+                }
+
+                #[test]
+                #[ignore]
+                fn skip() {}
+            "#,
+        )
+        .file("tests/custom.rs", "fn main() {}")
+        .build();
+
+    p.cargo(
+        "test -Ztest-report --no-fail-fast --report-json report.json --report-junit report.xml",
+    )
+    .masquerade_as_nightly_cargo()
+    .with_status(101)
+    .with_stdout_contains("test pass ... ok")
+    .with_stdout_contains("test fail ... FAILED")
+    .with_stdout_contains("test skip ... ignored")
+    .with_stdout_contains("---- fail stdout ----")
+    .with_stdout_contains(
+        "test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out",
+    )
+    .with_stdout_contains("test src/lib.rs - one (line 2) ... ok")
+    .run();
+
+    let report: serde_json::Value = serde_json::from_str(&p.read_file("report.json")).unwrap();
+    assert_eq!(report["version"], 1);
+    let tests = report["tests"].as_array().unwrap();
+    let find = |kind: &str, name: &str| {
+        tests
+            .iter()
+            .find(|t| t["kind"] == kind && t["name"] == name)
+            .unwrap_or_else(|| panic!("no {} test `{}` in {:#?}", kind, name, tests))
+    };
+    let fail = find("lib", "fail");
+    assert_eq!(fail["package"], "foo");
+    assert_eq!(fail["target"], "foo");
+    assert_eq!(fail["outcome"], "failed");
+    assert!(fail["duration"].is_number());
+    assert!(fail["output"].as_str().unwrap().contains("some output"));
+    assert_eq!(find("lib", "pass")["outcome"], "passed");
+    assert_eq!(find("lib", "pass")["output"], serde_json::Value::Null);
+    assert_eq!(find("lib", "skip")["outcome"], "ignored");
+    assert_eq!(find("integration-test", "custom")["outcome"], "passed");
+    assert_eq!(
+        find("doctest", "src/lib.rs - one (line 2)")["outcome"],
+        "passed"
+    );
+
+    let junit = p.read_file("report.xml");
+    assert!(junit.starts_with("<?xml"));
+    assert!(junit.contains("<testsuites tests=\"5\" failures=\"1\" skipped=\"1\">"));
+    assert!(junit.contains("<testsuite name=\"foo::lib::foo\" tests=\"3\" failures=\"1\""));
+    assert!(junit.contains("<testcase classname=\"foo::lib::foo\" name=\"fail\""));
+    assert!(junit.contains("&lt;oops&gt;"));
+}
+
+#[cargo_test]
+fn test_shard_requires_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test --shard 1/2")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--shard` requires `-Z test-shard`")
+        .run();
+}
+
+#[cargo_test]
+fn test_shard_invalid() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test -Ztest-shard --shard 3/2")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] invalid shard `3/2`, expected `N/M` with N between 1 and M")
+        .run();
+}
+
+fn sharded_project() -> Project {
+    project()
+        .file("src/lib.rs", "")
+        .file("tests/a.rs", "")
+        .file("tests/b.rs", "")
+        .file("tests/c.rs", "")
+        .file("tests/d.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn test_shard() {
+    let p = sharded_project();
+
+    p.cargo("test -Ztest-shard --shard 1/2")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]Shard 1/2, running 3 of 6 test suites")
+        .with_stderr_contains("[RUNNING] [..] (target/debug/deps/a-[..][EXE])")
+        .with_stderr_contains("[RUNNING] [..] (target/debug/deps/c-[..][EXE])")
+        .with_stderr_contains("[DOCTEST] foo")
+        .with_stderr_does_not_contain("[RUNNING] [..] (target/debug/deps/b-[..][EXE])")
+        .with_stderr_does_not_contain("[RUNNING] [..] (target/debug/deps/d-[..][EXE])")
+        .with_stderr_does_not_contain("[RUNNING] unittests [..]")
+        .run();
+
+    p.cargo("test -Ztest-shard --shard 2/2")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]Shard 2/2, running 3 of 6 test suites")
+        .with_stderr_contains("[RUNNING] [..] (target/debug/deps/b-[..][EXE])")
+        .with_stderr_contains("[RUNNING] [..] (target/debug/deps/d-[..][EXE])")
+        .with_stderr_contains("[RUNNING] unittests [..]")
+        .with_stderr_does_not_contain("[RUNNING] [..] (target/debug/deps/a-[..][EXE])")
+        .with_stderr_does_not_contain("[RUNNING] [..] (target/debug/deps/c-[..][EXE])")
+        .with_stderr_does_not_contain("[DOCTEST] foo")
+        .run();
+}
+
+#[cargo_test]
+fn test_shard_durations() {
+    let p = sharded_project();
+    let test = |target: &str, kind: &str, duration: f64| {
+        serde_json::json!({
+            "package": "foo",
+            "target": target,
+            "kind": kind,
+            "name": "t",
+            "outcome": "passed",
+            "duration": duration,
+        })
+    };
+    let report = serde_json::json!({
+        "version": 1,
+        "tests": [
+            test("a", "integration-test", 4.0),
+            test("a", "integration-test", 6.0),
+            test("b", "integration-test", 1.0),
+            test("c", "integration-test", 1.0),
+            test("foo", "lib", 1.0),
+            test("foo", "doctest", 1.0),
+        ],
+    });
+    p.change_file("durations.json", &report.to_string());
+
+    // `d` is missing from the report, and takes the average time, 2.8s.
+    p.cargo("test -Ztest-shard --shard 1/2 --shard-durations durations.json")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[..]Shard 1/2, running 1 of 6 test suites")
+        .with_stderr_contains("[RUNNING] [..] (target/debug/deps/a-[..][EXE])")
+        .with_stderr_does_not_contain("[RUNNING] [..] (target/debug/deps/d-[..][EXE])")
+        .with_stderr_does_not_contain("[DOCTEST] foo")
+        .run();
+
+    p.change_file("durations.json", r#"{"version": 2, "tests": []}"#);
+    p.cargo("test -Ztest-shard --shard 1/2 --shard-durations durations.json")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to read test durations from `[..]durations.json`")
+        .with_stderr_contains("[..]unsupported report version `2`")
+        .run();
+}
+
+#[cargo_test]
+fn coverage_requires_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test --coverage")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--coverage` requires `-Z coverage`")
+        .run();
+
+    p.cargo("test -Zcoverage --coverage --coverage-format html")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] invalid coverage format `html`, expected `lcov` or `json`")
+        .run();
+}
+
+#[cargo_test]
+fn coverage_requires_supported_rustc() {
+    // A rustc reporting the version of stable Rust 1.55, which has no
+    // `-C instrument-coverage`.
+    let compiler = project()
+        .at("compiler")
+        .file("Cargo.toml", &basic_manifest("compiler", "0.1.0"))
+        .file(
+            "src/main.rs",
+            r#"
+                use std::env;
+                use std::process::Command;
+
+                fn main() {
+                    let args: Vec<String> = env::args().skip(1).collect();
+                    if args == ["-vV"] {
+                        let output = Command::new("rustc").arg("-vV").output().unwrap();
+                        for line in String::from_utf8(output.stdout).unwrap().lines() {
+                            if line.starts_with("release: ") {
+                                println!("release: 1.55.0");
+                            } else {
+                                println!("{}", line);
+                            }
+                        }
+                        return;
+                    }
+                    let status = Command::new("rustc").args(&args).status().unwrap();
+                    std::process::exit(status.code().unwrap());
+                }
+            "#,
+        )
+        .build();
+    compiler.cargo("build").run();
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("test -Zcoverage --coverage")
+        .masquerade_as_nightly_cargo()
+        .env("RUSTC", compiler.bin("compiler"))
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] `--coverage` requires rustc 1.60 or newer, or a nightly rustc, \
+             but the current rustc is 1.55.0",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn coverage() {
+    if !is_nightly() {
+        // -C instrument-coverage needs the profiler runtime of nightly builds
+        return;
+    }
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn bar() -> u32 { 1 }")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                pub fn covered() -> u32 { bar::bar() }
+                pub fn uncovered() -> u32 { 2 }
+
+                #[test]
+                fn test_covered() {
+                    assert_eq!(covered(), 1);
+                }
+            "#,
+        )
+        .build();
+
+    p.cargo("test -Zcoverage --coverage -v")
+        .masquerade_as_nightly_cargo()
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C instrument-coverage[..]")
+        .with_stderr_does_not_contain(
+            "[RUNNING] `rustc --crate-name bar [..]instrument-coverage[..]",
+        )
+        .with_stdout_contains("test test_covered ... ok")
+        .run();
+
+    // The instrumented artifacts don't replace the usual ones.
+    assert!(p.build_dir().join("coverage/debug/deps").is_dir());
+    assert!(!p.build_dir().join("debug").exists());
+    let profiles = fs::read_dir(p.build_dir().join("coverage/profraw"))
+        .unwrap()
+        .count();
+    assert!(profiles > 0);
+
+    // The report needs the LLVM tools, which may not be installed.
+    let lcov = p.build_dir().join("coverage/lcov.info");
+    if lcov.exists() {
+        let lcov = fs::read_to_string(lcov).unwrap();
+        assert!(lcov.contains("src/lib.rs"), "{}", lcov);
+        assert!(!lcov.contains("bar-1.0.0"), "{}", lcov);
+    }
+}
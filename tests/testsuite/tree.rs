@@ -1798,3 +1798,198 @@ foo v0.1.0 ([..]/foo)
         .with_status(101)
         .run();
 }
+
+#[cargo_test]
+fn output_format_requires_z_flag() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format dot")
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--output-format` requires `-Z tree-output-format`")
+        .run();
+}
+
+#[cargo_test]
+fn output_format_dot() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format dot -Ztree-output-format")
+        .masquerade_as_nightly_cargo()
+        .with_stdout(
+            "\
+digraph {
+    0 [label=\"foo v0.1.0 ([..]/foo)\", shape=box, style=bold];
+    1 [label=\"a v1.0.0\"];
+    2 [label=\"c v1.0.0\"];
+    3 [label=\"bdep v1.0.0\"];
+    4 [label=\"devdep v1.0.0\"];
+    5 [label=\"b v1.0.0\"];
+    0 -> 1;
+    0 -> 2;
+    0 -> 3 [label=\"build\"];
+    0 -> 4 [label=\"dev\", style=dashed];
+    1 -> 5;
+    3 -> 5;
+    4 -> 5;
+    5 -> 2;
+}
+",
+        )
+        .run();
+
+    p.cargo("tree --output-format dot -Ztree-output-format -e normal --depth 1")
+        .masquerade_as_nightly_cargo()
+        .with_stdout(
+            "\
+digraph {
+    0 [label=\"foo v0.1.0 ([..]/foo)\", shape=box, style=bold];
+    1 [label=\"a v1.0.0\"];
+    2 [label=\"c v1.0.0\"];
+    0 -> 1;
+    0 -> 2;
+}
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn output_format_json() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format json -Ztree-output-format")
+        .masquerade_as_nightly_cargo()
+        .with_json(
+            r#"
+{
+  "version": 1,
+  "roots": [0],
+  "nodes": [
+    {
+      "type": "package",
+      "id": "foo 0.1.0 (path+file:///[..]/foo)",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [
+        { "index": 1, "kind": "normal" },
+        { "index": 2, "kind": "normal" },
+        { "index": 3, "kind": "build" },
+        { "index": 4, "kind": "dev" }
+      ]
+    },
+    {
+      "type": "package",
+      "id": "a 1.0.0 (registry+[..])",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [{ "index": 5, "kind": "normal" }]
+    },
+    {
+      "type": "package",
+      "id": "c 1.0.0 (registry+[..])",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": []
+    },
+    {
+      "type": "package",
+      "id": "bdep 1.0.0 (registry+[..])",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [{ "index": 5, "kind": "normal" }]
+    },
+    {
+      "type": "package",
+      "id": "devdep 1.0.0 (registry+[..])",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [{ "index": 5, "kind": "normal" }]
+    },
+    {
+      "type": "package",
+      "id": "b 1.0.0 (registry+[..])",
+      "features": [],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [{ "index": 2, "kind": "normal" }]
+    }
+  ]
+}
+"#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn output_format_json_features() {
+    Package::new("optdep", "1.0.0").feature("f1", &[]).publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            optdep = { version = "1.0", optional = true, features = ["f1"] }
+
+            [features]
+            default = ["optdep"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree --output-format json -Ztree-output-format -e features")
+        .masquerade_as_nightly_cargo()
+        .with_json(
+            r#"
+{
+  "version": 1,
+  "roots": [0],
+  "nodes": [
+    {
+      "type": "package",
+      "id": "foo 0.1.0 (path+file:///[..]/foo)",
+      "features": ["default", "optdep"],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": [
+        { "index": 1, "kind": "normal" },
+        { "index": 2, "kind": "normal" }
+      ]
+    },
+    {
+      "type": "feature",
+      "package_id": "optdep 1.0.0 (registry+[..])",
+      "name": "default",
+      "command_line": false,
+      "dependencies": [{ "index": 3, "kind": "feature" }]
+    },
+    {
+      "type": "feature",
+      "package_id": "optdep 1.0.0 (registry+[..])",
+      "name": "f1",
+      "command_line": false,
+      "dependencies": [{ "index": 3, "kind": "feature" }]
+    },
+    {
+      "type": "package",
+      "id": "optdep 1.0.0 (registry+[..])",
+      "features": ["f1"],
+      "platform": null,
+      "proc_macro": false,
+      "dependencies": []
+    }
+  ]
+}
+"#,
+        )
+        .run();
+}
@@ -359,3 +359,90 @@ foo v0.1.0 ([..]/foo)
         )
         .run();
 }
+
+#[cargo_test]
+fn why_feature() {
+    // Explains why a feature is enabled on a dependency.
+    Package::new("serde_derive", "1.0.0").publish();
+    Package::new("serde", "1.0.0")
+        .feature("derive", &["serde_derive"])
+        .add_dep(Dependency::new("serde_derive", "1.0").optional(true))
+        .publish();
+    Package::new("nameddep", "1.0.0")
+        .add_dep(Dependency::new("serde", "1.0").optional(true))
+        .feature("default", &["serde-stuff"])
+        .feature("serde-stuff", &["serde/derive"])
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            nameddep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree --why serde/derive -Ztree-why")
+        .masquerade_as_nightly_cargo()
+        .with_stdout(
+            "\
+foo v0.1.0 ([..]/foo)
+  -> nameddep feature \"default\" (requested by the normal dependency of `foo`)
+  -> nameddep feature \"serde-stuff\" (enabled by feature \"default\")
+  -> serde feature \"derive\" (enabled by feature \"serde-stuff\" of `nameddep`)
+",
+        )
+        .run();
+
+    p.cargo("tree --why serde/std -Ztree-why")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] feature `std` is not enabled on any package matching `serde`")
+        .run();
+
+    p.cargo("tree --why serde/derive")
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--why` requires `-Z tree-why`")
+        .run();
+}
+
+#[cargo_test]
+fn why_cli_feature() {
+    // A feature enabled on the command-line is shown as its own root.
+    Package::new("dep", "1.0.0").feature("f1", &[]).publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            dep = { version = "1.0", default-features = false }
+
+            [features]
+            dep-f1 = ["dep/f1"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree --why dep/f1 --features dep-f1 -Ztree-why")
+        .masquerade_as_nightly_cargo()
+        .with_stdout(
+            "\
+foo feature \"dep-f1\" (command-line)
+  -> dep feature \"f1\" (enabled by feature \"dep-f1\" of `foo`)
+",
+        )
+        .run();
+}
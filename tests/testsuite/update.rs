@@ -1,7 +1,7 @@
 //! Tests for the `cargo update` command.
 
 use cargo_test_support::registry::Package;
-use cargo_test_support::{basic_manifest, project};
+use cargo_test_support::{basic_manifest, git, project};
 
 #[cargo_test]
 fn minor_update_two_places() {
@@ -678,3 +678,252 @@ fn workspace_only() {
     assert!(!lock1.contains("0.0.2"));
     assert!(!lock2.contains("0.0.1"));
 }
+
+#[cargo_test]
+fn output_format_requires_z_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("update --output-format json")
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--output-format` requires `-Z update-report`")
+        .run();
+}
+
+#[cargo_test]
+fn dry_run_json_report() {
+    Package::new("log", "0.1.0").publish();
+    Package::new("old", "0.1.0").publish();
+    Package::new("rand", "0.1.0").publish();
+    Package::new("serde", "0.1.0")
+        .dep("log", "0.1")
+        .dep("old", "0.1")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                serde = "0.1"
+                log = "0.1"
+                rand = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    let old_lockfile = p.read_lockfile();
+
+    // Only the changed packages are checked for newer versions.
+    Package::new("rand", "0.2.0").publish();
+    Package::new("itoa", "0.1.0").publish();
+    Package::new("log", "0.1.1").publish();
+    Package::new("log", "0.2.0").publish();
+    Package::new("serde", "0.1.1")
+        .dep("log", "0.1")
+        .dep("itoa", "0.1")
+        .publish();
+
+    p.cargo("update --dry-run --output-format json -Zupdate-report")
+        .masquerade_as_nightly_cargo()
+        .with_json(
+            r#"
+            {
+              "version": 1,
+              "dry_run": true,
+              "added": [
+                {
+                  "name": "itoa",
+                  "version": "0.1.0",
+                  "source": "registry+https://github.com/rust-lang/crates.io-index"
+                }
+              ],
+              "removed": [
+                {
+                  "name": "old",
+                  "version": "0.1.0",
+                  "source": "registry+https://github.com/rust-lang/crates.io-index"
+                }
+              ],
+              "updated": [
+                {
+                  "name": "log",
+                  "from": {
+                    "version": "0.1.0",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index"
+                  },
+                  "to": {
+                    "version": "0.1.1",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index"
+                  }
+                },
+                {
+                  "name": "serde",
+                  "from": {
+                    "version": "0.1.0",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index"
+                  },
+                  "to": {
+                    "version": "0.1.1",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index"
+                  }
+                }
+              ],
+              "held_back": [
+                {
+                  "name": "log",
+                  "version": "0.1.1",
+                  "source": "registry+https://github.com/rust-lang/crates.io-index",
+                  "latest": "0.2.0"
+                }
+              ]
+            }
+            "#,
+        )
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[ADDING] itoa v0.1.0
+[UPDATING] log v0.1.0 -> v0.1.1
+[REMOVING] old v0.1.0
+[UPDATING] serde v0.1.0 -> v0.1.1
+[WARNING] not updating lockfile due to dry run
+",
+        )
+        .run();
+    assert_eq!(old_lockfile, p.read_lockfile());
+}
+
+#[cargo_test]
+fn json_report_source_change() {
+    let git_project = git::new("bar", |project| {
+        project
+            .file("Cargo.toml", &basic_manifest("bar", "0.1.0"))
+            .file("src/lib.rs", "")
+    });
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+
+                    [dependencies]
+                    bar = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "0.1"
+        "#,
+    );
+    p.cargo("update --output-format json -Zupdate-report")
+        .masquerade_as_nightly_cargo()
+        .with_json(
+            r#"
+            {
+              "version": 1,
+              "dry_run": false,
+              "added": [],
+              "removed": [],
+              "updated": [
+                {
+                  "name": "bar",
+                  "from": {
+                    "version": "0.1.0",
+                    "source": "git+[..]/bar#[..]"
+                  },
+                  "to": {
+                    "version": "0.1.0",
+                    "source": "registry+https://github.com/rust-lang/crates.io-index"
+                  }
+                }
+              ],
+              "held_back": []
+            }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn json_report_git_revision_change() {
+    let (git_project, repo) = git::new_repo("bar", |project| {
+        project
+            .file("Cargo.toml", &basic_manifest("bar", "0.1.0"))
+            .file("src/lib.rs", "")
+    });
+    let rev1 = repo.revparse_single("HEAD").unwrap().id();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+
+                    [dependencies]
+                    bar = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    git_project.change_file("src/lib.rs", "pub fn bar() {}");
+    git::add(&repo);
+    let rev2 = git::commit(&repo);
+
+    p.cargo("update --output-format json -Zupdate-report")
+        .masquerade_as_nightly_cargo()
+        .with_json(&format!(
+            r#"
+            {{
+              "version": 1,
+              "dry_run": false,
+              "added": [],
+              "removed": [],
+              "updated": [
+                {{
+                  "name": "bar",
+                  "from": {{
+                    "version": "0.1.0",
+                    "source": "git+[..]/bar#{}"
+                  }},
+                  "to": {{
+                    "version": "0.1.0",
+                    "source": "git+[..]/bar#{}"
+                  }}
+                }}
+              ],
+              "held_back": []
+            }}
+            "#,
+            rev1, rev2
+        ))
+        .run();
+}
@@ -0,0 +1,134 @@
+//! Tests for rerunning commands when the sources change with `--watch`.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use cargo_test_support::{project, sleep_ms, Project};
+
+fn watched_project() -> Project {
+    project()
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file(
+            "build.rs",
+            "fn main() { println!(\"cargo:rerun-if-changed=data.txt\"); }",
+        )
+        .file("data.txt", "1")
+        .file("README.md", "")
+        .build()
+}
+
+/// Spawns cargo, sending the lines of its stderr to the returned receiver.
+fn spawn(p: &Project, cmd: &str) -> (Child, Receiver<String>) {
+    let mut cargo = p.cargo(cmd).masquerade_as_nightly_cargo().build_command();
+    cargo.stdout(Stdio::null()).stderr(Stdio::piped());
+    let mut child = cargo.spawn().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    (child, rx)
+}
+
+/// Waits for a line of stderr containing `needle`, returning the lines
+/// before it.
+fn wait_for(rx: &Receiver<String>, needle: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+        let line = rx
+            .recv_timeout(Duration::from_secs(60))
+            .unwrap_or_else(|_| panic!("`{}` not found in:\n{}", needle, lines.join("\n")));
+        if line.contains(needle) {
+            return lines;
+        }
+        lines.push(line);
+    }
+}
+
+#[cargo_test]
+fn requires_flag() {
+    let p = watched_project();
+
+    p.cargo("check --watch")
+        .masquerade_as_nightly_cargo()
+        .with_status(101)
+        .with_stderr("[ERROR] Usage of `--watch` requires `-Z watch`")
+        .run();
+}
+
+#[cargo_test]
+fn reruns_on_changes() {
+    let p = watched_project();
+    let (mut child, rx) = spawn(&p, "check -Zwatch --watch");
+
+    wait_for(&rx, "Watching");
+    // Leaves time for the mtime to differ on filesystems with a coarse one.
+    sleep_ms(1000);
+    p.change_file("src/lib.rs", "pub fn bar() {}");
+    let lines = wait_for(&rx, "Watching");
+    assert!(lines.iter().any(|line| line.contains("Checking foo")));
+
+    sleep_ms(1000);
+    // Only the build script is run again, as its output stays the same.
+    p.change_file("data.txt", "2");
+    let lines = wait_for(&rx, "Watching");
+    assert!(lines.iter().any(|line| line.contains("Compiling foo")));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cargo_test]
+fn watches_sources_after_errors() {
+    let p = watched_project();
+    p.change_file("src/lib.rs", "pub fn foo() {");
+    let (mut child, rx) = spawn(&p, "check -Zwatch --watch");
+
+    let lines = wait_for(&rx, "Watching");
+    assert!(lines.iter().any(|line| line.contains("error")));
+    sleep_ms(1000);
+    p.change_file("src/lib.rs", "pub fn foo() {}");
+    let lines = wait_for(&rx, "Watching");
+    assert!(lines.iter().any(|line| line.contains("Finished")));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[cargo_test]
+fn restarts_run() {
+    let p = project()
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    eprintln!("started 1");
+                    std::thread::sleep(std::time::Duration::from_secs(600));
+                }
+            "#,
+        )
+        .build();
+    let (mut child, rx) = spawn(&p, "run -Zwatch --watch");
+
+    wait_for(&rx, "started 1");
+    sleep_ms(1000);
+    p.change_file(
+        "src/main.rs",
+        r#"
+            fn main() {
+                eprintln!("started 2");
+            }
+        "#,
+    );
+    wait_for(&rx, "started 2");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}